@@ -0,0 +1,166 @@
+//! Backlight control for boards that switch or dim the backlight LED with an
+//! MCU pin. The driver itself doesn't own a backlight pin, so these wrappers
+//! sit next to it. The Pico-LCD 1.14" wires the backlight to a GPIO (GP13),
+//! not to the controller's brightness output, so the controller's WRDISBV
+//! brightness register has no effect there.
+use core::convert::Infallible;
+use embedded_hal::blocking::delay::DelayUs;
+use embedded_hal::digital::v2::OutputPin;
+use embedded_hal::PwmPin;
+
+///
+/// A backlight with a brightness from 0 (off) to 255 (full brightness).
+///
+pub trait Backlight {
+    type Error;
+
+    /// Sets the brightness; level 0 switches the backlight off
+    fn set_backlight(&mut self, level: u8) -> Result<(), Self::Error>;
+
+    ///
+    /// Blinks the backlight `times` times for notifications: full brightness
+    /// for `on_us`, then off for `off_us`. The backlight is left off, set the
+    /// brightness again afterwards to restore it.
+    ///
+    /// # Arguments
+    ///
+    /// * `times` - number of pulses
+    /// * `on_us` - time the backlight is on per pulse, in microseconds
+    /// * `off_us` - time the backlight is off after each pulse, in microseconds
+    /// * `delay_source` - mutable reference to a delay provider
+    ///
+    fn pulse_backlight(
+        &mut self,
+        times: u8,
+        on_us: u32,
+        off_us: u32,
+        delay_source: &mut impl DelayUs<u32>,
+    ) -> Result<(), Self::Error> {
+        for _ in 0..times {
+            self.set_backlight(u8::MAX)?;
+            delay_source.delay_us(on_us);
+            self.set_backlight(0)?;
+            delay_source.delay_us(off_us);
+        }
+        Ok(())
+    }
+}
+
+///
+/// A backlight switched on and off by a GPIO pin, driven high for on. Any
+/// level above 0 turns it fully on.
+///
+#[derive(Debug)]
+pub struct GpioBacklight<P>(pub P);
+
+impl<P: OutputPin> Backlight for GpioBacklight<P> {
+    type Error = P::Error;
+
+    fn set_backlight(&mut self, level: u8) -> Result<(), Self::Error> {
+        if level > 0 {
+            self.0.set_high()
+        } else {
+            self.0.set_low()
+        }
+    }
+}
+
+///
+/// A backlight dimmed by a PWM channel, where the duty cycle is the share of
+/// time the backlight is on. The level is scaled linearly to the channel's
+/// maximum duty; level 0 disables the channel.
+///
+#[derive(Debug)]
+pub struct PwmBacklight<P>(pub P);
+
+impl<P: PwmPin<Duty = u16>> Backlight for PwmBacklight<P> {
+    type Error = Infallible;
+
+    fn set_backlight(&mut self, level: u8) -> Result<(), Self::Error> {
+        if level == 0 {
+            self.0.disable();
+            return Ok(());
+        }
+        let duty = u32::from(self.0.get_max_duty()) * u32::from(level) / 255;
+        self.0.set_duty(duty as u16);
+        self.0.enable();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::{MockDelay, MockPin};
+    use std::vec::Vec;
+
+    // A PWM channel recording every duty set
+    #[derive(Default)]
+    struct MockPwm {
+        duties: Vec<u16>,
+        enabled: bool,
+    }
+
+    impl PwmPin for MockPwm {
+        type Duty = u16;
+
+        fn disable(&mut self) {
+            self.enabled = false;
+        }
+
+        fn enable(&mut self) {
+            self.enabled = true;
+        }
+
+        fn get_duty(&self) -> u16 {
+            self.duties.last().copied().unwrap_or(0)
+        }
+
+        fn get_max_duty(&self) -> u16 {
+            1000
+        }
+
+        fn set_duty(&mut self, duty: u16) {
+            self.duties.push(duty);
+        }
+    }
+
+    #[test]
+    fn pwm_backlight_scales_the_duty() {
+        let mut backlight = PwmBacklight(MockPwm::default());
+        backlight.set_backlight(255).unwrap();
+        assert!(backlight.0.enabled);
+        backlight.set_backlight(128).unwrap();
+        backlight.set_backlight(1).unwrap();
+        assert_eq!(backlight.0.duties, [1000, 501, 3]);
+        backlight.set_backlight(0).unwrap();
+        assert!(!backlight.0.enabled);
+        assert_eq!(backlight.0.duties.len(), 3);
+    }
+
+    #[test]
+    fn gpio_backlight_is_on_for_any_level() {
+        let mut backlight = GpioBacklight(MockPin::default());
+        for level in [255, 1, 0] {
+            backlight.set_backlight(level).unwrap();
+        }
+        assert_eq!(backlight.0.levels, [true, true, false]);
+    }
+
+    #[test]
+    fn pulses_toggle_the_backlight_with_the_given_timing() {
+        let mut backlight = GpioBacklight(MockPin::default());
+        let mut delay = MockDelay::default();
+        backlight.pulse_backlight(3, 200, 50, &mut delay).unwrap();
+        assert_eq!(backlight.0.levels, [true, false, true, false, true, false]);
+        assert_eq!(delay.delays, [200, 50, 200, 50, 200, 50]);
+
+        let mut pwm = PwmBacklight(MockPwm::default());
+        pwm.pulse_backlight(1, 10, 20, &mut delay).unwrap();
+        assert_eq!(pwm.0.duties, [1000]);
+        assert!(!pwm.0.enabled);
+
+        backlight.0.fail = true;
+        assert!(backlight.pulse_backlight(1, 10, 20, &mut delay).is_err());
+    }
+}
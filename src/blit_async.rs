@@ -0,0 +1,204 @@
+//! Pixel transfers handed to an async (DMA) data send, and an init that
+//! awaits its delays.
+use crate::{Error, ErrorStage, DISPON_DELAY_US, RESET_PULSE_US, RESET_SETTLE_US, ST7789};
+use core::future::Future;
+use display_interface::WriteOnlyDataCommand;
+use embedded_hal::digital::v2::OutputPin;
+
+impl<DI, RST, PinE> ST7789<DI, RST>
+where
+    DI: WriteOnlyDataCommand,
+    RST: OutputPin<Error = PinE>,
+{
+    ///
+    /// Same as `blit_pixels`, but the pixel data is sent by the given async
+    /// transfer, e.g. an SPI DMA write on an async executor, so the caller
+    /// yields while the frame is on the bus. The address window and RAMWR are
+    /// sent synchronously through the display interface first.
+    ///
+    /// `send` must put the bytes on the bus as display data, i.e. with DC
+    /// high and chip select asserted, as the display interface would. The
+    /// whole buffer goes out as a single transfer: the retry count, the
+    /// maximum transfer size, chunk pacing and tracing don't apply.
+    ///
+    /// # Arguments
+    ///
+    /// * `sx` - x coordinate start
+    /// * `sy` - y coordinate start
+    /// * `dx` - width
+    /// * `dy` - height
+    /// * `data` - u8 slice containing raw pixel data
+    /// * `send` - starts the async transfer of the pixel data
+    ///
+    pub async fn blit_pixels_async<'d, F, Fut, E>(
+        &mut self,
+        sx: u16,
+        sy: u16,
+        dx: u16,
+        dy: u16,
+        data: &'d [u8],
+        send: F,
+    ) -> Result<(), Error<PinE>>
+    where
+        F: FnOnce(&'d [u8]) -> Fut,
+        Fut: Future<Output = Result<(), E>>,
+    {
+        let (ex, ey) = match self.blit_window(sx, sy, dx, dy, data.len())? {
+            Some(end) => end,
+            None => return Ok(()), // empty rectangle
+        };
+        self.start_blit(sx, sy, ex, ey)?;
+        #[cfg(feature = "shadow")]
+        if let Some(shadow) = &mut self.shadow {
+            shadow.push_bytes(data, self.pixel_format, self.little_endian);
+        }
+        let result = send(data).await.map_err(|_| Error::DisplayError);
+        self.note_failure(result, ErrorStage::Pixels)
+    }
+
+    ///
+    /// Same as `init`, but the delays are awaited through `delay_us`, e.g. an
+    /// async timer of the executor, so the power-up sequence doesn't block.
+    /// The commands and the reset pin changes still go out synchronously: the
+    /// display interface and the reset pin only have blocking traits, and a
+    /// pin change or a few command bytes take no noticeable time. Switch on
+    /// the backlight with its own (async) pin after this returns.
+    ///
+    /// # Arguments
+    ///
+    /// * `delay_us` - waits for the given number of microseconds
+    ///
+    pub async fn init_async<F, Fut>(&mut self, mut delay_us: F) -> Result<(), Error<PinE>>
+    where
+        F: FnMut(u32) -> Fut,
+        Fut: Future<Output = ()>,
+    {
+        self.hard_reset_async(&mut delay_us).await?;
+        self.init_commands()?;
+        delay_us(DISPON_DELAY_US).await;
+        Ok(())
+    }
+
+    ///
+    /// Same as `hard_reset`, but the delays are awaited through `delay_us`.
+    ///
+    /// # Arguments
+    ///
+    /// * `delay_us` - waits for the given number of microseconds
+    ///
+    pub async fn hard_reset_async<F, Fut>(&mut self, mut delay_us: F) -> Result<(), Error<PinE>>
+    where
+        F: FnMut(u32) -> Fut,
+        Fut: Future<Output = ()>,
+    {
+        self.set_reset(false)?;
+        delay_us(RESET_PULSE_US).await;
+        self.set_reset(true)?;
+        delay_us(RESET_PULSE_US).await;
+        self.set_reset(false)?;
+        delay_us(RESET_SETTLE_US).await;
+        self.reset_state();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::instruction::Instruction::*;
+    use crate::mock::{display, initialized, MockDelay};
+    use core::future::Future;
+    use core::pin::Pin;
+    use core::task::{Context, Poll};
+    use std::cell::RefCell;
+    use std::sync::Arc;
+    use std::task::{Wake, Waker};
+    use std::vec::Vec;
+
+    struct NoWake;
+
+    impl Wake for NoWake {
+        fn wake(self: Arc<Self>) {}
+    }
+
+    // Polls a future to completion on the current thread.
+    fn block_on<F: Future>(future: F) -> F::Output {
+        let waker = Waker::from(Arc::new(NoWake));
+        let mut context = Context::from_waker(&waker);
+        let mut future = std::boxed::Box::pin(future);
+        loop {
+            if let Poll::Ready(output) = future.as_mut().poll(&mut context) {
+                return output;
+            }
+        }
+    }
+
+    // A DMA transfer that is still running when first polled
+    struct Transfer<'a> {
+        data: &'a [u8],
+        sent: &'a RefCell<Vec<u8>>,
+        polled: bool,
+    }
+
+    impl Future for Transfer<'_> {
+        type Output = Result<(), ()>;
+
+        fn poll(mut self: Pin<&mut Self>, context: &mut Context<'_>) -> Poll<Result<(), ()>> {
+            if !self.polled {
+                self.polled = true;
+                context.waker().wake_by_ref();
+                return Poll::Pending;
+            }
+            self.sent.borrow_mut().extend_from_slice(self.data);
+            Poll::Ready(Ok(()))
+        }
+    }
+
+    #[test]
+    fn async_blit_sets_the_window_and_awaits_the_data() {
+        let mut display = initialized();
+        let sent = RefCell::new(Vec::new());
+        let data = [0x12, 0x34, 0x56, 0x78];
+        let send = |data| Transfer { data, sent: &sent, polled: false };
+        block_on(display.blit_pixels_async(1, 2, 2, 1, &data, send)).unwrap();
+        assert_eq!(display.di.commands(), [CASET as u8, RASET as u8, RAMWR as u8]);
+        assert_eq!(display.di.window(), (41, 55, 42, 55));
+        assert_eq!(display.di.pixel_data(), []);
+        assert_eq!(*sent.borrow(), data);
+    }
+
+    // An async timer that isn't done when first polled, recording the
+    // durations that were awaited to the end
+    struct Sleep<'a> {
+        us: u32,
+        awaited: &'a RefCell<Vec<u32>>,
+        polled: bool,
+    }
+
+    impl Future for Sleep<'_> {
+        type Output = ();
+
+        fn poll(mut self: Pin<&mut Self>, context: &mut Context<'_>) -> Poll<()> {
+            if !self.polled {
+                self.polled = true;
+                context.waker().wake_by_ref();
+                return Poll::Pending;
+            }
+            self.awaited.borrow_mut().push(self.us);
+            Poll::Ready(())
+        }
+    }
+
+    #[test]
+    fn async_init_awaits_the_delays_of_init() {
+        let awaited = RefCell::new(Vec::new());
+        let mut display = display();
+        block_on(display.init_async(|us| Sleep { us, awaited: &awaited, polled: false })).unwrap();
+
+        let mut delay = MockDelay::default();
+        let mut blocking = crate::mock::display();
+        blocking.init(&mut delay).unwrap();
+        assert_eq!(*awaited.borrow(), delay.delays);
+        assert_eq!(display.di.log, blocking.di.log);
+        assert_eq!(display.rst.levels, blocking.rst.levels);
+    }
+}
@@ -0,0 +1,112 @@
+//! Bring-up aids for finding the color order and inversion of unknown panels
+//! and for checking the signal integrity of the bus.
+use crate::colors::{BLACK, BLUE, LIME, RED};
+use crate::{DisplayState, Error, ST7789};
+use display_interface::WriteOnlyDataCommand;
+use embedded_hal::blocking::delay::DelayUs;
+use embedded_hal::digital::v2::OutputPin;
+
+// Stress pattern for the data line: two pixels all off and all on for long
+// runs, then two alternating every bit for the highest toggle rate
+const SI_PATTERN: [u8; 8] = [0x00, 0x00, 0xff, 0xff, 0x55, 0x55, 0xaa, 0xaa];
+// Bytes per transfer of the pattern, a multiple of its length
+const SI_CHUNK: usize = 64;
+
+impl<DI, RST, PinE> ST7789<DI, RST>
+where
+    DI: WriteOnlyDataCommand,
+    RST: OutputPin<Error = PinE>,
+{
+    ///
+    /// Cycles through all combinations of color order and inversion so a
+    /// person can pick the one that looks right. For each combination a test
+    /// pattern is drawn: red, green and blue stripes, left to right, on a
+    /// black background. After waiting `pause_us`, `confirm` is asked whether
+    /// the pattern shows correctly, e.g. by polling a button. The first
+    /// confirmed combination is kept and returned; if none is confirmed the
+    /// previous settings are restored and `None` is returned.
+    ///
+    /// With the wrong color order red and blue are swapped; with the wrong
+    /// inversion the background is white and the stripes show cyan, magenta
+    /// and yellow. These settings only affect how the panel shows the RAM
+    /// content, so they can't be detected automatically by reading pixels
+    /// back, even with a readable interface.
+    ///
+    /// The test pattern overwrites the whole visible area.
+    ///
+    /// # Arguments
+    ///
+    /// * `delay_source` - mutable reference to a delay provider
+    /// * `pause_us` - time each combination is shown before asking
+    /// * `confirm` - returns whether the pattern looks right with the given settings
+    ///
+    pub fn calibrate(
+        &mut self,
+        delay_source: &mut impl DelayUs<u32>,
+        pause_us: u32,
+        mut confirm: impl FnMut(&DisplayState) -> bool,
+    ) -> Result<Option<DisplayState>, Error<PinE>> {
+        let previous = self.display_state();
+        for &color_order in &[previous.color_order, previous.color_order.swapped()] {
+            for &inverted in &[previous.inverted, !previous.inverted] {
+                self.set_color_order(color_order)?;
+                self.set_inversion(inverted)?;
+                self.draw_calibration_pattern()?;
+                delay_source.delay_us(pause_us);
+                let state = self.display_state();
+                if confirm(&state) {
+                    return Ok(Some(state));
+                }
+            }
+        }
+        self.restore_state(previous)?;
+        Ok(None)
+    }
+
+    // Red, green and blue stripes on black, each a quarter of the width.
+    fn draw_calibration_pattern(&mut self) -> Result<(), Error<PinE>> {
+        let (w, h) = (self.width(), self.height());
+        let stripe = w / 4;
+        self.fill_rect(0, 0, w, h, BLACK)?;
+        for (i, &color) in [RED, LIME, BLUE].iter().enumerate() {
+            self.fill_rect(stripe * i as u16, 0, stripe, h, color)?;
+        }
+        Ok(())
+    }
+
+    ///
+    /// Fills the visible area with a deterministic stress pattern for
+    /// checking the signal integrity of the bus, e.g. at a raised SPI clock.
+    /// Every row repeats the same 8 byte sequence `00 00 FF FF 55 55 AA AA`:
+    /// long runs of zeros and ones followed by data that toggles on every
+    /// clock. In 16bpp this gives fine vertical stripes of black, white and
+    /// two grays, so any bit error shows as a pixel or a column that breaks
+    /// the stripes. The data goes out through the normal blit path, in
+    /// transfers of 64 bytes.
+    ///
+    /// For a closer look, trigger a scope on chip select and check the clock
+    /// and data edges against the pattern during the `55 AA` part, where
+    /// ringing and poor termination are worst.
+    ///
+    pub fn draw_si_test_pattern(&mut self) -> Result<(), Error<PinE>> {
+        if self.size_x == 0 || self.size_y == 0 {
+            return Ok(());
+        }
+        let mut buf = [0u8; SI_CHUNK];
+        for (i, byte) in buf.iter_mut().enumerate() {
+            *byte = SI_PATTERN[i % SI_PATTERN.len()];
+        }
+        let row_len = self.data_len(self.size_x.into());
+        self.start_blit(0, 0, self.size_x - 1, self.size_y - 1)?;
+        for _ in 0..self.size_y {
+            // each row restarts the pattern, so the stripes line up
+            let mut left = row_len;
+            while left > 0 {
+                let n = left.min(SI_CHUNK);
+                self.write_pixel_bytes(&buf[..n])?;
+                left -= n;
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,212 @@
+//! Common Rgb565 color values, as accepted by `set_pixel` and friends.
+//!
+//! Names and values follow the 16 color web (VGA) palette, plus a few common extras.
+//!
+//! The values are plain Rgb565 numbers, independent of the byte order the
+//! controller is set up for: the driver sends u16 colors in the configured
+//! order itself (see `ST7789::set_little_endian_pixels`). Only raw pixel data
+//! built for `blit_pixels` needs the order applied, with `to_bytes` or
+//! `ST7789::color_bytes`.
+
+///
+/// Packs 8 bit per channel RGB values into a Rgb565 color, dropping the low bits.
+///
+/// # Arguments
+///
+/// * `r` - red channel
+/// * `g` - green channel
+/// * `b` - blue channel
+///
+pub const fn rgb565(r: u8, g: u8, b: u8) -> u16 {
+    ((r as u16 & 0xf8) << 8) | ((g as u16 & 0xfc) << 3) | (b as u16 >> 3)
+}
+
+///
+/// Bytes of a Rgb565 color in the order the controller receives them, for
+/// building raw pixel data: high byte first by default, low byte first when
+/// the controller is switched to little endian with RAMCTRL.
+///
+/// # Arguments
+///
+/// * `color` - the Rgb565 color
+/// * `little_endian` - whether the controller expects the low byte first
+///
+pub const fn to_bytes(color: u16, little_endian: bool) -> [u8; 2] {
+    if little_endian {
+        color.to_le_bytes()
+    } else {
+        color.to_be_bytes()
+    }
+}
+
+///
+/// Blends two Rgb565 colors, channel by channel: alpha 255 gives `fg`, 0 gives
+/// `bg` and values in between mix them linearly, rounded to the nearest step.
+///
+/// # Arguments
+///
+/// * `fg` - the color drawn on top
+/// * `bg` - the color underneath
+/// * `alpha` - opacity of `fg`
+///
+pub const fn blend565(fg: u16, bg: u16, alpha: u8) -> u16 {
+    blend_channel(fg, bg, alpha, 11, 0x1f)
+        | blend_channel(fg, bg, alpha, 5, 0x3f)
+        | blend_channel(fg, bg, alpha, 0, 0x1f)
+}
+
+// Blends the channel at `shift` with the given bit mask of two Rgb565 colors.
+const fn blend_channel(fg: u16, bg: u16, alpha: u8, shift: u16, mask: u16) -> u16 {
+    let a = alpha as u32;
+    let f = ((fg >> shift) & mask) as u32;
+    let b = ((bg >> shift) & mask) as u32;
+    (((f * a + b * (255 - a) + 127) / 255) as u16) << shift
+}
+
+///
+/// Scales the channels of a Rgb565 color for white balance: a gain of 255
+/// keeps a channel as it is, lower gains dim it proportionally, rounded to
+/// the nearest step. Gains only reduce channels, so the channels a panel
+/// overemphasizes are the ones to turn down.
+///
+/// # Arguments
+///
+/// * `color` - the Rgb565 color to correct
+/// * `gains` - red, green and blue gain
+///
+pub const fn white_balance(color: u16, gains: [u8; 3]) -> u16 {
+    blend_channel(color, 0, gains[0], 11, 0x1f)
+        | blend_channel(color, 0, gains[1], 5, 0x3f)
+        | blend_channel(color, 0, gains[2], 0, 0x1f)
+}
+
+///
+/// The color `color` is shown as in idle mode (see `ST7789::set_idle_mode`).
+/// Idle mode only uses the MSB of every channel, so each channel is either
+/// fully on or off and only the 8 colors `BLACK`, `RED`, `LIME`, `BLUE`,
+/// `YELLOW`, `FUCHSIA`, `AQUA` and `WHITE` remain. Channels at half
+/// brightness or above turn fully on, e.g. `GRAY` shows as `WHITE` and
+/// `MAROON` as `RED`, anything darker turns off.
+///
+/// # Arguments
+///
+/// * `color` - the Rgb565 color as drawn
+///
+pub const fn idle_color(color: u16) -> u16 {
+    let r = if color & 0x8000 != 0 { 0xf800 } else { 0 };
+    let g = if color & 0x0400 != 0 { 0x07e0 } else { 0 };
+    let b = if color & 0x0010 != 0 { 0x001f } else { 0 };
+    r | g | b
+}
+
+///
+/// Packs Rgb565 colors into 12 bit per pixel data for `PixelFormat::Rgb444`,
+/// two pixels in 3 bytes (R1G1 B1R2 G2B2), keeping the upper 4 bits of
+/// every channel. An odd number of colors ends with two bytes whose last low
+/// nibble is 0. Returns the number of bytes written, or `None` if `out` is
+/// shorter than that (`(colors.len() * 3 + 1) / 2`).
+///
+/// # Arguments
+///
+/// * `colors` - the Rgb565 colors to pack
+/// * `out` - buffer for the packed data
+///
+pub fn pack_rgb444(colors: &[u16], out: &mut [u8]) -> Option<usize> {
+    let len = (colors.len() * 3).div_ceil(2);
+    let out = out.get_mut(..len)?;
+    for (pair, bytes) in colors.chunks(2).zip(out.chunks_mut(3)) {
+        let first = rgb565_to_rgb444(pair[0]);
+        let second = pair.get(1).map_or(0, |&c| rgb565_to_rgb444(c));
+        bytes[0] = (first >> 4) as u8;
+        bytes[1] = ((first << 4) as u8) | (second >> 8) as u8;
+        if let Some(last) = bytes.get_mut(2) {
+            *last = second as u8;
+        }
+    }
+    Some(len)
+}
+
+// Upper 4 bits of every channel of a Rgb565 color, as 0x0RGB.
+const fn rgb565_to_rgb444(color: u16) -> u16 {
+    ((color >> 12) << 8) | (((color >> 7) & 0x0f) << 4) | ((color >> 1) & 0x0f)
+}
+
+// Expands a 0x0RGB 12 bit color to Rgb565, repeating the high bits.
+#[cfg(feature = "shadow")]
+pub(crate) const fn rgb444_to_rgb565(color: u16) -> u16 {
+    let (r, g, b) = ((color >> 8) & 0x0f, (color >> 4) & 0x0f, color & 0x0f);
+    (((r << 1) | (r >> 3)) << 11) | (((g << 2) | (g >> 2)) << 5) | ((b << 1) | (b >> 3))
+}
+
+pub const BLACK: u16 = rgb565(0x00, 0x00, 0x00);
+pub const SILVER: u16 = rgb565(0xc0, 0xc0, 0xc0);
+pub const GRAY: u16 = rgb565(0x80, 0x80, 0x80);
+pub const WHITE: u16 = rgb565(0xff, 0xff, 0xff);
+pub const MAROON: u16 = rgb565(0x80, 0x00, 0x00);
+pub const RED: u16 = rgb565(0xff, 0x00, 0x00);
+pub const PURPLE: u16 = rgb565(0x80, 0x00, 0x80);
+pub const FUCHSIA: u16 = rgb565(0xff, 0x00, 0xff);
+pub const GREEN: u16 = rgb565(0x00, 0x80, 0x00);
+pub const LIME: u16 = rgb565(0x00, 0xff, 0x00);
+pub const OLIVE: u16 = rgb565(0x80, 0x80, 0x00);
+pub const YELLOW: u16 = rgb565(0xff, 0xff, 0x00);
+pub const NAVY: u16 = rgb565(0x00, 0x00, 0x80);
+pub const BLUE: u16 = rgb565(0x00, 0x00, 0xff);
+pub const TEAL: u16 = rgb565(0x00, 0x80, 0x80);
+pub const AQUA: u16 = rgb565(0x00, 0xff, 0xff);
+
+pub const CYAN: u16 = AQUA;
+pub const MAGENTA: u16 = FUCHSIA;
+pub const ORANGE: u16 = rgb565(0xff, 0xa5, 0x00);
+pub const DARK_GRAY: u16 = rgb565(0x40, 0x40, 0x40);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn palette_matches_rgb565_values() {
+        assert_eq!(BLACK, 0x0000);
+        assert_eq!(WHITE, 0xffff);
+        assert_eq!(RED, 0xf800);
+        assert_eq!(LIME, 0x07e0);
+        assert_eq!(BLUE, 0x001f);
+        assert_eq!(YELLOW, 0xffe0);
+        assert_eq!(CYAN, 0x07ff);
+        assert_eq!(MAGENTA, 0xf81f);
+        assert_eq!(GRAY, 0x8410);
+        assert_eq!(GREEN, 0x0400);
+        assert_eq!(ORANGE, 0xfd20);
+    }
+
+    #[test]
+    fn rgb565_drops_the_low_bits() {
+        assert_eq!(rgb565(0x07, 0x03, 0x07), 0);
+        assert_eq!(rgb565(0x08, 0x04, 0x08), 0x0821);
+    }
+
+    #[test]
+    fn idle_colors_keep_the_top_bit_of_each_channel() {
+        assert_eq!(idle_color(WHITE), WHITE);
+        assert_eq!(idle_color(GRAY), WHITE);
+        assert_eq!(idle_color(GREEN), LIME);
+        assert_eq!(idle_color(ORANGE), YELLOW);
+        assert_eq!(idle_color(0x7bef), BLACK);
+        assert_eq!(idle_color(CYAN), CYAN);
+    }
+
+    #[test]
+    fn raw_bytes_follow_the_byte_order() {
+        assert_eq!(to_bytes(ORANGE, false), [(ORANGE >> 8) as u8, ORANGE as u8]);
+        assert_eq!(to_bytes(ORANGE, true), [ORANGE as u8, (ORANGE >> 8) as u8]);
+
+        // raw data built this way matches what the driver sends for u16 colors
+        for little_endian in [false, true] {
+            let mut display = crate::mock::initialized();
+            display.set_little_endian_pixels(little_endian);
+            display.set_pixel(0, 0, ORANGE).unwrap();
+            assert_eq!(display.di.params(crate::instruction::Instruction::RAMWR), display.color_bytes(ORANGE));
+            assert_eq!(display.color_bytes(ORANGE), to_bytes(ORANGE, little_endian));
+        }
+    }
+}
@@ -0,0 +1,260 @@
+//! Display configuration applied by `init`.
+use crate::instruction::Instruction;
+use crate::Orientation;
+
+///
+/// Order of the color components in the panel
+///
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ColorOrder {
+    Rgb,
+    Bgr,
+}
+
+impl ColorOrder {
+    pub(crate) fn swapped(self) -> Self {
+        match self {
+            Self::Rgb => Self::Bgr,
+            Self::Bgr => Self::Rgb,
+        }
+    }
+}
+
+///
+/// Pixel format of the data written to display RAM (COLMOD)
+///
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PixelFormat {
+    /// 16 bits per pixel, 2 bytes
+    Rgb565,
+    /// 18 bits per pixel, 3 bytes (6 bits per channel, left aligned)
+    Rgb666,
+    /// 12 bits per pixel, two pixels packed into 3 bytes as R1G1 B1R2 G2B2
+    /// (see `colors::pack_rgb444`). An odd number of pixels ends with two
+    /// bytes, the low nibble of the last one unused.
+    Rgb444,
+}
+
+impl PixelFormat {
+    pub(crate) fn to_colmod(self) -> u8 {
+        match self {
+            Self::Rgb565 => 0x55,
+            Self::Rgb666 => 0x66,
+            Self::Rgb444 => 0x53,
+        }
+    }
+
+    // Bits each pixel takes in the data stream.
+    fn bits_per_pixel(self) -> usize {
+        match self {
+            Self::Rgb565 => 16,
+            Self::Rgb666 => 24,
+            Self::Rgb444 => 12,
+        }
+    }
+
+    // Bytes per pixel, rounded up for Rgb444.
+    pub(crate) fn bytes_per_pixel(self) -> usize {
+        self.bits_per_pixel().div_ceil(8)
+    }
+
+    // Bytes of raw data holding `pixels` pixels.
+    pub(crate) fn data_len(self, pixels: usize) -> usize {
+        (pixels * self.bits_per_pixel()).div_ceil(8)
+    }
+
+    // Bytes of the smallest group of whole pixels, the granularity at which
+    // raw data may be split between transfers.
+    pub(crate) fn unit_bytes(self) -> usize {
+        match self {
+            Self::Rgb444 => 3,
+            _ => self.bytes_per_pixel(),
+        }
+    }
+
+    // Byte offset of pixel `pixels` in raw data, None if it doesn't start on
+    // a byte boundary (odd pixels in Rgb444).
+    pub(crate) fn byte_offset(self, pixels: usize) -> Option<usize> {
+        let bits = pixels * self.bits_per_pixel();
+        if bits.is_multiple_of(8) {
+            Some(bits / 8)
+        } else {
+            None
+        }
+    }
+}
+
+///
+/// Family of the LCD panel driven by the controller, selecting the
+/// inversion `init` needs for correct colors
+///
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PanelType {
+    /// IPS panels, such as the one on the Pico-LCD 1.14", show inverted
+    /// colors unless `init` enables display inversion (INVON)
+    Ips,
+    /// TN panels show the RAM content as is (INVOFF)
+    Tn,
+}
+
+impl PanelType {
+    /// Whether `init` has to enable display inversion for this panel type
+    pub fn inverted(self) -> bool {
+        self == Self::Ips
+    }
+}
+
+///
+/// Offsets of the visible area in controller RAM for panels of common
+/// resolutions, in landscape orientation: ((size_x, size_y), (off_x, off_y))
+///
+pub const KNOWN_OFFSETS: &[((u16, u16), (u16, u16))] = &[
+    ((240, 135), (40, 53)), // 1.14", e.g. Pico-LCD 1.14"
+    ((240, 240), (0, 0)),   // 1.3" and 1.54" square panels
+    ((280, 240), (20, 0)),  // 1.69" panels
+    ((320, 170), (0, 35)),  // 1.9" panels
+    ((320, 240), (0, 0)),   // 2.0" and 2.4" panels using all of the RAM
+];
+
+///
+/// Looks up the offsets of a landscape resolution in `KNOWN_OFFSETS`,
+/// `None` if the resolution isn't listed.
+///
+/// # Arguments
+///
+/// * `size_x` - x axis resolution of the display in pixels
+/// * `size_y` - y axis resolution of the display in pixels
+///
+pub const fn known_offsets(size_x: u16, size_y: u16) -> Option<(u16, u16)> {
+    let mut i = 0;
+    while i < KNOWN_OFFSETS.len() {
+        let ((x, y), offset) = KNOWN_OFFSETS[i];
+        if x == size_x && y == size_y {
+            return Some(offset);
+        }
+        i += 1;
+    }
+    None
+}
+
+///
+/// A sequence of commands with their parameter bytes
+///
+pub type InitSequence = &'static [(Instruction, &'static [u8])];
+
+///
+/// Complete display configuration for `ST7789::new_with_config`
+///
+#[derive(Clone, Copy, Debug)]
+pub struct DisplayConfig {
+    /// Visible size in landscape orientation (x, y)
+    pub size: (u16, u16),
+    /// Offset of the visible area in landscape orientation (x, y)
+    pub offset: (u16, u16),
+    /// Orientation set by `init`
+    pub orientation: Orientation,
+    /// Whether the panel is mounted upside down, see `ST7789::with_upside_down`
+    pub upside_down: bool,
+    /// Color component order of the panel
+    pub color_order: ColorOrder,
+    /// Pixel format set by `init`. Formats other than Rgb565 can only be
+    /// written with the raw blit methods; drawing u16 colors fails with
+    /// `DisplayError`.
+    pub pixel_format: PixelFormat,
+    /// Whether `init` enables display inversion (INVON)
+    pub inverted: bool,
+    /// Whether the reset pin is driven high to reset the controller (inverted reset line)
+    pub reset_active_high: bool,
+    /// Replaces the built-in panel tuning commands (porch, gate, power and gamma settings)
+    pub init_sequence: Option<InitSequence>,
+}
+
+impl DisplayConfig {
+    ///
+    /// Creates a configuration with the given size and offsets and the
+    /// defaults used by `ST7789::new` for everything else.
+    ///
+    pub fn new(size_x: u16, size_y: u16, off_x: u16, off_y: u16) -> Self {
+        Self {
+            size: (size_x, size_y),
+            offset: (off_x, off_y),
+            orientation: Orientation::Landscape,
+            upside_down: false,
+            color_order: ColorOrder::Rgb,
+            pixel_format: PixelFormat::Rgb565,
+            inverted: true,
+            reset_active_high: false,
+            init_sequence: None,
+        }
+    }
+
+    ///
+    /// Applies the defaults for a panel type, i.e. the display inversion. The
+    /// defaults of `new` are those of `PanelType::Ips`.
+    ///
+    /// # Arguments
+    ///
+    /// * `panel` - the family of the connected panel
+    ///
+    pub fn with_panel_type(mut self, panel: PanelType) -> Self {
+        self.inverted = panel.inverted();
+        self
+    }
+
+    ///
+    /// Selects an upside down mounting of the panel, see
+    /// `ST7789::with_upside_down`.
+    ///
+    /// # Arguments
+    ///
+    /// * `upside_down` - whether the panel is mounted upside down
+    ///
+    pub fn with_upside_down(mut self, upside_down: bool) -> Self {
+        self.upside_down = upside_down;
+        self
+    }
+}
+
+///
+/// Snapshot of the display settings, see `ST7789::display_state`
+///
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DisplayState {
+    pub orientation: Orientation,
+    pub color_order: ColorOrder,
+    pub inverted: bool,
+    /// Mirroring of the (x, y) axis
+    pub mirror: (bool, bool),
+    pub pixel_format: PixelFormat,
+    /// Whether 16bpp colors are sent low byte first, see
+    /// `ST7789::set_little_endian_pixels`
+    pub little_endian: bool,
+    /// Whether the controller has been initialized since its last reset
+    pub initialized: bool,
+}
+
+///
+/// A display configuration that can't work with the controller, see `ST7789::try_new`
+///
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ConfigError {
+    /// Width or height is zero
+    ZeroSize,
+    /// The visible area is larger than the 320x240 controller RAM (landscape)
+    SizeTooLarge,
+    /// The visible area doesn't fit into controller RAM at the given offset
+    OffsetTooLarge,
+}
+
+impl core::fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            ConfigError::ZeroSize => write!(f, "display size is zero"),
+            ConfigError::SizeTooLarge => write!(f, "display size exceeds controller RAM"),
+            ConfigError::OffsetTooLarge => write!(f, "display offset exceeds controller RAM"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ConfigError {}
@@ -0,0 +1,85 @@
+//! Delay providers for `init` and `hard_reset`.
+use embedded_hal::blocking::delay::{DelayMs, DelayUs};
+
+///
+/// A delay provider that doesn't wait at all.
+///
+/// Useful for simulation, tests and bring-up with a logic analyzer. Not safe
+/// for real hardware: the controller needs the reset and sleep-out delays,
+/// and without them it may ignore commands or stay blank.
+///
+#[derive(Clone, Copy, Debug, Default)]
+pub struct NoDelay;
+
+impl DelayUs<u32> for NoDelay {
+    fn delay_us(&mut self, _us: u32) {}
+}
+
+///
+/// Adapts a millisecond delay provider for use with `init` and `hard_reset`.
+///
+/// Requested delays are rounded up to whole milliseconds, so the driver's
+/// short reset pulses take 1ms each instead of 100us.
+///
+#[derive(Clone, Copy, Debug, Default)]
+pub struct DelayMsAdapter<D>(pub D);
+
+impl<D: DelayMs<u32>> DelayUs<u32> for DelayMsAdapter<D> {
+    fn delay_us(&mut self, us: u32) {
+        self.0.delay_ms(us.div_ceil(1000));
+    }
+}
+
+// Adapts a plain delay function taking microseconds, as used by
+// `ErrorPolicy::Reinit`.
+pub(crate) struct FnDelay(pub fn(u32));
+
+impl DelayUs<u32> for FnDelay {
+    fn delay_us(&mut self, us: u32) {
+        (self.0)(us)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::{display, MockDelay};
+
+    #[test]
+    fn init_without_delays_sends_the_same_commands() {
+        let mut timed = display();
+        timed.init(&mut MockDelay::default()).unwrap();
+        let mut untimed = display();
+        untimed.init(&mut NoDelay).unwrap();
+        assert_eq!(untimed.di.log, timed.di.log);
+    }
+
+    #[derive(Default)]
+    struct MsDelay(std::vec::Vec<u32>);
+
+    impl DelayMs<u32> for MsDelay {
+        fn delay_ms(&mut self, ms: u32) {
+            self.0.push(ms);
+        }
+    }
+
+    #[test]
+    fn millisecond_adapter_rounds_up() {
+        let mut adapter = DelayMsAdapter(MsDelay::default());
+        for us in [0, 1, 999, 1000, 1001, 120_000] {
+            adapter.delay_us(us);
+        }
+        assert_eq!(adapter.0 .0, [0, 1, 1, 1, 2, 120]);
+    }
+
+    #[test]
+    fn init_waits_through_the_adapter() {
+        let mut delay = MockDelay::default();
+        display().init(&mut delay).unwrap();
+        assert_eq!(delay.delays, [100, 100, 1000, 1000]);
+
+        let mut adapter = DelayMsAdapter(MsDelay::default());
+        display().init(&mut adapter).unwrap();
+        assert_eq!(adapter.0 .0, [1, 1, 1, 1]);
+    }
+}
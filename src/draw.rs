@@ -0,0 +1,856 @@
+//! Drawing primitives built on the windowed pixel streaming of the driver.
+use crate::colors::blend565;
+use crate::{end_point, misuse, Error, ST7789};
+use display_interface::WriteOnlyDataCommand;
+use embedded_hal::digital::v2::OutputPin;
+
+///
+/// Clockwise rotation applied in software by `ST7789::blit_rotated`
+///
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Rotation {
+    Deg0,
+    Deg90,
+    Deg180,
+    Deg270,
+}
+
+impl<DI, RST, PinE> ST7789<DI, RST>
+where
+    DI: WriteOnlyDataCommand,
+    RST: OutputPin<Error = PinE>,
+{
+    ///
+    /// Draws a horizontal line as a single windowed write, clipped to the visible area.
+    ///
+    /// # Arguments
+    ///
+    /// * `x` - x coordinate start
+    /// * `y` - y coordinate
+    /// * `w` - length in pixels
+    /// * `color` - the Rgb565 color value
+    ///
+    pub fn draw_hline(&mut self, x: u16, y: u16, w: u16, color: u16) -> Result<(), Error<PinE>> {
+        self.fill_rect(x, y, w, 1, color)
+    }
+
+    ///
+    /// Draws a vertical line as a single windowed write, clipped to the visible area.
+    ///
+    /// # Arguments
+    ///
+    /// * `x` - x coordinate
+    /// * `y` - y coordinate start
+    /// * `h` - length in pixels
+    /// * `color` - the Rgb565 color value
+    ///
+    pub fn draw_vline(&mut self, x: u16, y: u16, h: u16, color: u16) -> Result<(), Error<PinE>> {
+        self.fill_rect(x, y, 1, h, color)
+    }
+
+    ///
+    /// Draws the one pixel wide outline of a rectangle as four windowed
+    /// writes, clipped to the visible area.
+    ///
+    /// # Arguments
+    ///
+    /// * `x` - x coordinate start
+    /// * `y` - y coordinate start
+    /// * `w` - width
+    /// * `h` - height
+    /// * `color` - the Rgb565 color value
+    ///
+    pub fn draw_rect(&mut self, x: u16, y: u16, w: u16, h: u16, color: u16) -> Result<(), Error<PinE>> {
+        if w == 0 || h == 0 {
+            return Ok(());
+        }
+        // edges beyond u16 range are off the display anyway
+        let right = x.saturating_add(w - 1);
+        let bottom = y.saturating_add(h - 1);
+        self.draw_hline(x, y, w, color)?;
+        if h > 1 {
+            self.draw_hline(x, bottom, w, color)?;
+        }
+        if h > 2 {
+            self.draw_vline(x, y.saturating_add(1), h - 2, color)?;
+            if w > 1 {
+                self.draw_vline(right, y.saturating_add(1), h - 2, color)?;
+            }
+        }
+        Ok(())
+    }
+
+    ///
+    /// Fills a rectangle with `color` blended over the known solid background
+    /// `bg`, see `colors::blend565`. As nothing is read back from the display,
+    /// the result is only right if the area really shows `bg`.
+    ///
+    /// # Arguments
+    ///
+    /// * `x` - x coordinate start
+    /// * `y` - y coordinate start
+    /// * `w` - width
+    /// * `h` - height
+    /// * `color` - the Rgb565 color drawn on top
+    /// * `bg` - the Rgb565 color of the background
+    /// * `alpha` - opacity of `color`, 255 is opaque
+    ///
+    #[allow(clippy::too_many_arguments)]
+    pub fn fill_rect_blended(
+        &mut self,
+        x: u16,
+        y: u16,
+        w: u16,
+        h: u16,
+        color: u16,
+        bg: u16,
+        alpha: u8,
+    ) -> Result<(), Error<PinE>> {
+        self.fill_rect(x, y, w, h, blend565(color, bg, alpha))
+    }
+
+    ///
+    /// Draws a horizontal progress bar: the left `fraction` of the rectangle
+    /// is filled with `fg`, the rest with `bg`. `fraction` is clamped to
+    /// 0.0 - 1.0 (NaN counts as 0.0) and the split rounded to the nearest pixel.
+    ///
+    /// # Arguments
+    ///
+    /// * `x` - x coordinate start
+    /// * `y` - y coordinate start
+    /// * `w` - width
+    /// * `h` - height
+    /// * `fraction` - progress from 0.0 to 1.0
+    /// * `fg` - the Rgb565 color of the done part
+    /// * `bg` - the Rgb565 color of the remaining part
+    ///
+    #[allow(clippy::too_many_arguments)]
+    pub fn draw_progress_bar(
+        &mut self,
+        x: u16,
+        y: u16,
+        w: u16,
+        h: u16,
+        fraction: f32,
+        fg: u16,
+        bg: u16,
+    ) -> Result<(), Error<PinE>> {
+        let fraction = if fraction > 0.0 { fraction.min(1.0) } else { 0.0 };
+        let done = (f32::from(w) * fraction + 0.5) as u16;
+        self.fill_rect(x, y, done, h, fg)?;
+        self.fill_rect(x.saturating_add(done), y, w - done, h, bg)
+    }
+
+    ///
+    /// Draws a sparkline: the background is cleared to `bg`, then every column
+    /// shows one sample as a vertical segment joining it to the previous one.
+    /// Samples are scaled so 0 is the bottom and 255 the top row. With more
+    /// samples than columns they are downsampled by picking evenly spaced
+    /// ones; with fewer, only the first `samples.len()` columns are used.
+    ///
+    /// # Arguments
+    ///
+    /// * `x` - x coordinate start
+    /// * `y` - y coordinate start
+    /// * `w` - width
+    /// * `h` - height
+    /// * `samples` - the values to plot, oldest first
+    /// * `color` - the Rgb565 color of the line
+    /// * `bg` - the Rgb565 background color
+    ///
+    #[allow(clippy::too_many_arguments)]
+    pub fn draw_sparkline(
+        &mut self,
+        x: u16,
+        y: u16,
+        w: u16,
+        h: u16,
+        samples: &[u8],
+        color: u16,
+        bg: u16,
+    ) -> Result<(), Error<PinE>> {
+        self.fill_rect(x, y, w, h, bg)?;
+        let columns = samples.len().min(w.into());
+        if columns == 0 || h == 0 {
+            return Ok(());
+        }
+        let span = u32::from(h - 1);
+        let row = |sample: u8| y.saturating_add((span - (u32::from(sample) * span + 127) / 255) as u16);
+        let mut prev = row(samples[0]);
+        for col in 0..columns {
+            let cur = row(samples[col * samples.len() / columns]);
+            let (top, bottom) = (prev.min(cur), prev.max(cur));
+            self.draw_vline(x.saturating_add(col as u16), top, bottom - top + 1, color)?;
+            prev = cur;
+        }
+        Ok(())
+    }
+
+    ///
+    /// Fills a circle, sending each of its rows as one horizontal line.
+    /// Parts outside the visible area are clipped.
+    ///
+    /// # Arguments
+    ///
+    /// * `cx` - x coordinate of the center
+    /// * `cy` - y coordinate of the center
+    /// * `r` - radius in pixels
+    /// * `color` - the Rgb565 color value
+    ///
+    pub fn fill_circle(&mut self, cx: u16, cy: u16, r: u16, color: u16) -> Result<(), Error<PinE>> {
+        let (cx, cy, r) = (i32::from(cx), i32::from(cy), i32::from(r));
+        // rows further from the center are off screen on both sides
+        let max_dy = r.min(cy.max(i32::from(self.size_y) - 1 - cy));
+        // walk the rows from the center outwards, shrinking the half width
+        // until the span end lies within the circle (squares of large radii
+        // overflow i32)
+        let mut dx = r;
+        for dy in 0..=max_dy {
+            while i64::from(dx).pow(2) + i64::from(dy).pow(2) > i64::from(r).pow(2) {
+                dx -= 1;
+            }
+            self.hline_clipped(cx - dx, cy + dy, 2 * dx + 1, color)?;
+            if dy != 0 {
+                self.hline_clipped(cx - dx, cy - dy, 2 * dx + 1, color)?;
+            }
+        }
+        Ok(())
+    }
+
+    ///
+    /// Fills a rectangle with rounded corners: the straight middle part as
+    /// one windowed write and every row of the corner regions as one
+    /// horizontal line. The radius is limited so the corners of opposite
+    /// sides don't overlap, i.e. to `(min(w, h) - 1) / 2`, which turns a
+    /// square into a circle. Parts outside the visible area are clipped.
+    ///
+    /// # Arguments
+    ///
+    /// * `x` - x coordinate start
+    /// * `y` - y coordinate start
+    /// * `w` - width
+    /// * `h` - height
+    /// * `radius` - corner radius in pixels
+    /// * `color` - the Rgb565 color value
+    ///
+    pub fn fill_rounded_rect(
+        &mut self,
+        x: u16,
+        y: u16,
+        w: u16,
+        h: u16,
+        radius: u16,
+        color: u16,
+    ) -> Result<(), Error<PinE>> {
+        if w == 0 || h == 0 {
+            return Ok(());
+        }
+        let r = radius.min((w.min(h) - 1) / 2);
+        self.fill_rect(x, y.saturating_add(r), w, h - 2 * r, color)?;
+        let (x, y, w, h, r) = (i32::from(x), i32::from(y), i32::from(w), i32::from(h), i32::from(r));
+        // corner centers are r pixels in from the edges; walk the rows
+        // outwards from them like fill_circle does
+        let mut dx = r;
+        for dy in 1..=r {
+            while dx * dx + dy * dy > r * r {
+                dx -= 1;
+            }
+            let span = w - 2 * r + 2 * dx;
+            self.hline_clipped(x + r - dx, y + r - dy, span, color)?;
+            self.hline_clipped(x + r - dx, y + h - 1 - r + dy, span, color)?;
+        }
+        Ok(())
+    }
+
+    ///
+    /// Draws a gauge needle: a line of `length` pixels from the center at
+    /// `angle_deg` degrees, where 0 points right and angles increase
+    /// clockwise (90 points down). The line is drawn with Bresenham's
+    /// algorithm, using integer trigonometry only, and the pixels of each
+    /// row are sent as one horizontal line. Parts outside the visible area
+    /// are clipped.
+    ///
+    /// # Arguments
+    ///
+    /// * `cx` - x coordinate of the center
+    /// * `cy` - y coordinate of the center
+    /// * `angle_deg` - direction of the needle in degrees
+    /// * `length` - length of the needle in pixels
+    /// * `color` - the Rgb565 color value
+    ///
+    pub fn draw_needle(
+        &mut self,
+        cx: u16,
+        cy: u16,
+        angle_deg: u16,
+        length: u16,
+        color: u16,
+    ) -> Result<(), Error<PinE>> {
+        let (x0, y0) = (i32::from(cx), i32::from(cy));
+        // round to the nearest pixel on both sides of zero
+        let scale = |sin: i32| sin.signum() * ((i32::from(length) * sin.abs() + (1 << 13)) >> 14);
+        let x1 = x0 + scale(sin_q14(angle_deg % 360 + 90));
+        let y1 = y0 + scale(sin_q14(angle_deg));
+        self.line_clipped(x0, y0, x1, y1, color)
+    }
+
+    ///
+    /// Draws connected line segments through `points`, in order, like
+    /// `draw_needle` with Bresenham's algorithm and one horizontal line per
+    /// row of every segment. A single point is drawn as a pixel, no points
+    /// draw nothing. Parts outside the visible area are clipped.
+    ///
+    /// # Arguments
+    ///
+    /// * `points` - the (x, y) coordinates to connect
+    /// * `color` - the Rgb565 color value
+    ///
+    pub fn draw_polyline(&mut self, points: &[(u16, u16)], color: u16) -> Result<(), Error<PinE>> {
+        let point = |&(x, y): &(u16, u16)| (i32::from(x), i32::from(y));
+        match points {
+            [] => Ok(()),
+            [only] => {
+                let (x, y) = point(only);
+                self.hline_clipped(x, y, 1, color)
+            }
+            _ => {
+                for segment in points.windows(2) {
+                    let ((x0, y0), (x1, y1)) = (point(&segment[0]), point(&segment[1]));
+                    self.line_clipped(x0, y0, x1, y1, color)?;
+                }
+                Ok(())
+            }
+        }
+    }
+
+    // Draws a line between two points with Bresenham's algorithm, sending
+    // the pixels of each row as one horizontal line clipped to the visible area.
+    fn line_clipped(&mut self, x0: i32, y0: i32, x1: i32, y1: i32, color: u16) -> Result<(), Error<PinE>> {
+        let (dx, dy) = ((x1 - x0).abs(), -(y1 - y0).abs());
+        let (step_x, step_y) = ((x1 - x0).signum(), (y1 - y0).signum());
+        let (mut x, mut y, mut err) = (x0, y0, dx + dy);
+        // start of the run of pixels in the current row
+        let mut run = x;
+        loop {
+            if x == x1 && y == y1 {
+                return self.hline_clipped(run.min(x), y, (x - run).abs() + 1, color);
+            }
+            let e2 = 2 * err;
+            if e2 >= dy {
+                err += dy;
+                x += step_x;
+            }
+            if e2 <= dx {
+                // the row is done, x may already have moved on to the next one
+                let last = if e2 >= dy { x - step_x } else { x };
+                self.hline_clipped(run.min(last), y, (last - run).abs() + 1, color)?;
+                err += dx;
+                y += step_y;
+                run = x;
+            }
+        }
+    }
+
+    // Draws a horizontal line given in signed coordinates, clipping it to the visible area.
+    pub(crate) fn hline_clipped(&mut self, x: i32, y: i32, w: i32, color: u16) -> Result<(), Error<PinE>> {
+        let sx = x.max(0);
+        let ex = (x + w).min(self.size_x.into());
+        if y < 0 || y >= self.size_y.into() || ex <= sx {
+            return Ok(());
+        }
+        self.fill_rect(sx as u16, y as u16, (ex - sx) as u16, 1, color)
+    }
+
+    ///
+    /// Tiles a small pattern across a rectangle, streaming the colors without
+    /// building a buffer of the whole area. The pattern's top left pixel is
+    /// placed at the rectangle's top left corner.
+    ///
+    /// # Arguments
+    ///
+    /// * `x` - x coordinate start
+    /// * `y` - y coordinate start
+    /// * `w` - width
+    /// * `h` - height
+    /// * `pattern` - Rgb565 colors of the pattern, row by row
+    /// * `pattern_w` - width of the pattern in pixels
+    ///
+    pub fn fill_pattern(
+        &mut self,
+        x: u16,
+        y: u16,
+        w: u16,
+        h: u16,
+        pattern: &[u16],
+        pattern_w: u16,
+    ) -> Result<(), Error<PinE>> {
+        let pattern_w = pattern_w as usize;
+        if pattern_w == 0 || pattern.is_empty() || !pattern.len().is_multiple_of(pattern_w) {
+            return Err(misuse(Error::DisplayError));
+        }
+        if w == 0 || h == 0 {
+            return Ok(());
+        }
+        let pattern_h = pattern.len() / pattern_w;
+        let (ex, ey) = end_point(x, y, w, h)?;
+        let colors = (0..h as usize).flat_map(move |row| {
+            let line = &pattern[(row % pattern_h) * pattern_w..][..pattern_w];
+            (0..w as usize).map(move |col| line[col % pattern_w])
+        });
+        self.set_pixels(x, y, ex, ey, colors)
+    }
+
+    ///
+    /// Fills a rectangle with a vertical gradient from `top` in the first row
+    /// to `bottom` in the last one. The colors are interpolated at 8 bits per
+    /// channel and reduced to Rgb565 with ordered (4x4 Bayer) dithering, so
+    /// the steps between neighbouring 565 values are broken up instead of
+    /// showing as bands. The pattern is aligned to the display coordinates,
+    /// so adjacent gradients join seamlessly.
+    ///
+    /// # Arguments
+    ///
+    /// * `x` - x coordinate start
+    /// * `y` - y coordinate start
+    /// * `w` - width
+    /// * `h` - height
+    /// * `top` - the RGB color of the first row, 8 bits per channel
+    /// * `bottom` - the RGB color of the last row, 8 bits per channel
+    ///
+    pub fn fill_vertical_gradient_dithered(
+        &mut self,
+        x: u16,
+        y: u16,
+        w: u16,
+        h: u16,
+        top: [u8; 3],
+        bottom: [u8; 3],
+    ) -> Result<(), Error<PinE>> {
+        if w == 0 || h == 0 {
+            return Ok(());
+        }
+        let (ex, ey) = end_point(x, y, w, h)?;
+        let span = i32::from(h - 1).max(1);
+        let colors = (0..h).flat_map(move |row| {
+            // channel values at row, in 8.8 fixed point
+            let mut level = [0i32; 3];
+            for (c, value) in level.iter_mut().enumerate() {
+                let (from, to) = (i32::from(top[c]) << 8, i32::from(bottom[c]) << 8);
+                *value = from + (to - from) * i32::from(row) / span;
+            }
+            let bayer_row = &BAYER_4X4[usize::from((y + row) & 3)];
+            (0..w).map(move |col| dither565(level, bayer_row[usize::from((x + col) & 3)]))
+        });
+        self.set_pixels(x, y, ex, ey, colors)
+    }
+
+    ///
+    /// Draws a 1 bit per pixel bitmap, expanding set bits to `fg` and cleared
+    /// bits to `bg`. Each row starts on a new byte and bits are read MSB first,
+    /// so a row occupies `(w + 7) / 8` bytes and the padding bits at the end
+    /// of a row are ignored.
+    ///
+    /// # Arguments
+    ///
+    /// * `x` - x coordinate start
+    /// * `y` - y coordinate start
+    /// * `w` - width
+    /// * `h` - height
+    /// * `bits` - bitmap data, row by row
+    /// * `fg` - Rgb565 color for set bits
+    /// * `bg` - Rgb565 color for cleared bits
+    ///
+    #[allow(clippy::too_many_arguments)]
+    pub fn draw_bitmap_1bpp(
+        &mut self,
+        x: u16,
+        y: u16,
+        w: u16,
+        h: u16,
+        bits: &[u8],
+        fg: u16,
+        bg: u16,
+    ) -> Result<(), Error<PinE>> {
+        let row_bytes = (w as usize).div_ceil(8);
+        if bits.len() < row_bytes * h as usize {
+            return Err(misuse(Error::DisplayError));
+        }
+        if w == 0 || h == 0 {
+            return Ok(());
+        }
+        let (ex, ey) = end_point(x, y, w, h)?;
+        let colors = bits.chunks(row_bytes).take(h as usize).flat_map(move |row| {
+            (0..w as usize).map(move |col| if bit_set(row, col) { fg } else { bg })
+        });
+        self.set_pixels(x, y, ex, ey, colors)
+    }
+
+    ///
+    /// Draws only the set bits of a 1 bit per pixel bitmap in `fg`, leaving
+    /// the pixels under cleared bits untouched. Every horizontal run of set
+    /// bits is sent as its own window, so sparse bitmaps are cheapest. The
+    /// bitmap layout is the same as for `draw_bitmap_1bpp`.
+    ///
+    /// # Arguments
+    ///
+    /// * `x` - x coordinate start
+    /// * `y` - y coordinate start
+    /// * `w` - width
+    /// * `h` - height
+    /// * `bits` - bitmap data, row by row
+    /// * `fg` - Rgb565 color for set bits
+    ///
+    pub fn draw_bitmap_1bpp_masked(
+        &mut self,
+        x: u16,
+        y: u16,
+        w: u16,
+        h: u16,
+        bits: &[u8],
+        fg: u16,
+    ) -> Result<(), Error<PinE>> {
+        let row_bytes = (w as usize).div_ceil(8);
+        if bits.len() < row_bytes * h as usize {
+            return Err(misuse(Error::DisplayError));
+        }
+        if w == 0 || h == 0 {
+            return Ok(());
+        }
+        end_point(x, y, w, h)?;
+        for (dy, row) in bits.chunks(row_bytes).take(h as usize).enumerate() {
+            let ry = y + dy as u16;
+            let mut col = 0;
+            while col < w as usize {
+                if !bit_set(row, col) {
+                    col += 1;
+                    continue;
+                }
+                let start = col;
+                while col < w as usize && bit_set(row, col) {
+                    col += 1;
+                }
+                let rx = x + start as u16;
+                let len = col - start;
+                self.set_pixels(rx, ry, rx + len as u16 - 1, ry, core::iter::repeat_n(fg, len))?;
+            }
+        }
+        Ok(())
+    }
+
+    ///
+    /// Draws a buffer of Rgb565 colors rotated clockwise by the given angle,
+    /// without changing the display orientation. The rotation is done while
+    /// streaming, so for 90 and 270 degrees the drawn area is `h` wide and `w` high.
+    ///
+    /// # Arguments
+    ///
+    /// * `x` - x coordinate of the top left corner of the drawn area
+    /// * `y` - y coordinate of the top left corner of the drawn area
+    /// * `w` - width of the source buffer
+    /// * `h` - height of the source buffer
+    /// * `data` - source colors, row by row
+    /// * `rotation` - clockwise rotation to apply
+    ///
+    pub fn blit_rotated(
+        &mut self,
+        x: u16,
+        y: u16,
+        w: u16,
+        h: u16,
+        data: &[u16],
+        rotation: Rotation,
+    ) -> Result<(), Error<PinE>> {
+        let (w, h) = (w as usize, h as usize);
+        if data.len() != w * h {
+            return Err(misuse(Error::DisplayError));
+        }
+        if w == 0 || h == 0 {
+            return Ok(());
+        }
+        let (out_w, out_h) = match rotation {
+            Rotation::Deg0 | Rotation::Deg180 => (w, h),
+            Rotation::Deg90 | Rotation::Deg270 => (h, w),
+        };
+        let (ex, ey) = end_point(x, y, out_w as u16, out_h as u16)?;
+        let colors = (0..out_h).flat_map(move |oy| {
+            (0..out_w).map(move |ox| {
+                let (sx, sy) = match rotation {
+                    Rotation::Deg0 => (ox, oy),
+                    Rotation::Deg90 => (oy, h - 1 - ox),
+                    Rotation::Deg180 => (w - 1 - ox, h - 1 - oy),
+                    Rotation::Deg270 => (w - 1 - oy, ox),
+                };
+                data[sy * w + sx]
+            })
+        });
+        self.set_pixels(x, y, ex, ey, colors)
+    }
+}
+
+// sin(0..=90 degrees) in Q14 fixed point
+const SIN_Q14: [i32; 91] = [
+    0, 286, 572, 857, 1143, 1428, 1713, 1997, 2280, 2563,
+    2845, 3126, 3406, 3686, 3964, 4240, 4516, 4790, 5063, 5334,
+    5604, 5872, 6138, 6402, 6664, 6924, 7182, 7438, 7692, 7943,
+    8192, 8438, 8682, 8923, 9162, 9397, 9630, 9860, 10087, 10311,
+    10531, 10749, 10963, 11174, 11381, 11585, 11786, 11982, 12176, 12365,
+    12551, 12733, 12911, 13085, 13255, 13421, 13583, 13741, 13894, 14044,
+    14189, 14330, 14466, 14598, 14726, 14849, 14968, 15082, 15191, 15296,
+    15396, 15491, 15582, 15668, 15749, 15826, 15897, 15964, 16026, 16083,
+    16135, 16182, 16225, 16262, 16294, 16322, 16344, 16362, 16374, 16382,
+    16384,
+];
+
+// Sine of an angle in whole degrees, Q14 fixed point.
+fn sin_q14(angle_deg: u16) -> i32 {
+    let a = usize::from(angle_deg % 360);
+    match a {
+        0..=90 => SIN_Q14[a],
+        91..=180 => SIN_Q14[180 - a],
+        181..=270 => -SIN_Q14[a - 180],
+        _ => -SIN_Q14[360 - a],
+    }
+}
+
+// Ordered dithering thresholds, 0 - 15
+const BAYER_4X4: [[u8; 4]; 4] = [[0, 8, 2, 10], [12, 4, 14, 6], [3, 11, 1, 9], [15, 7, 13, 5]];
+
+// Quantizes 8.8 fixed point channel values to Rgb565, rounding up where the
+// dropped fraction exceeds the threshold, i.e. computing
+// floor(value / step + threshold / 16) for each channel.
+fn dither565(level: [i32; 3], threshold: u8) -> u16 {
+    let t = i32::from(threshold);
+    // one 5 bit step is 8 << 8 and one 6 bit step 4 << 8
+    let r = ((level[0] + t * 128) >> 11).min(0x1f) as u16;
+    let g = ((level[1] + t * 64) >> 10).min(0x3f) as u16;
+    let b = ((level[2] + t * 128) >> 11).min(0x1f) as u16;
+    (r << 11) | (g << 5) | b
+}
+
+// Whether the bit for column `col` is set in a MSB first bitmap row.
+pub(crate) fn bit_set(row: &[u8], col: usize) -> bool {
+    row[col / 8] & (0x80 >> (col % 8)) != 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Rotation;
+    use crate::instruction::Instruction::*;
+    use crate::mock::initialized;
+
+    #[test]
+    fn bitmap_expands_bits_and_skips_row_padding() {
+        let mut display = initialized();
+        display.draw_bitmap_1bpp(2, 1, 3, 2, &[0b1011_1111, 0b0100_0000], 1, 2).unwrap();
+        assert_eq!(display.di.window(), (42, 54, 44, 55));
+        assert_eq!(display.di.pixel_data(), [1, 2, 1, 2, 1, 2]);
+    }
+
+    #[test]
+    fn masked_bitmap_writes_one_window_per_run() {
+        let mut display = initialized();
+        let bits = [0b1101_0000, 0b0000_0000, 0b0110_0000];
+        display.draw_bitmap_1bpp_masked(0, 0, 4, 3, &bits, 5).unwrap();
+        assert_eq!(display.di.count(RAMWR), 3);
+        let rows: [[u16; 4]; 3] = [[5, 5, 0, 5], [0, 0, 0, 0], [0, 5, 5, 0]];
+        for (y, row) in rows.iter().enumerate() {
+            for (x, &color) in row.iter().enumerate() {
+                assert_eq!(display.di.at(x as u16, y as u16), color, "({}, {})", x, y);
+            }
+        }
+    }
+
+    #[test]
+    fn rotated_blit_reorders_a_2x3_buffer() {
+        let data = [1, 2, 3, 4, 5, 6];
+        let cases: [(Rotation, (u16, u16), [u16; 6]); 4] = [
+            (Rotation::Deg0, (1, 2), [1, 2, 3, 4, 5, 6]),
+            (Rotation::Deg90, (2, 1), [5, 3, 1, 6, 4, 2]),
+            (Rotation::Deg180, (1, 2), [6, 5, 4, 3, 2, 1]),
+            (Rotation::Deg270, (2, 1), [2, 4, 6, 1, 3, 5]),
+        ];
+        for (rotation, (ex, ey), expected) in cases {
+            let mut display = initialized();
+            display.blit_rotated(0, 0, 2, 3, &data, rotation).unwrap();
+            assert_eq!(display.di.window(), (40, 53, 40 + ex, 53 + ey), "{:?}", rotation);
+            assert_eq!(display.di.pixel_data(), expected, "{:?}", rotation);
+        }
+    }
+
+    #[test]
+    fn rect_outline_is_four_writes() {
+        let mut display = initialized();
+        display.draw_rect(1, 1, 5, 4, 3).unwrap();
+        assert_eq!(display.di.count(RAMWR), 4);
+        for y in 0..6 {
+            for x in 0..7 {
+                let border = (1..=5).contains(&x) && (1..=4).contains(&y) && (x == 1 || x == 5 || y == 1 || y == 4);
+                assert_eq!(display.di.at(x, y), if border { 3 } else { 0 }, "({}, {})", x, y);
+            }
+        }
+
+        // the right edge is off the display
+        display.di.clear();
+        display.draw_rect(238, 0, 5, 3, 3).unwrap();
+        assert_eq!(display.di.count(RAMWR), 3);
+    }
+
+    #[test]
+    fn progress_bar_splits_at_the_fraction() {
+        let mut display = initialized();
+        let row = |display: &crate::mock::Display| (0..10).map(|x| display.di.at(x, 0)).collect::<std::vec::Vec<_>>();
+        display.draw_progress_bar(0, 0, 10, 2, 0.0, 1, 2).unwrap();
+        assert_eq!(row(&display), [2; 10]);
+        display.draw_progress_bar(0, 0, 10, 2, 0.5, 1, 2).unwrap();
+        assert_eq!(row(&display), [1, 1, 1, 1, 1, 2, 2, 2, 2, 2]);
+        assert_eq!(display.di.at(4, 1), 1);
+        display.draw_progress_bar(0, 0, 10, 2, 1.0, 1, 2).unwrap();
+        assert_eq!(row(&display), [1; 10]);
+        display.draw_progress_bar(0, 0, 10, 2, -1.0, 1, 2).unwrap();
+        assert_eq!(row(&display), [2; 10]);
+
+        display.di.clear();
+        display.draw_progress_bar(0, 0, 0, 2, 0.5, 1, 2).unwrap();
+        assert_eq!(display.di.pixel_data(), []);
+    }
+
+    #[test]
+    fn blended_fill_mixes_with_the_background() {
+        let mut display = initialized();
+        display.fill_rect_blended(0, 0, 2, 2, 0xffff, 0x0000, 0).unwrap();
+        assert_eq!(display.di.at(1, 1), 0x0000);
+        display.fill_rect_blended(0, 0, 2, 2, 0xffff, 0x0000, 128).unwrap();
+        assert_eq!(display.di.at(1, 1), 0x8410);
+        display.fill_rect_blended(0, 0, 2, 2, 0xf800, 0x001f, 128).unwrap();
+        assert_eq!(display.di.at(1, 1), 0x800f);
+        display.fill_rect_blended(0, 0, 2, 2, 0xffff, 0x0000, 255).unwrap();
+        assert_eq!(display.di.at(1, 1), 0xffff);
+    }
+
+    #[test]
+    fn sparkline_joins_a_ramp() {
+        let mut display = initialized();
+        let grid = |display: &crate::mock::Display| {
+            let mut grid = [[0; 4]; 4];
+            for (y, row) in grid.iter_mut().enumerate() {
+                for (x, pixel) in row.iter_mut().enumerate() {
+                    *pixel = display.di.at(x as u16, y as u16);
+                }
+            }
+            grid
+        };
+        let ramp = [[2, 2, 2, 1], [2, 2, 1, 1], [2, 1, 1, 2], [1, 1, 2, 2]];
+        display.draw_sparkline(0, 0, 4, 4, &[0, 85, 170, 255], 1, 2).unwrap();
+        assert_eq!(grid(&display), ramp);
+
+        // every second sample is picked
+        display.draw_sparkline(0, 0, 4, 4, &[0, 255, 85, 255, 170, 255, 255, 0], 1, 2).unwrap();
+        assert_eq!(grid(&display), ramp);
+
+        display.draw_sparkline(0, 0, 4, 4, &[], 1, 2).unwrap();
+        assert_eq!(grid(&display), [[2; 4]; 4]);
+    }
+
+    #[test]
+    fn gradient_dither_follows_the_bayer_pattern() {
+        let mut display = initialized();
+        // half way between two 5 bit red steps: every threshold of 8 and up
+        // rounds up, giving a checkerboard
+        display.fill_vertical_gradient_dithered(0, 0, 4, 4, [4, 0, 0], [4, 0, 0]).unwrap();
+        for y in 0..4 {
+            for x in 0..4 {
+                let expected = if (x + y) % 2 == 1 { 0x0800 } else { 0 };
+                assert_eq!(display.di.at(x, y), expected, "({}, {})", x, y);
+            }
+        }
+        // exact 565 colors aren't dithered
+        display.fill_vertical_gradient_dithered(0, 0, 4, 4, [8, 4, 8], [8, 4, 8]).unwrap();
+        assert!((0..4).all(|x| display.di.at(x, 1) == 0x0821));
+
+        display.fill_vertical_gradient_dithered(0, 0, 4, 4, [0; 3], [255; 3]).unwrap();
+        assert!((0..4).all(|x| display.di.at(x, 0) == 0 && display.di.at(x, 3) == 0xffff));
+    }
+
+    #[test]
+    fn rounded_rect_cuts_the_corners() {
+        let check = |radius| {
+            let mut display = initialized();
+            display.fill_rounded_rect(1, 1, 6, 5, radius, 1).unwrap();
+            let expected = ["..##..", ".####.", "######", ".####.", "..##.."];
+            for (y, row) in expected.iter().enumerate() {
+                for (x, c) in row.chars().enumerate() {
+                    let pixel = display.di.at(x as u16 + 1, y as u16 + 1);
+                    assert_eq!(pixel, u16::from(c == '#'), "radius {} ({}, {})", radius, x, y);
+                }
+            }
+        };
+        check(2);
+        // too large radii are limited to half the smaller side
+        check(10);
+
+        let mut display = initialized();
+        display.fill_rounded_rect(0, 0, 3, 3, 1, 1).unwrap();
+        assert_eq!([(0, 0), (1, 0), (0, 1), (1, 1)].map(|(x, y)| display.di.at(x, y)), [0, 1, 1, 1]);
+        // clipped at the display edges
+        display.fill_rounded_rect(236, 132, 10, 10, 3, 1).unwrap();
+        assert_eq!(display.di.at(239, 134), 1);
+        assert_eq!(display.di.pixel(280, 187), 0);
+        assert_eq!(display.di.pixel(279, 188), 0);
+    }
+
+    #[test]
+    fn horizontal_needles_are_one_run() {
+        let mut display = initialized();
+        display.draw_needle(100, 60, 0, 20, 1).unwrap();
+        assert_eq!(display.di.count(RAMWR), 1);
+        assert_eq!(display.di.window(), (140, 113, 160, 113));
+
+        // pointing left past the edge, clipped to x 0
+        display.di.clear();
+        display.draw_needle(10, 60, 180, 200, 2).unwrap();
+        assert_eq!(display.di.count(RAMWR), 1);
+        assert_eq!(display.di.window(), (40, 113, 50, 113));
+    }
+
+    #[test]
+    fn vertical_needles_take_one_pixel_per_row() {
+        let mut display = initialized();
+        display.draw_needle(100, 60, 90, 10, 1).unwrap();
+        assert_eq!(display.di.count(RAMWR), 11);
+        assert!((60..=70).all(|y| display.di.at(100, y) == 1));
+        assert_eq!((display.di.at(100, 71), display.di.at(101, 65)), (0, 0));
+
+        display.di.clear();
+        display.draw_needle(100, 60, 270, 10, 2).unwrap();
+        assert!((50..=60).all(|y| display.di.at(100, y) == 2));
+        assert_eq!(display.di.at(100, 49), 0);
+    }
+
+    #[test]
+    fn polylines_join_the_segments_of_a_zigzag() {
+        let mut display = initialized();
+        // down right by 2, then up right by 2
+        display.draw_polyline(&[(10, 10), (12, 12), (14, 10)], 1).unwrap();
+        for (x, y) in [(10, 10), (11, 11), (12, 12), (13, 11), (14, 10)] {
+            assert_eq!(display.di.at(x, y), 1, "({}, {})", x, y);
+        }
+        assert_eq!((display.di.at(11, 10), display.di.at(12, 11), display.di.at(13, 10)), (0, 0, 0));
+        // one run per row of each segment
+        assert_eq!(display.di.count(RAMWR), 6);
+
+        display.di.clear();
+        display.draw_polyline(&[(3, 4)], 2).unwrap();
+        assert_eq!(display.di.at(3, 4), 2);
+        display.di.clear();
+        display.draw_polyline(&[], 2).unwrap();
+        assert!(display.di.log.is_empty());
+    }
+
+    #[test]
+    fn huge_circles_cover_the_visible_rows() {
+        let mut display = initialized();
+        display.fill_circle(10, 10, 60000, 0xffff).unwrap();
+        assert_eq!(display.di.count(RAMWR), 135);
+        assert_eq!((display.di.at(0, 0), display.di.at(239, 134)), (0xffff, 0xffff));
+    }
+}
@@ -0,0 +1,69 @@
+//! Object safe display abstraction, for code that stores drivers as trait objects.
+use crate::{Error, ST7789};
+use display_interface::WriteOnlyDataCommand;
+use embedded_hal::digital::v2::OutputPin;
+
+///
+/// The core drawing operations of a display, usable as `dyn DisplayDriver`.
+/// Colors are Rgb565 and coordinates are in the display's current
+/// orientation.
+///
+pub trait DisplayDriver {
+    type Error;
+
+    /// Width of the visible area in pixels
+    fn width(&self) -> u16;
+
+    /// Height of the visible area in pixels
+    fn height(&self) -> u16;
+
+    /// Fills a rectangle with a single color, clipped to the visible area
+    fn fill_rect(&mut self, x: u16, y: u16, w: u16, h: u16, color: u16) -> Result<(), Self::Error>;
+
+    /// Sets the pixels of a rectangle with inclusive end coordinates, row by row
+    fn set_pixels(
+        &mut self,
+        sx: u16,
+        sy: u16,
+        ex: u16,
+        ey: u16,
+        colors: &mut dyn Iterator<Item = u16>,
+    ) -> Result<(), Self::Error>;
+
+    /// Fills the whole visible area with a single color
+    fn clear(&mut self, color: u16) -> Result<(), Self::Error> {
+        let (w, h) = (self.width(), self.height());
+        self.fill_rect(0, 0, w, h, color)
+    }
+}
+
+impl<DI, RST, PinE> DisplayDriver for ST7789<DI, RST>
+where
+    DI: WriteOnlyDataCommand,
+    RST: OutputPin<Error = PinE>,
+{
+    type Error = Error<PinE>;
+
+    fn width(&self) -> u16 {
+        ST7789::width(self)
+    }
+
+    fn height(&self) -> u16 {
+        ST7789::height(self)
+    }
+
+    fn fill_rect(&mut self, x: u16, y: u16, w: u16, h: u16, color: u16) -> Result<(), Self::Error> {
+        ST7789::fill_rect(self, x, y, w, h, color)
+    }
+
+    fn set_pixels(
+        &mut self,
+        sx: u16,
+        sy: u16,
+        ex: u16,
+        ey: u16,
+        colors: &mut dyn Iterator<Item = u16>,
+    ) -> Result<(), Self::Error> {
+        ST7789::set_pixels(self, sx, sy, ex, ey, colors)
+    }
+}
@@ -0,0 +1,286 @@
+//! A minimal built-in 5x7 ASCII font.
+use crate::{Error, ST7789};
+use core::convert::TryFrom;
+use display_interface::WriteOnlyDataCommand;
+use embedded_hal::digital::v2::OutputPin;
+
+/// Horizontal advance per character in pixels, including 1 pixel spacing
+pub const GLYPH_WIDTH: u16 = 6;
+/// Height of a character cell in pixels, including 1 pixel spacing
+pub const GLYPH_HEIGHT: u16 = 8;
+
+///
+/// Horizontal placement of text within a field, see `ST7789::draw_text_aligned`
+///
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Align {
+    Left,
+    Center,
+    Right,
+}
+
+///
+/// Width of `text` in pixels when drawn with the built-in font, including
+/// the spacing of the last character. Saturates at `u16::MAX`.
+///
+/// # Arguments
+///
+/// * `text` - the text to measure
+///
+pub fn text_width(text: &str) -> u16 {
+    let width = text.chars().count().saturating_mul(GLYPH_WIDTH.into());
+    u16::try_from(width).unwrap_or(u16::MAX)
+}
+
+// Glyphs for the printable ASCII range (0x20 - 0x7e), five columns each,
+// least significant bit at the top.
+const GLYPHS: [[u8; 5]; 95] = [
+    [0x00, 0x00, 0x00, 0x00, 0x00], // ' '
+    [0x00, 0x00, 0x5f, 0x00, 0x00], // '!'
+    [0x00, 0x07, 0x00, 0x07, 0x00], // '"'
+    [0x14, 0x7f, 0x14, 0x7f, 0x14], // '#'
+    [0x24, 0x2a, 0x7f, 0x2a, 0x12], // '$'
+    [0x23, 0x13, 0x08, 0x64, 0x62], // '%'
+    [0x36, 0x49, 0x55, 0x22, 0x50], // '&'
+    [0x00, 0x05, 0x03, 0x00, 0x00], // '''
+    [0x00, 0x1c, 0x22, 0x41, 0x00], // '('
+    [0x00, 0x41, 0x22, 0x1c, 0x00], // ')'
+    [0x08, 0x2a, 0x1c, 0x2a, 0x08], // '*'
+    [0x08, 0x08, 0x3e, 0x08, 0x08], // '+'
+    [0x00, 0x50, 0x30, 0x00, 0x00], // ','
+    [0x08, 0x08, 0x08, 0x08, 0x08], // '-'
+    [0x00, 0x60, 0x60, 0x00, 0x00], // '.'
+    [0x20, 0x10, 0x08, 0x04, 0x02], // '/'
+    [0x3e, 0x51, 0x49, 0x45, 0x3e], // '0'
+    [0x00, 0x42, 0x7f, 0x40, 0x00], // '1'
+    [0x42, 0x61, 0x51, 0x49, 0x46], // '2'
+    [0x21, 0x41, 0x45, 0x4b, 0x31], // '3'
+    [0x18, 0x14, 0x12, 0x7f, 0x10], // '4'
+    [0x27, 0x45, 0x45, 0x45, 0x39], // '5'
+    [0x3c, 0x4a, 0x49, 0x49, 0x30], // '6'
+    [0x01, 0x71, 0x09, 0x05, 0x03], // '7'
+    [0x36, 0x49, 0x49, 0x49, 0x36], // '8'
+    [0x06, 0x49, 0x49, 0x29, 0x1e], // '9'
+    [0x00, 0x36, 0x36, 0x00, 0x00], // ':'
+    [0x00, 0x56, 0x36, 0x00, 0x00], // ';'
+    [0x08, 0x14, 0x22, 0x41, 0x00], // '<'
+    [0x14, 0x14, 0x14, 0x14, 0x14], // '='
+    [0x00, 0x41, 0x22, 0x14, 0x08], // '>'
+    [0x02, 0x01, 0x51, 0x09, 0x06], // '?'
+    [0x32, 0x49, 0x79, 0x41, 0x3e], // '@'
+    [0x7e, 0x11, 0x11, 0x11, 0x7e], // 'A'
+    [0x7f, 0x49, 0x49, 0x49, 0x36], // 'B'
+    [0x3e, 0x41, 0x41, 0x41, 0x22], // 'C'
+    [0x7f, 0x41, 0x41, 0x22, 0x1c], // 'D'
+    [0x7f, 0x49, 0x49, 0x49, 0x41], // 'E'
+    [0x7f, 0x09, 0x09, 0x01, 0x01], // 'F'
+    [0x3e, 0x41, 0x41, 0x51, 0x32], // 'G'
+    [0x7f, 0x08, 0x08, 0x08, 0x7f], // 'H'
+    [0x00, 0x41, 0x7f, 0x41, 0x00], // 'I'
+    [0x20, 0x40, 0x41, 0x3f, 0x01], // 'J'
+    [0x7f, 0x08, 0x14, 0x22, 0x41], // 'K'
+    [0x7f, 0x40, 0x40, 0x40, 0x40], // 'L'
+    [0x7f, 0x02, 0x04, 0x02, 0x7f], // 'M'
+    [0x7f, 0x04, 0x08, 0x10, 0x7f], // 'N'
+    [0x3e, 0x41, 0x41, 0x41, 0x3e], // 'O'
+    [0x7f, 0x09, 0x09, 0x09, 0x06], // 'P'
+    [0x3e, 0x41, 0x51, 0x21, 0x5e], // 'Q'
+    [0x7f, 0x09, 0x19, 0x29, 0x46], // 'R'
+    [0x46, 0x49, 0x49, 0x49, 0x31], // 'S'
+    [0x01, 0x01, 0x7f, 0x01, 0x01], // 'T'
+    [0x3f, 0x40, 0x40, 0x40, 0x3f], // 'U'
+    [0x1f, 0x20, 0x40, 0x20, 0x1f], // 'V'
+    [0x7f, 0x20, 0x18, 0x20, 0x7f], // 'W'
+    [0x63, 0x14, 0x08, 0x14, 0x63], // 'X'
+    [0x03, 0x04, 0x78, 0x04, 0x03], // 'Y'
+    [0x61, 0x51, 0x49, 0x45, 0x43], // 'Z'
+    [0x00, 0x7f, 0x41, 0x41, 0x00], // '['
+    [0x02, 0x04, 0x08, 0x10, 0x20], // '\'
+    [0x00, 0x41, 0x41, 0x7f, 0x00], // ']'
+    [0x04, 0x02, 0x01, 0x02, 0x04], // '^'
+    [0x40, 0x40, 0x40, 0x40, 0x40], // '_'
+    [0x00, 0x01, 0x02, 0x04, 0x00], // '`'
+    [0x20, 0x54, 0x54, 0x54, 0x78], // 'a'
+    [0x7f, 0x48, 0x44, 0x44, 0x38], // 'b'
+    [0x38, 0x44, 0x44, 0x44, 0x20], // 'c'
+    [0x38, 0x44, 0x44, 0x48, 0x7f], // 'd'
+    [0x38, 0x54, 0x54, 0x54, 0x18], // 'e'
+    [0x08, 0x7e, 0x09, 0x01, 0x02], // 'f'
+    [0x08, 0x14, 0x54, 0x54, 0x3c], // 'g'
+    [0x7f, 0x08, 0x04, 0x04, 0x78], // 'h'
+    [0x00, 0x44, 0x7d, 0x40, 0x00], // 'i'
+    [0x20, 0x40, 0x44, 0x3d, 0x00], // 'j'
+    [0x00, 0x7f, 0x10, 0x28, 0x44], // 'k'
+    [0x00, 0x41, 0x7f, 0x40, 0x00], // 'l'
+    [0x7c, 0x04, 0x18, 0x04, 0x78], // 'm'
+    [0x7c, 0x08, 0x04, 0x04, 0x78], // 'n'
+    [0x38, 0x44, 0x44, 0x44, 0x38], // 'o'
+    [0x7c, 0x14, 0x14, 0x14, 0x08], // 'p'
+    [0x08, 0x14, 0x14, 0x18, 0x7c], // 'q'
+    [0x7c, 0x08, 0x04, 0x04, 0x08], // 'r'
+    [0x48, 0x54, 0x54, 0x54, 0x20], // 's'
+    [0x04, 0x3f, 0x44, 0x40, 0x20], // 't'
+    [0x3c, 0x40, 0x40, 0x20, 0x7c], // 'u'
+    [0x1c, 0x20, 0x40, 0x20, 0x1c], // 'v'
+    [0x3c, 0x40, 0x30, 0x40, 0x3c], // 'w'
+    [0x44, 0x28, 0x10, 0x28, 0x44], // 'x'
+    [0x0c, 0x50, 0x50, 0x50, 0x3c], // 'y'
+    [0x44, 0x64, 0x54, 0x4c, 0x44], // 'z'
+    [0x00, 0x08, 0x36, 0x41, 0x00], // '{'
+    [0x00, 0x00, 0x7f, 0x00, 0x00], // '|'
+    [0x00, 0x41, 0x36, 0x08, 0x00], // '}'
+    [0x08, 0x04, 0x08, 0x10, 0x08], // '~'
+];
+
+// Row-major 1bpp bitmap of a character cell, one byte per row (MSB first).
+// Characters outside the printable ASCII range are drawn as '?'.
+fn glyph_rows(c: char) -> [u8; GLYPH_HEIGHT as usize] {
+    let index = match c {
+        ' '..='~' => c as usize - ' ' as usize,
+        _ => '?' as usize - ' ' as usize,
+    };
+    let columns = &GLYPHS[index];
+    let mut rows = [0u8; GLYPH_HEIGHT as usize];
+    for (r, row) in rows.iter_mut().enumerate().take(7) {
+        for (c, column) in columns.iter().enumerate() {
+            if column & (1 << r) != 0 {
+                *row |= 0x80 >> c;
+            }
+        }
+    }
+    rows
+}
+
+impl<DI, RST, PinE> ST7789<DI, RST>
+where
+    DI: WriteOnlyDataCommand,
+    RST: OutputPin<Error = PinE>,
+{
+    ///
+    /// Draws a single line of text with the built-in 5x7 font. Every character
+    /// fills a `GLYPH_WIDTH` x `GLYPH_HEIGHT` cell with `fg` and `bg`, so
+    /// previous content is overwritten. Drawing stops at the first character
+    /// that doesn't fit the visible area completely.
+    ///
+    /// # Arguments
+    ///
+    /// * `x` - x coordinate of the top left corner of the first character
+    /// * `y` - y coordinate of the top left corner of the first character
+    /// * `text` - the text to draw
+    /// * `fg` - Rgb565 color of the characters
+    /// * `bg` - Rgb565 background color
+    ///
+    pub fn draw_text(&mut self, x: u16, y: u16, text: &str, fg: u16, bg: u16) -> Result<(), Error<PinE>> {
+        if u32::from(y) + u32::from(GLYPH_HEIGHT) > u32::from(self.size_y) {
+            return Ok(());
+        }
+        let mut cx = u32::from(x);
+        for c in text.chars() {
+            if cx + u32::from(GLYPH_WIDTH) > u32::from(self.size_x) {
+                break;
+            }
+            let rows = glyph_rows(c);
+            self.draw_bitmap_1bpp(cx as u16, y, GLYPH_WIDTH, GLYPH_HEIGHT, &rows, fg, bg)?;
+            cx += u32::from(GLYPH_WIDTH);
+        }
+        Ok(())
+    }
+
+    ///
+    /// Draws a line of text aligned within a field of `w` pixels, one
+    /// character cell high, e.g. for right-aligned numbers. The part of the
+    /// field not covered by the text is filled with `bg`, so nothing of a
+    /// previous, longer text remains. Characters that don't fit the field
+    /// are cut off at the end.
+    ///
+    /// # Arguments
+    ///
+    /// * `x` - x coordinate of the left edge of the field
+    /// * `y` - y coordinate of the top edge of the field
+    /// * `w` - width of the field
+    /// * `text` - the text to draw
+    /// * `align` - placement of the text within the field
+    /// * `fg` - Rgb565 color of the characters
+    /// * `bg` - Rgb565 background color
+    ///
+    #[allow(clippy::too_many_arguments)]
+    pub fn draw_text_aligned(
+        &mut self,
+        x: u16,
+        y: u16,
+        w: u16,
+        text: &str,
+        align: Align,
+        fg: u16,
+        bg: u16,
+    ) -> Result<(), Error<PinE>> {
+        let fit = usize::from(w / GLYPH_WIDTH);
+        let end = text.char_indices().nth(fit).map_or(text.len(), |(i, _)| i);
+        let text = &text[..end];
+        let text_w = text_width(text);
+        let left = match align {
+            Align::Left => 0,
+            Align::Center => (w - text_w) / 2,
+            Align::Right => w - text_w,
+        };
+        self.fill_rect(x, y, left, GLYPH_HEIGHT, bg)?;
+        let text_x = x.saturating_add(left);
+        self.fill_rect(text_x.saturating_add(text_w), y, w - left - text_w, GLYPH_HEIGHT, bg)?;
+        self.draw_text(text_x, y, text, fg, bg)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instruction::Instruction::*;
+    use crate::mock::initialized;
+
+    #[test]
+    fn draws_42() {
+        let mut display = initialized();
+        display.draw_text(0, 0, "42", 1, 0).unwrap();
+        assert_eq!(display.di.count(RAMWR), 2);
+        let expected = [
+            "...#...###..",
+            "..##..#...#.",
+            ".#.#......#.",
+            "#..#.....#..",
+            "#####...#...",
+            "...#...#....",
+            "...#..#####.",
+            "............",
+        ];
+        for (y, row) in expected.iter().enumerate() {
+            for (x, c) in row.chars().enumerate() {
+                assert_eq!(display.di.at(x as u16, y as u16), u16::from(c == '#'), "({}, {})", x, y);
+            }
+        }
+    }
+
+    #[test]
+    fn text_width_counts_cells() {
+        assert_eq!(text_width("42"), 2 * GLYPH_WIDTH);
+        assert_eq!(text_width("\u{e9}t\u{e9}"), 3 * GLYPH_WIDTH);
+        assert_eq!(text_width(""), 0);
+    }
+
+    #[test]
+    fn aligned_text_is_offset_within_the_field() {
+        let mut display = initialized();
+        // the bottom row of "4" starts in the first column of its cell
+        for (align, left) in [(Align::Left, 0), (Align::Center, 9), (Align::Right, 18)] {
+            display.fill_rect(0, 0, 30, GLYPH_HEIGHT, 7).unwrap();
+            display.draw_text_aligned(0, 0, 30, "42", align, 1, 2).unwrap();
+            assert_eq!(display.di.at(left, 4), 1, "{:?}", align);
+            assert!((0..left).all(|x| display.di.at(x, 4) == 2), "{:?}", align);
+            assert!((left + 12..30).all(|x| display.di.at(x, 4) == 2), "{:?}", align);
+        }
+
+        // cut off to the characters fitting the field
+        display.fill_rect(0, 0, 30, GLYPH_HEIGHT, 7).unwrap();
+        display.draw_text_aligned(0, 0, 8, "42", Align::Right, 1, 2).unwrap();
+        assert_eq!([0, 1, 2].map(|x| display.di.at(x, 4)), [2, 2, 1]);
+        assert_eq!(display.di.at(8, 4), 7);
+    }
+}
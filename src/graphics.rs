@@ -1,34 +1,391 @@
-use embedded_graphics::drawable::Pixel;
+use embedded_graphics::drawable::{Drawable, Pixel};
+use embedded_graphics::fonts::Font;
 use embedded_graphics::geometry::Dimensions;
-use embedded_graphics::image::{Image, ImageDimensions, IntoPixelIter};
+use embedded_graphics::image::{Image, ImageDimensions, ImageRaw, IntoPixelIter};
 use embedded_graphics::pixelcolor::raw::{RawData, RawU16};
-use embedded_graphics::pixelcolor::Rgb565;
-use embedded_graphics::prelude::{DrawTarget, Size};
+use embedded_graphics::pixelcolor::{BinaryColor, Rgb565};
+use embedded_graphics::prelude::{DrawTarget, Point, Size};
 use embedded_graphics::primitives::Rectangle;
 use embedded_graphics::style::{PrimitiveStyle, Styled};
 
 use embedded_hal::digital::v2::OutputPin;
 
-use crate::{Error, ST7789};
+use crate::instruction::Instruction::RAMWR;
+use crate::{misuse, Error, ST7789};
+use core::convert::TryFrom;
 use display_interface::WriteOnlyDataCommand;
 
+///
+/// Converts an embedded-graphics rectangle to the inclusive start and end
+/// coordinates `(sx, sy, ex, ey)` used by the driver's window methods.
+///
+/// Both corners of a `Rectangle` are inclusive, so a rectangle built from a
+/// top left corner and a size must end at `top_left + size - (1, 1)`. Returns
+/// `None` if the rectangle is empty (a corner past the other) or doesn't fit
+/// into u16 coordinates.
+///
+/// # Arguments
+///
+/// * `rect` - the rectangle to convert
+///
+pub fn window_from_rect(rect: &Rectangle) -> Option<(u16, u16, u16, u16)> {
+    let coord = |v: i32| u16::try_from(v).ok();
+    let (sx, sy) = (coord(rect.top_left.x)?, coord(rect.top_left.y)?);
+    let (ex, ey) = (coord(rect.bottom_right.x)?, coord(rect.bottom_right.y)?);
+    if ex < sx || ey < sy {
+        return None;
+    }
+    Some((sx, sy, ex, ey))
+}
+
 impl<DI, RST, PinE> ST7789<DI, RST>
 where
     DI: WriteOnlyDataCommand,
     RST: OutputPin<Error = PinE>,
 {
-    fn fill_rect(
+    ///
+    /// Same as `fill_rect`, but for an embedded-graphics rectangle. The
+    /// rectangle is clipped to the visible area.
+    ///
+    /// # Arguments
+    ///
+    /// * `rect` - the rectangle to fill
+    /// * `color` - the Rgb565 color value
+    ///
+    pub fn fill_rectangle(&mut self, rect: &Rectangle, color: u16) -> Result<(), Error<PinE>> {
+        match self.clip_to_display(rect) {
+            Some((sx, sy, ex, ey)) => self.fill_rect(sx, sy, ex - sx + 1, ey - sy + 1, color),
+            None => Ok(()),
+        }
+    }
+
+    ///
+    /// Fills the part of `rect` that lies within `clip`, for applications
+    /// managing their own clip regions. Nothing is sent if they don't overlap.
+    /// The result is clipped to the visible area as well.
+    ///
+    /// # Arguments
+    ///
+    /// * `rect` - the rectangle to fill
+    /// * `clip` - the region drawing is restricted to
+    /// * `color` - the Rgb565 color value
+    ///
+    pub fn fill_rect_clipped(
         &mut self,
-        item: &dyn Dimensions,
-        colors: &mut dyn Iterator<Item = u16>,
+        rect: &Rectangle,
+        clip: &Rectangle,
+        color: u16,
     ) -> Result<(), Error<PinE>> {
-        let sx = item.top_left().x as u16;
-        let sy = item.top_left().y as u16;
-        let ex = item.bottom_right().x as u16;
-        let ey = item.bottom_right().y as u16;
+        let (a, b) = (rect, clip);
+        let top_left = Point::new(a.top_left.x.max(b.top_left.x), a.top_left.y.max(b.top_left.y));
+        let bottom_right = Point::new(
+            a.bottom_right.x.min(b.bottom_right.x),
+            a.bottom_right.y.min(b.bottom_right.y),
+        );
+        if bottom_right.x < top_left.x || bottom_right.y < top_left.y {
+            return Ok(()); // no overlap
+        }
+        self.fill_rectangle(&Rectangle::new(top_left, bottom_right), color)
+    }
+
+    ///
+    /// Erases a region to the background color before redrawing it, e.g. the
+    /// bounding box of a widget that may have become smaller since it was last
+    /// drawn. Clipped to the visible area like `fill_rectangle`.
+    ///
+    /// # Arguments
+    ///
+    /// * `rect` - the region to erase, usually the previous bounding box
+    /// * `color` - the Rgb565 background color
+    ///
+    pub fn clear_region(&mut self, rect: &Rectangle, color: u16) -> Result<(), Error<PinE>> {
+        self.fill_rectangle(rect, color)
+    }
+
+    ///
+    /// Fills the visible area around `content` with a border color, as up to
+    /// four rectangles (above, below, left and right of it), leaving the
+    /// content untouched. Sides where the content touches the edge of the
+    /// visible area are skipped; if the content lies completely outside of
+    /// it, the whole visible area is filled.
+    ///
+    /// # Arguments
+    ///
+    /// * `content` - the area to leave out
+    /// * `color` - the Rgb565 border color
+    ///
+    pub fn fill_border(&mut self, content: Rectangle, color: u16) -> Result<(), Error<PinE>> {
+        let (width, height) = (self.size_x, self.size_y);
+        let (sx, sy, ex, ey) = match self.clip_to_display(&content) {
+            Some(window) => window,
+            None => return self.fill_rect(0, 0, width, height, color),
+        };
+        let rows = ey - sy + 1;
+        self.fill_rect(0, 0, width, sy, color)?;
+        self.fill_rect(0, ey + 1, width, height - ey - 1, color)?;
+        self.fill_rect(0, sy, sx, rows, color)?;
+        self.fill_rect(ex + 1, sy, width - ex - 1, rows, color)
+    }
+
+    ///
+    /// Same as `set_pixels`, but for an embedded-graphics rectangle, see
+    /// `window_from_rect`. Fails with `OutOfBounds` for negative or empty
+    /// rectangles, as the colors couldn't be matched to pixels after clipping.
+    ///
+    /// # Arguments
+    ///
+    /// * `rect` - the rectangle to set
+    /// * `colors` - anything that can provide `IntoIterator<Item = u16>` to iterate over pixel data
+    ///
+    pub fn set_pixels_rectangle<T>(&mut self, rect: &Rectangle, colors: T) -> Result<(), Error<PinE>>
+    where
+        T: IntoIterator<Item = u16>,
+    {
+        let (sx, sy, ex, ey) = window_from_rect(rect).ok_or_else(|| misuse(Error::OutOfBounds))?;
+        self.set_pixels(sx, sy, ex, ey, colors)
+    }
+
+    // Streams the pixels of an item covering `area` in row-major order,
+    // dropping those outside the visible area.
+    fn fill_clipped<T>(&mut self, area: &Rectangle, pixels: T) -> Result<(), Error<PinE>>
+    where
+        T: IntoIterator<Item = Pixel<Rgb565>>,
+    {
+        let (sx, sy, ex, ey) = match self.clip_to_display(area) {
+            Some(window) => window,
+            None => return Ok(()),
+        };
+        let x_range = i32::from(sx)..=i32::from(ex);
+        let y_range = i32::from(sy)..=i32::from(ey);
+        let colors = pixels
+            .into_iter()
+            .filter(move |Pixel(p, _)| x_range.contains(&p.x) && y_range.contains(&p.y))
+            .map(|Pixel(_, color)| RawU16::from(color).into_inner());
 
         self.set_pixels(sx, sy, ex, ey, colors)
     }
+
+    // Whether a point lies within the visible area.
+    fn is_visible(&self, point: Point) -> bool {
+        point.x >= 0
+            && point.y >= 0
+            && point.x < self.size_x.into()
+            && point.y < self.size_y.into()
+    }
+
+    ///
+    /// Blits the part of a raw framebuffer covered by `area` to the same
+    /// location on the display. `area` is clipped to both the framebuffer and
+    /// the display; nothing is sent if the clipped area is empty.
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - raw pixel data of the whole framebuffer, row by row
+    /// * `width` - width of the framebuffer in pixels
+    /// * `area` - region of the framebuffer to send
+    ///
+    pub fn blit_framebuffer_region(
+        &mut self,
+        data: &[u8],
+        width: u16,
+        area: &Rectangle,
+    ) -> Result<(), Error<PinE>> {
+        if width == 0 {
+            return Ok(());
+        }
+        let format = self.pixel_format;
+        let row_len = format
+            .byte_offset(width.into())
+            .ok_or_else(|| misuse(Error::DisplayError))?;
+        let height = data.len() / row_len;
+        let max_x = i32::from(width.min(self.size_x)) - 1;
+        let max_y = (height as i32).min(self.size_y.into()) - 1;
+
+        let sx = area.top_left.x.max(0);
+        let sy = area.top_left.y.max(0);
+        let ex = area.bottom_right.x.min(max_x);
+        let ey = area.bottom_right.y.min(max_y);
+        if ex < sx || ey < sy {
+            return Ok(()); // empty or fully clipped
+        }
+
+        // with Rgb444 the area has to start on an even pixel
+        let start = format
+            .byte_offset(sy as usize * width as usize + sx as usize)
+            .ok_or_else(|| misuse(Error::DisplayError))?;
+        self.blit_pixels_strided(
+            sx as u16,
+            sy as u16,
+            (ex - sx + 1) as u16,
+            (ey - sy + 1) as u16,
+            &data[start..],
+            width.into(),
+        )
+    }
+
+    ///
+    /// Renders `area` one scanline at a time, so only a single line of pixels
+    /// has to be held in RAM. `render` is called for every row from top to
+    /// bottom with the row's y coordinate and the line buffer to fill. The
+    /// address window is set once for the whole area and the rows are
+    /// streamed back to back.
+    ///
+    /// `area` is clipped to the display; `line` must hold at least as many
+    /// pixels as the clipped area is wide.
+    ///
+    /// # Arguments
+    ///
+    /// * `area` - region to render
+    /// * `line` - line buffer handed to `render`
+    /// * `render` - fills the line buffer for the given y coordinate
+    ///
+    pub fn render_scanlines<F>(
+        &mut self,
+        area: &Rectangle,
+        line: &mut [Rgb565],
+        mut render: F,
+    ) -> Result<(), Error<PinE>>
+    where
+        F: FnMut(i32, &mut [Rgb565]),
+    {
+        let (sx, sy, ex, ey) = match self.clip_to_display(area) {
+            Some(window) => window,
+            None => return Ok(()),
+        };
+        let width = (ex - sx + 1) as usize;
+        if line.len() < width {
+            return Err(misuse(Error::DisplayError));
+        }
+        let line = &mut line[..width];
+
+        self.set_address_window(sx, sy, ex, ey)?;
+        self.write_command(RAMWR)?;
+        for y in sy..=ey {
+            render(y.into(), line);
+            self.write_pixels(line.iter().map(|c| RawU16::from(*c).into_inner()))?;
+        }
+        Ok(())
+    }
+
+    ///
+    /// Draws a 1 bit per pixel image, e.g. an icon, mapping set pixels to
+    /// `on` and cleared pixels to `off`. The part of the image inside the
+    /// visible area is sent as one window, instead of pixel by pixel as
+    /// drawing an `Image` of it does; the padding bits at the end of the
+    /// image rows are skipped.
+    ///
+    /// # Arguments
+    ///
+    /// * `image` - the image to draw
+    /// * `top_left` - position of the top left corner of the image
+    /// * `on` - color of set pixels
+    /// * `off` - color of cleared pixels
+    ///
+    pub fn draw_mono_image(
+        &mut self,
+        image: &ImageRaw<BinaryColor>,
+        top_left: Point,
+        on: Rgb565,
+        off: Rgb565,
+    ) -> Result<(), Error<PinE>> {
+        let size = Size::new(image.width(), image.height());
+        if size.width == 0 || size.height == 0 {
+            return Ok(());
+        }
+        let area = Rectangle::new(top_left, top_left + size - Point::new(1, 1));
+        let (sx, sy, ex, ey) = match self.clip_to_display(&area) {
+            Some(window) => window,
+            None => return Ok(()),
+        };
+        let (on, off) = (RawU16::from(on).into_inner(), RawU16::from(off).into_inner());
+        let (columns, rows) = (i32::from(sx)..=i32::from(ex), i32::from(sy)..=i32::from(ey));
+        // pixels come row by row, so the ones left after clipping are in
+        // window order
+        let colors = image
+            .into_iter()
+            .map(|Pixel(point, color)| (point + top_left, color))
+            .filter(|(point, _)| columns.contains(&point.x) && rows.contains(&point.y))
+            .map(|(_, color)| if color == BinaryColor::On { on } else { off });
+        self.set_pixels(sx, sy, ex, ey, colors)
+    }
+
+    ///
+    /// Draws a single character of an embedded-graphics font as one window
+    /// of `fg` and `bg` pixels, instead of pixel by pixel as drawing a styled
+    /// `Text` does. The cell is the character's width (narrower than
+    /// `CHARACTER_SIZE` for variable width fonts) plus the font's character
+    /// spacing, filled with `bg`, by the font's height. Nothing is drawn if
+    /// the cell doesn't fit the visible area completely. Returns the width of
+    /// the cell, to advance to the next character.
+    ///
+    /// # Arguments
+    ///
+    /// * `x` - x coordinate of the top left corner of the cell
+    /// * `y` - y coordinate of the top left corner of the cell
+    /// * `c` - the character to draw
+    /// * `fg` - Rgb565 color of the character
+    /// * `bg` - Rgb565 background color
+    ///
+    pub fn draw_font_glyph<F: Font>(&mut self, x: u16, y: u16, c: char, fg: u16, bg: u16) -> Result<u16, Error<PinE>> {
+        let glyph_w = F::char_width(c);
+        let w = glyph_w + F::CHARACTER_SPACING;
+        let h = F::CHARACTER_SIZE.height;
+        let fits = u32::from(x) + w <= u32::from(self.size_x) && u32::from(y) + h <= u32::from(self.size_y);
+        if w == 0 || h == 0 || !fits {
+            return Ok(0);
+        }
+        let colors = (0..h).flat_map(move |row| {
+            (0..w).map(move |col| if col < glyph_w && F::character_pixel(c, col, row) { fg } else { bg })
+        });
+        self.set_pixels(x, y, x + w as u16 - 1, y + h as u16 - 1, colors)?;
+        Ok(w as u16)
+    }
+
+    ///
+    /// Draws text in an embedded-graphics font with `draw_font_glyph`, one
+    /// window per character. Like a styled `Text`, a `'\n'` continues at `x`
+    /// one line further down. A line ends at the first character that doesn't
+    /// fit the visible area completely.
+    ///
+    /// # Arguments
+    ///
+    /// * `x` - x coordinate of the top left corner of the first character
+    /// * `y` - y coordinate of the top left corner of the first character
+    /// * `text` - the text to draw
+    /// * `fg` - Rgb565 color of the characters
+    /// * `bg` - Rgb565 background color
+    ///
+    pub fn draw_font_text<F: Font>(&mut self, x: u16, y: u16, text: &str, fg: u16, bg: u16) -> Result<(), Error<PinE>> {
+        let mut cy = u32::from(y);
+        for line in text.split('\n') {
+            if cy + F::CHARACTER_SIZE.height > u32::from(self.size_y) {
+                break;
+            }
+            let mut cx = x;
+            for c in line.chars() {
+                match self.draw_font_glyph::<F>(cx, cy as u16, c, fg, bg)? {
+                    0 => break,
+                    w => cx += w,
+                }
+            }
+            cy += F::CHARACTER_SIZE.height;
+        }
+        Ok(())
+    }
+
+    // Clips a rectangle to the visible area, returning the inclusive window
+    // or None if nothing is left.
+    pub(crate) fn clip_to_display(&self, area: &Rectangle) -> Option<(u16, u16, u16, u16)> {
+        let sx = area.top_left.x.max(0);
+        let sy = area.top_left.y.max(0);
+        let ex = area.bottom_right.x.min(i32::from(self.size_x) - 1);
+        let ey = area.bottom_right.y.min(i32::from(self.size_y) - 1);
+        if ex < sx || ey < sy {
+            None
+        } else {
+            Some((sx as u16, sy as u16, ex as u16, ey as u16))
+        }
+    }
 }
 
 impl<DI, RST, PinE> DrawTarget<Rgb565> for ST7789<DI, RST>
@@ -39,6 +396,9 @@ where
     type Error = Error<PinE>;
 
     fn draw_pixel(&mut self, pixel: Pixel<Rgb565>) -> Result<(), Self::Error> {
+        if !self.is_visible(pixel.0) {
+            return Ok(());
+        }
         let color = RawU16::from(pixel.1).into_inner();
         let x = pixel.0.x as u16;
         let y = pixel.0.y as u16;
@@ -53,18 +413,30 @@ where
     {
         use crate::batch::DrawBatch;
 
-        self.draw_batch(item)
+        let (width, height) = (i32::from(self.size_x), i32::from(self.size_y));
+        let visible = item
+            .into_iter()
+            .filter(move |Pixel(p, _)| p.x >= 0 && p.y >= 0 && p.x < width && p.y < height);
+        self.draw_batch(visible)
     }
 
     fn draw_rectangle(
         &mut self,
         item: &Styled<Rectangle, PrimitiveStyle<Rgb565>>,
     ) -> Result<(), Self::Error> {
+        // solid rect without a border can be filled from a small buffer
+        if let (Some(color), true) = (
+            item.style.fill_color,
+            item.style.stroke_color.is_none() || item.style.stroke_width == 0,
+        ) {
+            let area = Rectangle::new(item.top_left(), item.bottom_right());
+            return self.fill_rectangle(&area, RawU16::from(color).into_inner());
+        }
         // filled rect can be rendered into frame window directly
         if item.style.fill_color.is_some() {
-            let mut colors = item.into_iter().map(|p| RawU16::from(p.1).into_inner());
+            let area = Rectangle::new(item.top_left(), item.bottom_right());
 
-            self.fill_rect(item, &mut colors)
+            self.fill_clipped(&area, item)
         } else if let Some(_color) = item.style.stroke_color {
             if item.style.stroke_width == 0 {
                 return Ok(()); // nothing to draw
@@ -86,13 +458,9 @@ where
     {
         // TODO: this is inconsistent in embedded-graphics between Rectangle and Image
         // See: https://github.com/jamwaffles/embedded-graphics/issues/182
-        let sx = item.top_left().x as u16;
-        let sy = item.top_left().y as u16;
-        let ex = (item.bottom_right().x - 1) as u16;
-        let ey = (item.bottom_right().y - 1) as u16;
-        let colors = item.into_iter().map(|p| RawU16::from(p.1).into_inner());
+        let area = Rectangle::new(item.top_left(), item.bottom_right() - Point::new(1, 1));
 
-        self.set_pixels(sx, sy, ex, ey, colors)
+        self.fill_clipped(&area, item)
     }
 
     fn size(&self) -> Size {
@@ -103,9 +471,173 @@ where
     where
         Self: Sized,
     {
-	let pixelcount = self.size_x * self.size_y;
-        let colors = core::iter::repeat(RawU16::from(color).into_inner()).take(pixelcount.into()); // blank entire HW RAM contents
-        self.set_pixels(0, 0, self.size_x, self.size_y, colors)
+        self.fill_rect(0, 0, self.size_x, self.size_y, RawU16::from(color).into_inner())
+    }
+}
+
+///
+/// A DrawTarget that collects horizontally adjacent pixels of a row and sends
+/// them as a single window, instead of one window per pixel. The pending run
+/// is flushed when a pixel doesn't continue it (e.g. on a new row), when it
+/// reaches `N` pixels, on `flush` and on drop. Errors during the flush on drop
+/// are ignored, call `flush` to see them.
+///
+/// Pixels outside the visible area are dropped.
+///
+pub struct RowBuffered<'a, DI, RST, const N: usize>
+where
+    DI: WriteOnlyDataCommand,
+    RST: OutputPin,
+{
+    display: &'a mut ST7789<DI, RST>,
+    colors: [u16; N],
+    len: usize,
+    x: u16,
+    y: u16,
+}
+
+impl<'a, DI, RST, PinE, const N: usize> RowBuffered<'a, DI, RST, N>
+where
+    DI: WriteOnlyDataCommand,
+    RST: OutputPin<Error = PinE>,
+{
+    ///
+    /// Creates a row buffered draw target on top of the display.
+    ///
+    /// # Arguments
+    ///
+    /// * `display` - the display to draw to
+    ///
+    pub fn new(display: &'a mut ST7789<DI, RST>) -> Self {
+        Self {
+            display,
+            colors: [0; N],
+            len: 0,
+            x: 0,
+            y: 0,
+        }
+    }
+
+    ///
+    /// Sends the pending run of pixels, if any.
+    ///
+    pub fn flush(&mut self) -> Result<(), Error<PinE>> {
+        if self.len == 0 {
+            return Ok(());
+        }
+        let len = self.len;
+        self.len = 0;
+        let ex = self.x + len as u16 - 1;
+        self.display
+            .set_pixels(self.x, self.y, ex, self.y, self.colors[..len].iter().copied())
+    }
+}
+
+impl<DI, RST, PinE, const N: usize> DrawTarget<Rgb565> for RowBuffered<'_, DI, RST, N>
+where
+    DI: WriteOnlyDataCommand,
+    RST: OutputPin<Error = PinE>,
+{
+    type Error = Error<PinE>;
+
+    fn draw_pixel(&mut self, pixel: Pixel<Rgb565>) -> Result<(), Self::Error> {
+        if !self.display.is_visible(pixel.0) {
+            return Ok(());
+        }
+        let x = pixel.0.x as u16;
+        let y = pixel.0.y as u16;
+        let continues = self.len > 0 && y == self.y && x == self.x + self.len as u16;
+        if !continues || self.len == N {
+            self.flush()?;
+            self.x = x;
+            self.y = y;
+        }
+        if N == 0 {
+            // nothing can be buffered, send the pixel right away
+            return self.display.draw_pixel(pixel);
+        }
+        self.colors[self.len] = RawU16::from(pixel.1).into_inner();
+        self.len += 1;
+        Ok(())
+    }
+
+    fn size(&self) -> Size {
+        DrawTarget::<Rgb565>::size(self.display)
+    }
+}
+
+impl<DI, RST, const N: usize> Drop for RowBuffered<'_, DI, RST, N>
+where
+    DI: WriteOnlyDataCommand,
+    RST: OutputPin,
+{
+    fn drop(&mut self) {
+        let _ = self.flush();
+    }
+}
+
+// Length of the row runs buffered by `ST7789::draw_bounded`
+const BOUNDED_RUN: usize = 64;
+
+// Drops pixels outside a rectangle before handing them to a row buffer.
+struct Bounded<'a, 'b, DI, RST>
+where
+    DI: WriteOnlyDataCommand,
+    RST: OutputPin,
+{
+    inner: &'b mut RowBuffered<'a, DI, RST, BOUNDED_RUN>,
+    bounds: Rectangle,
+}
+
+impl<DI, RST, PinE> DrawTarget<Rgb565> for Bounded<'_, '_, DI, RST>
+where
+    DI: WriteOnlyDataCommand,
+    RST: OutputPin<Error = PinE>,
+{
+    type Error = Error<PinE>;
+
+    fn draw_pixel(&mut self, pixel: Pixel<Rgb565>) -> Result<(), Self::Error> {
+        let Point { x, y } = pixel.0;
+        let (tl, br) = (self.bounds.top_left, self.bounds.bottom_right);
+        if x < tl.x || y < tl.y || x > br.x || y > br.y {
+            return Ok(());
+        }
+        self.inner.draw_pixel(pixel)
+    }
+
+    fn size(&self) -> Size {
+        DrawTarget::<Rgb565>::size(&*self.inner)
+    }
+}
+
+impl<DI, RST, PinE> ST7789<DI, RST>
+where
+    DI: WriteOnlyDataCommand,
+    RST: OutputPin<Error = PinE>,
+{
+    ///
+    /// Draws an embedded-graphics item known to lie within `bounds`, sending
+    /// horizontal runs of its pixels in one window each (see `RowBuffered`)
+    /// instead of one window per pixel. Pixels outside `bounds` or the
+    /// visible area are dropped. Only a run of up to 64 pixels is buffered,
+    /// so this works for widgets of any size, and is fastest for items drawn
+    /// row by row such as filled shapes, images and text.
+    ///
+    /// # Arguments
+    ///
+    /// * `bounds` - rectangle containing everything the item draws
+    /// * `item` - the item to draw
+    ///
+    pub fn draw_bounded<T>(&mut self, bounds: &Rectangle, item: T) -> Result<(), Error<PinE>>
+    where
+        T: Drawable<Rgb565>,
+    {
+        let mut buffered = RowBuffered::<DI, RST, BOUNDED_RUN>::new(self);
+        item.draw(&mut Bounded {
+            inner: &mut buffered,
+            bounds: *bounds,
+        })?;
+        buffered.flush()
     }
 }
 
@@ -126,3 +658,200 @@ where
 		self.blit_pixels(sx, sy, ex, ey, data)
 	}
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::instruction::Instruction::*;
+    use crate::mock::initialized;
+    use crate::{window_from_rect, RowBuffered, FILL_CHUNK};
+    use embedded_graphics::pixelcolor::Rgb565;
+    use embedded_graphics::prelude::*;
+    use embedded_graphics::primitives::{Line, Rectangle};
+    use embedded_graphics::style::PrimitiveStyle;
+
+    #[test]
+    fn scanlines_render_a_gradient_row_by_row() {
+        let mut display = initialized();
+        let mut line = [Rgb565::BLACK; 8];
+        let area = Rectangle::new(Point::new(1, 2), Point::new(4, 4));
+        display
+            .render_scanlines(&area, &mut line, |y, line| {
+                for (x, pixel) in line.iter_mut().enumerate() {
+                    *pixel = Rgb565::new(y as u8, x as u8, 0);
+                }
+            })
+            .unwrap();
+        assert_eq!(display.di.count(RAMWR), 1);
+        assert_eq!(display.di.window(), (41, 55, 44, 57));
+        for y in 2..=4 {
+            for x in 0..4 {
+                assert_eq!(display.di.at(1 + x, y), y << 11 | x << 5);
+            }
+        }
+    }
+
+    #[test]
+    fn fills_exceeding_the_display_write_only_the_intersection() {
+        let mut display = initialized();
+        let style = PrimitiveStyle::with_fill(Rgb565::RED);
+        Rectangle::new(Point::new(-5, -5), Point::new(10, 10)).into_styled(style).draw(&mut display).unwrap();
+        assert_eq!(display.di.window(), (40, 53, 50, 63));
+        assert_eq!(display.di.pixel_data().len(), 11 * 11);
+        Rectangle::new(Point::new(230, 130), Point::new(300, 200)).into_styled(style).draw(&mut display).unwrap();
+        assert_eq!(display.di.window(), (270, 183, 279, 187));
+        assert_eq!(display.di.pixel(280, 183), 0);
+        assert_eq!(display.di.pixel(279, 188), 0);
+
+        display.di.clear();
+        Rectangle::new(Point::new(-9, 0), Point::new(-1, 5)).into_styled(style).draw(&mut display).unwrap();
+        Pixel(Point::new(240, 0), Rgb565::RED).draw(&mut display).unwrap();
+        assert!(display.di.log.is_empty());
+    }
+
+    #[test]
+    fn row_buffer_flushes_a_line_once() {
+        let mut display = initialized();
+        let mut buffered = RowBuffered::<_, _, 64>::new(&mut display);
+        Line::new(Point::new(3, 4), Point::new(12, 4))
+            .into_styled(PrimitiveStyle::with_stroke(Rgb565::RED, 1))
+            .draw(&mut buffered)
+            .unwrap();
+        // the run is pending until the buffer is dropped
+        drop(buffered);
+        assert_eq!(display.di.count(RAMWR), 1);
+        assert_eq!(display.di.window(), (43, 57, 52, 57));
+        assert_eq!(display.di.pixel_data(), [0xf800; 10]);
+    }
+
+    #[test]
+    fn solid_fills_reuse_a_small_buffer() {
+        let mut display = initialized();
+        Rectangle::new(Point::new(0, 0), Point::new(99, 9))
+            .into_styled(PrimitiveStyle::with_fill(Rgb565::BLUE))
+            .draw(&mut display)
+            .unwrap();
+        let sizes = display.di.data_sizes();
+        // window parameters, then the pixels in whole buffers
+        assert_eq!(sizes.len(), 4 + 1000usize.div_ceil(FILL_CHUNK));
+        assert!(sizes[4..].iter().all(|&size| size <= FILL_CHUNK * 2));
+        assert_eq!(sizes[4..].iter().sum::<usize>(), 2000);
+        assert_eq!(display.di.pixel_data(), [0x001f; 1000]);
+    }
+
+    #[test]
+    fn rectangles_convert_to_inclusive_windows() {
+        let top_left = Point::new(2, 3);
+        let rect = Rectangle::new(top_left, top_left + Size::new(4, 2) - Point::new(1, 1));
+        assert_eq!(window_from_rect(&rect), Some((2, 3, 5, 4)));
+        assert_eq!(window_from_rect(&Rectangle::new(Point::new(5, 5), Point::new(5, 5))), Some((5, 5, 5, 5)));
+        assert_eq!(window_from_rect(&Rectangle::new(Point::new(5, 5), Point::new(4, 5))), None);
+        assert_eq!(window_from_rect(&Rectangle::new(Point::new(-1, 0), Point::new(4, 5))), None);
+
+        let mut display = initialized();
+        display.set_pixels_rectangle(&rect, 1..=8).unwrap();
+        assert_eq!(display.di.window(), (42, 56, 45, 57));
+        assert_eq!(display.di.pixel_data(), [1, 2, 3, 4, 5, 6, 7, 8]);
+    }
+
+    #[test]
+    fn bounded_drawing_sends_one_window_per_row() {
+        let mut display = initialized();
+        let style = PrimitiveStyle::with_fill(Rgb565::RED);
+        let rect = Rectangle::new(Point::new(2, 2), Point::new(5, 4)).into_styled(style);
+        display.draw_bounded(&Rectangle::new(Point::new(0, 0), Point::new(3, 10)), &rect).unwrap();
+        assert_eq!(display.di.count(RAMWR), 3);
+        assert_eq!(display.di.window(), (42, 57, 43, 57));
+        for y in 2..=4 {
+            assert_eq!([1, 2, 3, 4].map(|x| display.di.at(x, y)), [0, 0xf800, 0xf800, 0]);
+        }
+        assert_eq!(display.di.at(2, 5), 0);
+    }
+
+    #[test]
+    fn clipped_fill_writes_only_the_intersection() {
+        let mut display = initialized();
+        let rect = Rectangle::new(Point::new(-5, -5), Point::new(10, 10));
+        let clip = Rectangle::new(Point::new(2, 3), Point::new(20, 6));
+        display.fill_rect_clipped(&rect, &clip, 7).unwrap();
+        assert_eq!(display.di.window(), (42, 56, 50, 59));
+        assert_eq!(display.di.count(RAMWR), 1);
+
+        // and to the visible area
+        let rect = Rectangle::new(Point::new(230, 0), Point::new(300, 10));
+        let clip = Rectangle::new(Point::new(0, 0), Point::new(500, 5));
+        display.fill_rect_clipped(&rect, &clip, 7).unwrap();
+        assert_eq!(display.di.window(), (270, 53, 279, 58));
+
+        display.di.clear();
+        let clip = Rectangle::new(Point::new(20, 20), Point::new(30, 30));
+        display.fill_rect_clipped(&rect, &clip, 7).unwrap();
+        assert!(display.di.log.is_empty());
+    }
+
+    #[test]
+    fn font_glyphs_are_drawn_as_one_window() {
+        use embedded_graphics::fonts::{Font, Font6x8};
+
+        let mut display = initialized();
+        assert_eq!(display.draw_font_glyph::<Font6x8>(10, 5, 'A', 0xffff, 0x0001).unwrap(), 6);
+        assert_eq!(display.di.commands(), [CASET, RASET, RAMWR].map(|c| c as u8));
+        assert_eq!(display.di.window(), (50, 58, 55, 65));
+        assert_eq!(display.di.pixel_data().len(), 6 * 8);
+        for row in 0..8 {
+            for col in 0..6 {
+                let expected = if Font6x8::character_pixel('A', col, row) { 0xffff } else { 0x0001 };
+                assert_eq!(display.di.at(10 + col as u16, 5 + row as u16), expected);
+            }
+        }
+
+        // one window per character, and nothing for a cell past the edge
+        display.di.clear();
+        display.draw_font_text::<Font6x8>(0, 0, "Hi\nA", 0xffff, 0).unwrap();
+        assert_eq!(display.di.count(RAMWR), 3);
+        assert_eq!(display.draw_font_glyph::<Font6x8>(235, 0, 'A', 0xffff, 0).unwrap(), 0);
+        assert_eq!(display.di.count(RAMWR), 3);
+    }
+
+    #[test]
+    fn mono_images_map_the_bits_and_skip_the_row_padding() {
+        use embedded_graphics::image::ImageRaw;
+        use embedded_graphics::pixelcolor::BinaryColor;
+
+        // 3x2, the padding bits of the second row set
+        let icon: ImageRaw<BinaryColor> = ImageRaw::new(&[0b1010_0000, 0b0101_1111], 3, 2);
+        let (on, off) = (Rgb565::RED, Rgb565::BLUE);
+        let (r, b) = (0xf800, 0x001f);
+        let mut display = initialized();
+        display.draw_mono_image(&icon, Point::new(10, 10), on, off).unwrap();
+        assert_eq!(display.di.count(RAMWR), 1);
+        assert_eq!(display.di.window(), (50, 63, 52, 64));
+        assert_eq!(display.di.pixel_data(), [r, b, r, b, r, b]);
+
+        // clipped at the left and bottom edges, only the visible part is sent
+        display.di.clear();
+        display.draw_mono_image(&icon, Point::new(-1, 134), on, off).unwrap();
+        assert_eq!(display.di.window(), (40, 187, 41, 187));
+        assert_eq!(display.di.pixel_data(), [b, r]);
+    }
+
+    #[test]
+    fn borders_fill_up_to_four_rectangles_around_the_content() {
+        let mut display = initialized();
+        display.fill_border(Rectangle::new(Point::new(10, 10), Point::new(19, 19)), 1).unwrap();
+        assert_eq!(display.di.count(RAMWR), 4);
+        let corners = [(0, 0), (239, 134), (9, 15), (20, 15), (15, 9), (15, 20)];
+        assert!(corners.iter().all(|&(x, y)| display.di.at(x, y) == 1));
+        assert_eq!((display.di.at(10, 10), display.di.at(19, 19)), (0, 0));
+
+        // sides touching the edges are left out
+        let count = |content: Rectangle| {
+            let mut display = initialized();
+            display.fill_border(content, 1).unwrap();
+            display.di.count(RAMWR)
+        };
+        assert_eq!(count(Rectangle::new(Point::new(0, 0), Point::new(19, 19))), 2);
+        assert_eq!(count(Rectangle::new(Point::new(0, 10), Point::new(239, 19))), 2);
+        assert_eq!(count(Rectangle::new(Point::new(-5, -5), Point::new(300, 200))), 0);
+        assert_eq!(count(Rectangle::new(Point::new(300, 10), Point::new(310, 20))), 1);
+    }
+}
@@ -1,4 +1,5 @@
 /// ST7789 instructions.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 #[repr(u8)]
 pub enum Instruction {
 /* System Function Command Table 1 */
@@ -87,5 +88,218 @@ pub enum Instruction {
     PROMCTRL = 0xEC,
     PROMEN = 0xFA,
     NVMSET = 0xFC,
-    PROMACT = 0xFE
+    PROMACT = 0xFE,
+}
+
+impl Instruction {
+    ///
+    /// All instructions, in opcode table order, e.g. for tools validating or
+    /// generating init sequences. With `name` and `opcode` this gives the
+    /// (name, opcode) pairs of the instruction set.
+    ///
+    pub const ALL: &'static [Instruction] = &[
+        Self::NOP,
+        Self::SWRESET,
+        Self::RDDID,
+        Self::RDDST,
+        Self::RDDPM,
+        Self::RDDMADCTL,
+        Self::RDDCOLMOD,
+        Self::RDDIM,
+        Self::RDDSM,
+        Self::RDDSDR,
+        Self::SLPIN,
+        Self::SLPOUT,
+        Self::PTLON,
+        Self::NORON,
+        Self::INVOFF,
+        Self::INVON,
+        Self::GAMSET,
+        Self::DISPOFF,
+        Self::DISPON,
+        Self::CASET,
+        Self::RASET,
+        Self::RAMWR,
+        Self::RAMRD,
+        Self::PTLAR,
+        Self::VSCRDEF,
+        Self::TEOFF,
+        Self::TEON,
+        Self::MADCTL,
+        Self::VSCSAD,
+        Self::IDMOFF,
+        Self::IDMON,
+        Self::COLMOD,
+        Self::WRMEMC,
+        Self::RDMEMC,
+        Self::STE,
+        Self::GSCAN,
+        Self::WRDISBV,
+        Self::RDDISBV,
+        Self::WRCTRLD,
+        Self::RDCTRLD,
+        Self::WRCACE,
+        Self::RDCABC,
+        Self::WRCABCMB,
+        Self::RDCABCMB,
+        Self::RDABCSDR,
+        Self::RDID1,
+        Self::RDID2,
+        Self::RDID3,
+        Self::RAMCTRL,
+        Self::RGBCTRL,
+        Self::PORCTRL,
+        Self::FRCTRL1,
+        Self::PARCTRL,
+        Self::GCTRL,
+        Self::GTADJ,
+        Self::DGMEN,
+        Self::VCOMS,
+        Self::POWSAVE,
+        Self::DLPOFFSAVE,
+        Self::LCMCTRL,
+        Self::IDSET,
+        Self::VDVVRHEN,
+        Self::VRHS,
+        Self::VDVS,
+        Self::VCMOFSET,
+        Self::FRCTRL2,
+        Self::CABCCTRL,
+        Self::REGSEL1,
+        Self::REGSEL2,
+        Self::PWMFRSEL,
+        Self::PWCTRL1,
+        Self::VAPVANEN,
+        Self::CMD2EN,
+        Self::PVGAMCTRL,
+        Self::NVGAMCTRL,
+        Self::DGMLUTR,
+        Self::DGMLUTB,
+        Self::GATECTRL,
+        Self::SPI2EN,
+        Self::PWCTRL2,
+        Self::EQCTRL,
+        Self::PROMCTRL,
+        Self::PROMEN,
+        Self::NVMSET,
+        Self::PROMACT,
+    ];
+
+    /// The instruction's mnemonic as named in the datasheet
+    pub const fn name(self) -> &'static str {
+        use Instruction::*;
+        match self {
+            NOP => "NOP",
+            SWRESET => "SWRESET",
+            RDDID => "RDDID",
+            RDDST => "RDDST",
+            RDDPM => "RDDPM",
+            RDDMADCTL => "RDDMADCTL",
+            RDDCOLMOD => "RDDCOLMOD",
+            RDDIM => "RDDIM",
+            RDDSM => "RDDSM",
+            RDDSDR => "RDDSDR",
+            SLPIN => "SLPIN",
+            SLPOUT => "SLPOUT",
+            PTLON => "PTLON",
+            NORON => "NORON",
+            INVOFF => "INVOFF",
+            INVON => "INVON",
+            GAMSET => "GAMSET",
+            DISPOFF => "DISPOFF",
+            DISPON => "DISPON",
+            CASET => "CASET",
+            RASET => "RASET",
+            RAMWR => "RAMWR",
+            RAMRD => "RAMRD",
+            PTLAR => "PTLAR",
+            VSCRDEF => "VSCRDEF",
+            TEOFF => "TEOFF",
+            TEON => "TEON",
+            MADCTL => "MADCTL",
+            VSCSAD => "VSCSAD",
+            IDMOFF => "IDMOFF",
+            IDMON => "IDMON",
+            COLMOD => "COLMOD",
+            WRMEMC => "WRMEMC",
+            RDMEMC => "RDMEMC",
+            STE => "STE",
+            GSCAN => "GSCAN",
+            WRDISBV => "WRDISBV",
+            RDDISBV => "RDDISBV",
+            WRCTRLD => "WRCTRLD",
+            RDCTRLD => "RDCTRLD",
+            WRCACE => "WRCACE",
+            RDCABC => "RDCABC",
+            WRCABCMB => "WRCABCMB",
+            RDCABCMB => "RDCABCMB",
+            RDABCSDR => "RDABCSDR",
+            RDID1 => "RDID1",
+            RDID2 => "RDID2",
+            RDID3 => "RDID3",
+            RAMCTRL => "RAMCTRL",
+            RGBCTRL => "RGBCTRL",
+            PORCTRL => "PORCTRL",
+            FRCTRL1 => "FRCTRL1",
+            PARCTRL => "PARCTRL",
+            GCTRL => "GCTRL",
+            GTADJ => "GTADJ",
+            DGMEN => "DGMEN",
+            VCOMS => "VCOMS",
+            POWSAVE => "POWSAVE",
+            DLPOFFSAVE => "DLPOFFSAVE",
+            LCMCTRL => "LCMCTRL",
+            IDSET => "IDSET",
+            VDVVRHEN => "VDVVRHEN",
+            VRHS => "VRHS",
+            VDVS => "VDVS",
+            VCMOFSET => "VCMOFSET",
+            FRCTRL2 => "FRCTRL2",
+            CABCCTRL => "CABCCTRL",
+            REGSEL1 => "REGSEL1",
+            REGSEL2 => "REGSEL2",
+            PWMFRSEL => "PWMFRSEL",
+            PWCTRL1 => "PWCTRL1",
+            VAPVANEN => "VAPVANEN",
+            CMD2EN => "CMD2EN",
+            PVGAMCTRL => "PVGAMCTRL",
+            NVGAMCTRL => "NVGAMCTRL",
+            DGMLUTR => "DGMLUTR",
+            DGMLUTB => "DGMLUTB",
+            GATECTRL => "GATECTRL",
+            SPI2EN => "SPI2EN",
+            PWCTRL2 => "PWCTRL2",
+            EQCTRL => "EQCTRL",
+            PROMCTRL => "PROMCTRL",
+            PROMEN => "PROMEN",
+            NVMSET => "NVMSET",
+            PROMACT => "PROMACT",
+        }
+    }
+
+    /// The command byte sent for the instruction
+    pub const fn opcode(self) -> u8 {
+        self as u8
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Instruction;
+    use std::vec::Vec;
+
+    #[test]
+    fn the_list_covers_every_variant_of_the_enum() {
+        // the variants as declared, parsed from this file
+        let source = include_str!("instruction.rs");
+        let body = source.split("pub enum Instruction {").nth(1).unwrap().split('}').next().unwrap();
+        let declared: Vec<(&str, u8)> = body
+            .lines()
+            .filter_map(|line| line.trim().trim_end_matches(',').split_once(" = 0x"))
+            .map(|(name, opcode)| (name, u8::from_str_radix(opcode, 16).unwrap()))
+            .collect();
+        let listed: Vec<(&str, u8)> = Instruction::ALL.iter().map(|i| (i.name(), i.opcode())).collect();
+        assert_eq!(listed.len(), declared.len());
+        assert_eq!(listed, declared);
+    }
 }
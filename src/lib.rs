@@ -4,12 +4,41 @@
 
 //! This crate provides a ST7789 driver to connect to TFT displays.
 
+#[cfg(any(feature = "std", test))]
+extern crate std;
+
+mod backlight;
+mod calibrate;
+pub mod colors;
+mod config;
+mod delay;
+mod draw;
+mod driver;
 pub mod instruction;
+mod panel;
+mod power;
+mod scroll;
+mod source;
+mod write_batch;
+mod writer;
+
+pub use crate::backlight::{Backlight, GpioBacklight, PwmBacklight};
+pub use crate::config::{
+    known_offsets, ColorOrder, ConfigError, DisplayConfig, DisplayState, InitSequence, PanelType, PixelFormat,
+    KNOWN_OFFSETS,
+};
+pub use crate::delay::{DelayMsAdapter, NoDelay};
+pub use crate::draw::Rotation;
+pub use crate::driver::DisplayDriver;
+pub use crate::source::{Bitmap1bpp, Indexed4, Indexed8, PixelSource, Rgb565Bytes, Rgb888Bytes};
+pub use crate::write_batch::WriteBatch;
+pub use crate::writer::RamWriter;
 
 use crate::instruction::Instruction::*;
+use core::convert::TryFrom;
 use core::iter::once;
 
-use display_interface::DataFormat::{U16BEIter, U8Iter};
+use display_interface::DataFormat::{self, U16BEIter, U16LEIter, U8Iter};
 use display_interface::WriteOnlyDataCommand;
 use embedded_hal::blocking::delay::DelayUs;
 use embedded_hal::digital::v2::OutputPin;
@@ -17,12 +46,81 @@ use embedded_hal::digital::v2::OutputPin;
 #[cfg(feature = "graphics")]
 mod graphics;
 
+#[cfg(feature = "graphics")]
+pub use crate::graphics::{window_from_rect, BlitTarget, RowBuffered};
+
 #[cfg(feature = "batch")]
 mod batch;
 
+#[cfg(feature = "font")]
+pub mod font;
+
+#[cfg(feature = "read")]
+mod read;
+
+#[cfg(feature = "shadow")]
+mod shadow;
+
+#[cfg(feature = "qoi")]
+mod qoi;
+
+#[cfg(feature = "async")]
+mod blit_async;
+
+#[cfg(feature = "nvm")]
+mod nvm;
+#[cfg(feature = "nvm")]
+pub use crate::nvm::NVM_PROGRAM_US;
+
+#[cfg(feature = "read")]
+pub use crate::read::ReadableDataCommand;
+
+#[cfg(test)]
+mod mock;
+
+// Panel tuning commands sent by init, translated from the Waveshare demo code
+const PANEL_INIT: InitSequence = &[
+    (PORCTRL, &[0x0c, 0x0c, 0x00, 0x33, 0x33]), // reset default
+    (GCTRL, &[0x35]), // reset default
+    (VCOMS, &[0x19]),
+    (LCMCTRL, &[0x2c]), // reset default
+    (VDVVRHEN, &[0x01, 0xff]), // reset default
+    (VRHS, &[0x12]),
+    (VDVS, &[0x20]), // reset default
+    (FRCTRL2, &[0x0f]), // reset default
+    (PWCTRL1, &[0xa4, 0xa1]), // reset default
+    (PVGAMCTRL, &[0xd0, 0x04, 0x0d, 0x11, 0x13, 0x2b, 0x3f, 0x54, 0x4c, 0x18, 0x0d, 0x0b, 0x1f, 0x23]),
+    (NVGAMCTRL, &[0xd0, 0x04, 0x0c, 0x11, 0x13, 0x2c, 0x3f, 0x44, 0x51, 0x2f, 0x1f, 0x1f, 0x20, 0x23]),
+];
+
 ///
 /// ST7789 driver to connect to TFT displays.
 ///
+/// # Coordinates
+///
+/// All drawing methods take logical coordinates in the current orientation:
+/// (0, 0) is the top left corner as seen by the viewer and the visible area
+/// is `width()` x `height()`. Mapping them to controller RAM is split between
+/// the controller and the driver:
+///
+/// * hardware - rotation and mirroring are set up in MADCTL (row/column
+///   exchange and address order), so the controller walks its RAM in the
+///   logical direction and windows never have to be rotated in software
+/// * software - the driver swaps width and height between the landscape and
+///   portrait orientations and adds the panel offset selected for the
+///   current orientation to every window. On a mirrored axis the offset is
+///   taken from the opposite edge of RAM, as the controller counts from there
+///
+/// # Interfaces
+///
+/// The driver only talks to the controller through `WriteOnlyDataCommand`:
+/// commands go through `send_commands`, parameters and pixels through
+/// `send_data`, and the two are never mixed in one call. It never touches a
+/// DC line itself, so 3-wire SPI interfaces that mark commands and data with
+/// a 9th bit work the same way as 4-wire SPI or parallel ones, as long as the
+/// interface sends every byte of 16 bit data (`U16BEIter` / `U16LEIter`)
+/// with the data bit set.
+///
 pub struct ST7789<DI, RST>
 where
     DI: WriteOnlyDataCommand,
@@ -32,15 +130,103 @@ where
     di: DI,
     // Reset pin.
     rst: RST,
+    // Whether the reset pin is driven high to hold the controller in reset
+    reset_active_high: bool,
     // Visible size (x, y)
     size_x: u16,
     size_y: u16,
     // Offset to 'true origin' position of controller
     off_x: u16,
     off_y: u16,
-    orientation: Orientation
+    // Offsets for each orientation, indexed by `Orientation as usize`
+    offsets: [(u16, u16); 4],
+    // Extra shift of the window end coordinates for each orientation
+    end_adjust: [(i16, i16); 4],
+    // Last vertical scroll start address sent
+    scroll_offset: u16,
+    // Vertical scroll area as (top fixed rows, scrolling rows)
+    scroll_area: (u16, u16),
+    // Whether y coordinates wrap around in the scroll area
+    scroll_wrap: bool,
+    // Window last sent with CASET/RASET in RAM coordinates, if still known
+    ram_window: Option<(u16, u16, u16, u16)>,
+    // Orientation of the controller, with the upside down mounting applied
+    orientation: Orientation,
+    // Whether the panel is mounted upside down, flipping every orientation
+    upside_down: bool,
+    // Mirroring of the x and y axis on top of the orientation
+    mirror_x: bool,
+    mirror_y: bool,
+    // Whether the panel is refreshed bottom to top (ML bit of MADCTL)
+    scan_reversed: bool,
+    color_order: ColorOrder,
+    pixel_format: PixelFormat,
+    // Whether init enables display inversion
+    inverted: bool,
+    // Panel tuning commands sent by init
+    init_sequence: InitSequence,
+    // Additional attempts for failed interface writes
+    retries: u8,
+    // What to do when an operation fails
+    error_policy: ErrorPolicy,
+    // Whether sleep out was sent since the last reset, i.e. init ran
+    initialized: bool,
+    // Breadcrumbs for diagnosing interface failures
+    last_command: Option<instruction::Instruction>,
+    error_context: Option<ErrorContext>,
+    // Called between the transfers of a chunked write
+    chunk_pacing: Option<fn()>,
+    // Largest data transfer the interface accepts
+    max_transfer: Option<usize>,
+    // Waits for the interface to finish pending transfers
+    idle_wait: Option<fn(&mut DI)>,
+    // Channel gains applied to u16 colors, None for unity
+    white_balance: Option<[u8; 3]>,
+    // Whether 16bpp colors are sent low byte first (RAMCTRL ENDIAN set)
+    little_endian: bool,
+    // FRCTRL2 value sent by `set_frame_rate`, None for the init sequence's
+    frame_rate: Option<u8>,
+    // Display brightness value last written (WRDISBV)
+    brightness: u8,
+    // Command tracing callback
+    #[cfg(feature = "trace")]
+    trace: Option<TraceFn>,
+    // Pixel transfer counters
+    #[cfg(feature = "stats")]
+    stats: TransferStats,
+    // Copy of the written pixels
+    #[cfg(feature = "shadow")]
+    shadow: Option<shadow::Shadow>,
+}
+
+///
+/// Counts of pixel data transfers by kind, see `ST7789::transfer_stats`
+///
+#[cfg(feature = "stats")]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TransferStats {
+    /// Transfers of a contiguous byte slice (`DataFormat::U8`), usable with DMA
+    pub contiguous: u32,
+    /// Transfers streamed from an iterator (`DataFormat::U16BEIter`)
+    pub iterator: u32,
+}
+
+///
+/// Kind of bytes passed to the trace callback
+///
+#[cfg(feature = "trace")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TraceKind {
+    Command,
+    Data,
 }
 
+///
+/// Trace callback invoked with every command or data chunk sent to the display
+///
+#[cfg(feature = "trace")]
+pub type TraceFn = fn(TraceKind, &[u8]);
+
 ///
 /// An error holding its source (pins or SPI)
 ///
@@ -48,20 +234,177 @@ where
 pub enum Error<PinE> {
     DisplayError,
     Pin(PinE),
+    /// Coordinates outside of the addressable range
+    OutOfBounds,
+}
+
+impl<PinE: core::fmt::Debug> core::fmt::Display for Error<PinE> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Error::DisplayError => write!(f, "display interface error"),
+            Error::Pin(e) => write!(f, "pin error: {:?}", e),
+            Error::OutOfBounds => write!(f, "coordinates out of bounds"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<PinE: core::fmt::Debug> std::error::Error for Error<PinE> {}
+
+///
+/// Where the most recent interface failure happened, see `ST7789::last_error_context`
+///
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ErrorContext {
+    /// The last command sent before or while the failure happened
+    pub command: Option<instruction::Instruction>,
+    /// What was being sent when the failure happened
+    pub stage: ErrorStage,
+}
+
+///
+/// Part of a command transfer that failed
+///
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ErrorStage {
+    /// The command byte itself
+    Command,
+    /// The command parameters
+    Data,
+    /// Pixel data following RAMWR
+    Pixels,
+}
+
+///
+/// What the driver does when an operation fails with `DisplayError`, see
+/// `ST7789::set_error_policy`
+///
+#[derive(Clone, Copy, Debug, Default)]
+pub enum ErrorPolicy {
+    /// Return the error right away
+    #[default]
+    Propagate,
+    /// Reinitialize the controller with `soft_reset` and `init_commands`,
+    /// then retry the operation once. The function is the delay provider for
+    /// the reinit, called with the time to wait in microseconds.
+    Reinit(fn(u32)),
 }
 
-#[derive(Clone, Copy, PartialEq, Eq)]
+/// Number of pixels per transfer for solid fills. The pixels are encoded
+/// once into a buffer on the stack of `2 * FILL_CHUNK` bytes, which is sent
+/// repeatedly; 64 pixels (128 bytes) by default, 32 pixels (64 bytes) with
+/// the `fill-32` feature and 128 pixels (256 bytes) with `fill-128`, which
+/// wins if both are enabled. Larger buffers need fewer transfers per fill.
+pub const FILL_CHUNK: usize = if cfg!(feature = "fill-128") {
+    128
+} else if cfg!(feature = "fill-32") {
+    32
+} else {
+    64
+};
+
+/// Time `hard_reset` holds each level of the reset pulse, in microseconds
+pub const RESET_PULSE_US: u32 = 100;
+/// Time `hard_reset` waits after releasing reset before any command, in microseconds
+pub const RESET_SETTLE_US: u32 = 1_000;
+/// Time `init` waits after switching the display on (DISPON), in microseconds
+pub const DISPON_DELAY_US: u32 = 1_000;
+/// Time `soft_reset` waits after SWRESET before any command, in microseconds
+pub const SWRESET_DELAY_US: u32 = 120_000;
+
+// Size of the buffer used by `blit_from_reader`
+const READER_CHUNK: usize = 128;
+
+/// Width of the controller RAM in its native (portrait) orientation
+pub const RAM_WIDTH: u16 = 240;
+/// Height of the controller RAM in its native (portrait) orientation
+pub const RAM_HEIGHT: u16 = 320;
+
+///
+/// Display orientation
+///
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 #[repr(u8)]
-enum Orientation {
-    // Portrait
+pub enum Orientation {
     Landscape = 0,
-    // PortraitFlipped
+    Portrait = 1,
     LandscapeFlipped = 2,
+    PortraitFlipped = 3,
 }
 impl Orientation {
     fn to_madctl(self) -> u8 {
-	if self == Self::Landscape { 0x60 } else { 0xa0 }
+        match self {
+            Self::Landscape => 0x60,
+            Self::Portrait => 0x00,
+            Self::LandscapeFlipped => 0xa0,
+            Self::PortraitFlipped => 0xc0,
+        }
+    }
+
+    fn is_landscape(self) -> bool {
+        self == Self::Landscape || self == Self::LandscapeFlipped
+    }
+
+    // Orientation rotated by 180 degrees
+    fn flipped(self) -> Self {
+        match self {
+            Self::Landscape => Self::LandscapeFlipped,
+            Self::Portrait => Self::PortraitFlipped,
+            Self::LandscapeFlipped => Self::Landscape,
+            Self::PortraitFlipped => Self::Portrait,
+        }
+    }
+}
+
+// Marks an error caused by the arguments of a call rather than the interface.
+// With the `strict` feature debug builds panic here instead of returning it,
+// so misuse shows up right away during development.
+#[track_caller]
+fn misuse<PinE>(error: Error<PinE>) -> Error<PinE> {
+    #[cfg(feature = "strict")]
+    debug_assert!(false, "invalid arguments: {}", match error {
+        Error::OutOfBounds => "coordinates out of bounds",
+        _ => "buffer or parameter doesn't match the call",
+    });
+    error
+}
+
+// Everything init writes to the registers up to sleep out
+struct InitSettings {
+    madctl: u8,
+    colmod: u8,
+    sequence: InitSequence,
+    inverted: bool,
+}
+
+// Calls `write` for every command init sends before the display is switched
+// on, in order, with its parameters (empty for commands without any).
+fn for_each_init_write<E>(
+    settings: InitSettings,
+    mut write: impl FnMut(instruction::Instruction, &[u8]) -> Result<(), E>,
+) -> Result<(), E> {
+    write(MADCTL, &[settings.madctl])?;
+    write(COLMOD, &[settings.colmod])?;
+    for &(command, data) in settings.sequence {
+        write(command, data)?;
     }
+    write(if settings.inverted { INVON } else { INVOFF }, &[])?;
+    write(SLPOUT, &[])
+}
+
+// Rows of an inclusive window (sx, sy, ex, ey) of a frame buffer `width` pixels wide.
+fn tile_rows(buf: &[u16], width: usize, (sx, sy, ex, ey): (u16, u16, u16, u16)) -> impl Iterator<Item = &[u16]> {
+    (sy..=ey).map(move |y| &buf[usize::from(y) * width..][usize::from(sx)..=usize::from(ex)])
+}
+
+// Inclusive end coordinates of a non-empty rectangle, failing on u16 overflow.
+fn end_point<PinE>(sx: u16, sy: u16, dx: u16, dy: u16) -> Result<(u16, u16), Error<PinE>> {
+    let end = |s: u16, d: u16| {
+        d.checked_sub(1)
+            .and_then(|d| s.checked_add(d))
+            .ok_or_else(|| misuse(Error::OutOfBounds))
+    };
+    Ok((end(sx, dx)?, end(sy, dy)?))
 }
 
 impl<DI, RST, PinE> ST7789<DI, RST>
@@ -69,6 +412,11 @@ where
     DI: WriteOnlyDataCommand,
     RST: OutputPin<Error = PinE>,
 {
+    /// Width of the controller RAM in its native (portrait) orientation
+    pub const RAM_WIDTH: u16 = RAM_WIDTH;
+    /// Height of the controller RAM in its native (portrait) orientation
+    pub const RAM_HEIGHT: u16 = RAM_HEIGHT;
+
     ///
     /// Creates a new ST7789 driver instance
     ///
@@ -78,46 +426,367 @@ where
     /// * `rst` - display hard reset pin
     /// * `size_x` - x axis resolution of the display in pixels
     /// * `size_y` - y axis resolution of the display in pixels
+    /// * `off_x` - x offset of the visible area in controller RAM
+    /// * `off_y` - y offset of the visible area in controller RAM
+    ///
+    /// Size and offsets are given for landscape orientation. The offsets for
+    /// the other orientations are derived from them, assuming the panel is
+    /// mounted so the complementary offsets apply when mirrored. Panels that
+    /// don't follow this can be corrected with `set_offsets`.
+    ///
+    /// The configuration isn't checked; a visible area that doesn't fit into
+    /// controller RAM gives a driver that draws to the wrong places. Use
+    /// `try_new` to reject such configurations.
     ///
     pub fn new(di: DI, rst: RST, size_x: u16, size_y: u16, off_x: u16, off_y: u16) -> Self {
+        // offsets on the opposite edges of controller RAM, used when mirrored
+        let comp_x = RAM_HEIGHT.saturating_sub(size_x).saturating_sub(off_x);
+        let comp_y = RAM_WIDTH.saturating_sub(size_y).saturating_sub(off_y);
         Self {
             di,
             rst,
+            reset_active_high: false,
             size_x, size_y,
             off_x, off_y,
-            orientation: Orientation::Landscape
+            offsets: [
+                (off_x, off_y),   // Landscape
+                (comp_y, off_x),  // Portrait
+                (comp_x, comp_y), // LandscapeFlipped
+                (off_y, comp_x),  // PortraitFlipped
+            ],
+            end_adjust: [(0, 0); 4],
+            scroll_offset: 0,
+            scroll_area: (0, RAM_HEIGHT),
+            scroll_wrap: false,
+            ram_window: None,
+            orientation: Orientation::Landscape,
+            upside_down: false,
+            mirror_x: false,
+            mirror_y: false,
+            scan_reversed: false,
+            color_order: ColorOrder::Rgb,
+            pixel_format: PixelFormat::Rgb565,
+            inverted: true,
+            init_sequence: PANEL_INIT,
+            retries: 0,
+            error_policy: ErrorPolicy::Propagate,
+            initialized: false,
+            last_command: None,
+            error_context: None,
+            chunk_pacing: None,
+            max_transfer: None,
+            idle_wait: None,
+            white_balance: None,
+            little_endian: false,
+            frame_rate: None,
+            brightness: 0,
+            #[cfg(feature = "trace")]
+            trace: None,
+            #[cfg(feature = "stats")]
+            stats: TransferStats::default(),
+            #[cfg(feature = "shadow")]
+            shadow: None,
+        }
+    }
+
+    ///
+    /// Same as `new`, but fails if the visible area is empty or doesn't fit
+    /// into the 320x240 controller RAM (as seen in landscape) at the given
+    /// offsets.
+    ///
+    /// # Arguments
+    ///
+    /// * `di` - a display interface for talking with the display
+    /// * `rst` - display hard reset pin
+    /// * `size_x` - x axis resolution of the display in pixels
+    /// * `size_y` - y axis resolution of the display in pixels
+    /// * `off_x` - x offset of the visible area in controller RAM
+    /// * `off_y` - y offset of the visible area in controller RAM
+    ///
+    pub fn try_new(
+        di: DI,
+        rst: RST,
+        size_x: u16,
+        size_y: u16,
+        off_x: u16,
+        off_y: u16,
+    ) -> Result<Self, ConfigError> {
+        if size_x == 0 || size_y == 0 {
+            return Err(ConfigError::ZeroSize);
+        }
+        if size_x > RAM_HEIGHT || size_y > RAM_WIDTH {
+            return Err(ConfigError::SizeTooLarge);
+        }
+        if off_x > RAM_HEIGHT - size_x || off_y > RAM_WIDTH - size_y {
+            return Err(ConfigError::OffsetTooLarge);
+        }
+        Ok(Self::new(di, rst, size_x, size_y, off_x, off_y))
+    }
+
+    ///
+    /// Same as `new`, with the offsets looked up in `KNOWN_OFFSETS` by the
+    /// landscape resolution. Resolutions that aren't listed get offsets 0, 0;
+    /// this is correct for panels using the top left corner of the RAM, for
+    /// others the picture is shifted and `new` has to be used with the right
+    /// offsets.
+    ///
+    /// # Arguments
+    ///
+    /// * `di` - a display interface for talking with the display
+    /// * `rst` - display hard reset pin
+    /// * `size_x` - x axis resolution of the display in pixels
+    /// * `size_y` - y axis resolution of the display in pixels
+    ///
+    pub fn new_for_resolution(di: DI, rst: RST, size_x: u16, size_y: u16) -> Self {
+        let (off_x, off_y) = known_offsets(size_x, size_y).unwrap_or((0, 0));
+        Self::new(di, rst, size_x, size_y, off_x, off_y)
+    }
+
+    ///
+    /// Creates a new ST7789 driver instance like `new` and runs `init` on it,
+    /// returning the driver ready for drawing.
+    ///
+    /// # Arguments
+    ///
+    /// * `di` - a display interface for talking with the display
+    /// * `rst` - display hard reset pin
+    /// * `size_x` - x axis resolution of the display in pixels
+    /// * `size_y` - y axis resolution of the display in pixels
+    /// * `off_x` - x offset of the visible area in controller RAM
+    /// * `off_y` - y offset of the visible area in controller RAM
+    /// * `delay_source` - mutable reference to a delay provider
+    ///
+    pub fn new_initialized(
+        di: DI,
+        rst: RST,
+        size_x: u16,
+        size_y: u16,
+        off_x: u16,
+        off_y: u16,
+        delay_source: &mut impl DelayUs<u32>,
+    ) -> Result<Self, Error<PinE>> {
+        let mut display = Self::new(di, rst, size_x, size_y, off_x, off_y);
+        display.init(delay_source)?;
+        Ok(display)
+    }
+
+    ///
+    /// Creates a new ST7789 driver instance from a complete configuration,
+    /// which `init` applies to the display.
+    ///
+    /// # Arguments
+    ///
+    /// * `di` - a display interface for talking with the display
+    /// * `rst` - display hard reset pin
+    /// * `config` - size, offsets and panel settings
+    ///
+    pub fn new_with_config(di: DI, rst: RST, config: DisplayConfig) -> Self {
+        let (size_x, size_y) = config.size;
+        let (off_x, off_y) = config.offset;
+        let mut display = Self::new(di, rst, size_x, size_y, off_x, off_y);
+        display.upside_down = config.upside_down;
+        display.select_orientation(config.orientation);
+        display.color_order = config.color_order;
+        display.pixel_format = config.pixel_format;
+        display.inverted = config.inverted;
+        display.reset_active_high = config.reset_active_high;
+        if let Some(sequence) = config.init_sequence {
+            display.init_sequence = sequence;
         }
+        display
+    }
+
+    ///
+    /// Selects whether `init` enables display inversion (INVON) or disables it
+    /// (INVOFF). Defaults to enabled, which IPS panels like the Pico-LCD need;
+    /// normally white panels show inverted colors unless this is disabled.
+    ///
+    /// # Arguments
+    ///
+    /// * `inverted` - whether init sends INVON
+    ///
+    pub fn with_inversion(mut self, inverted: bool) -> Self {
+        self.inverted = inverted;
+        self
+    }
+
+    ///
+    /// Selects an upside down mounting of the panel, as in many enclosures:
+    /// every orientation is then rotated by 180 degrees, i.e. `Landscape`
+    /// is set up as `LandscapeFlipped` with that orientation's offsets and
+    /// the other way round, so drawing code stays the same for both
+    /// mountings. `set_orientation`, `display_state` and `get_orientation`
+    /// work with the orientation as seen by the viewer, while `set_offsets`
+    /// keeps taking the controller orientation. `init` applies it with
+    /// MADCTL. Defaults to the normal mounting.
+    ///
+    /// # Arguments
+    ///
+    /// * `upside_down` - whether the panel is mounted upside down
+    ///
+    pub fn with_upside_down(mut self, upside_down: bool) -> Self {
+        let orientation = self.view_orientation();
+        self.upside_down = upside_down;
+        self.select_orientation(orientation);
+        self
+    }
+
+    ///
+    /// Selects the polarity of the reset pin used by `hard_reset`. The
+    /// controller's reset is active low, so this is only needed when the pin
+    /// drives it through an inverting buffer. Defaults to active low.
+    ///
+    /// # Arguments
+    ///
+    /// * `active_high` - whether driving the pin high resets the controller
+    ///
+    pub fn with_reset_active_high(mut self, active_high: bool) -> Self {
+        self.reset_active_high = active_high;
+        self
+    }
+
+    ///
+    /// Total time in microseconds `init` and `init_clear` spend waiting on the
+    /// delay source, for startup time budgets. The time for sending the
+    /// commands and, for `init_clear`, the pixel data comes on top of this.
+    ///
+    pub const fn init_delay_total_us() -> u32 {
+        2 * RESET_PULSE_US + RESET_SETTLE_US + DISPON_DELAY_US
     }
 
     ///
     /// Runs commands to initialize the display
     ///
+    /// Debug builds panic when pixels are written before `init`, or one of
+    /// its variants, has run since the last reset, as a display that isn't
+    /// initialized stays blank or shows garbage.
+    ///
     /// # Arguments
     ///
     /// * `delay_source` - mutable reference to a delay provider
     ///
     pub fn init(&mut self, delay_source: &mut impl DelayUs<u32>) -> Result<(), Error<PinE>> {
+        self.init_with(delay_source, None)
+    }
+
+    ///
+    /// Same as `init`, but fills the visible area with `color` before the
+    /// display is switched on, so the random RAM contents are never shown.
+    ///
+    /// This adds one full-frame write to the init time, i.e. about 26ms for a
+    /// 240x135 panel on a 20MHz SPI bus.
+    ///
+    /// # Arguments
+    ///
+    /// * `delay_source` - mutable reference to a delay provider
+    /// * `color` - the Rgb565 color to fill the visible area with
+    ///
+    pub fn init_clear(&mut self, delay_source: &mut impl DelayUs<u32>, color: u16) -> Result<(), Error<PinE>> {
+        self.init_with(delay_source, Some(color))
+    }
+
+    ///
+    /// Sends all register writes of `init` without any delays, for callers
+    /// that sequence the reset and the waits themselves. `init` is equivalent
+    /// to:
+    ///
+    /// * `hard_reset`, or an own reset pulse of at least `RESET_PULSE_US`
+    ///   followed by `RESET_SETTLE_US` before the first command
+    /// * `init_commands`
+    /// * waiting `DISPON_DELAY_US` before drawing
+    ///
+    pub fn init_commands(&mut self) -> Result<(), Error<PinE>> {
+        self.write_init_settings(self.init_settings())?;
+        self.write_command(DISPON)
+    }
+
+    fn init_with(&mut self, delay_source: &mut impl DelayUs<u32>, clear: Option<u16>) -> Result<(), Error<PinE>> {
+        self.hard_reset(delay_source)?;
+        match clear {
+            Some(color) => {
+                self.write_init_settings(self.init_settings())?;
+                self.fill_rect(0, 0, self.size_x, self.size_y, color)?;
+                self.write_command(DISPON)?;
+            }
+            None => self.init_commands()?,
+        }
+        delay_source.delay_us(DISPON_DELAY_US);
+        Ok(())
+    }
+
+    ///
+    /// Same as `init`, but leaves out the init sequence, i.e. the porch,
+    /// gate, power, VCOM and gamma settings: only MADCTL, COLMOD, the
+    /// inversion, SLPOUT and DISPON are sent after the hard reset. For
+    /// modules whose maker loads tuned values into the controller's NVM,
+    /// which a reset restores and the generic sequence would overwrite.
+    ///
+    /// # Arguments
+    ///
+    /// * `delay_source` - mutable reference to a delay provider
+    ///
+    pub fn init_minimal(&mut self, delay_source: &mut impl DelayUs<u32>) -> Result<(), Error<PinE>> {
         self.hard_reset(delay_source)?;
-	self.write_command(MADCTL)?; self.write_data(&[self.orientation.to_madctl()])?;
-	self.write_command(COLMOD)?; self.write_data(&[0x55])?; // 16bpp
-	self.write_command(PORCTRL)?; self.write_data(&[0x0c, 0x0c, 0x00, 0x33, 0x33])?; // reset default
-	self.write_command(GCTRL)?; self.write_data(&[0x35])?; // reset default
-	self.write_command(VCOMS)?; self.write_data(&[0x19])?;
-	self.write_command(LCMCTRL)?; self.write_data(&[0x2c])?; // reset default
-	self.write_command(VDVVRHEN)?; self.write_data(&[0x01])?; // reset default, but 2nd data byte missing (default: 0xff)
-	self.write_command(VRHS)?; self.write_data(&[0x12])?;
-	self.write_command(VDVS)?; self.write_data(&[0x20])?; // reset default
-	self.write_command(FRCTRL2)?; self.write_data(&[0x0f])?; // reset default
-	self.write_command(PWCTRL1)?; self.write_data(&[0xa4, 0xa1])?; // reset default
-	self.write_command(PVGAMCTRL)?; self.write_data(&[0xd0, 0x04, 0x0d, 0x11, 0x13, 0x2b, 0x3f, 0x54, 0x4c, 0x18, 0x0d, 0x0b, 0x1f, 0x23])?;
-	self.write_command(NVGAMCTRL)?; self.write_data(&[0xd0, 0x04, 0x0c, 0x11, 0x13, 0x2c, 0x3f, 0x44, 0x51, 0x2f, 0x1f, 0x1f, 0x20, 0x23])?;
-	self.write_command(INVON)?;
-	self.write_command(SLPOUT)?;
-	self.write_command(DISPON)?;
-        delay_source.delay_us(1_000);
+        let settings = InitSettings {
+            sequence: &[],
+            ..self.init_settings()
+        };
+        self.write_init_settings(settings)?;
+        self.write_command(DISPON)?;
+        delay_source.delay_us(DISPON_DELAY_US);
         Ok(())
     }
 
+    // Sends the register writes of init up to and including sleep out.
+    fn write_init_settings(&mut self, settings: InitSettings) -> Result<(), Error<PinE>> {
+        for_each_init_write(settings, |command, data| {
+            self.write_command(command)?;
+            if !data.is_empty() {
+                self.write_data(data)?;
+            }
+            Ok(())
+        })
+    }
+
+    ///
+    /// Feeds every command `init` sends, with its parameters, to `sink`
+    /// without touching the display, e.g. for logging the exact bytes sent to
+    /// a panel. The commands reflect the current settings; the reset pulse,
+    /// the delays and the pixel data written by `init_clear` are not included.
+    ///
+    /// # Arguments
+    ///
+    /// * `sink` - called with each command and its parameter bytes, in order
+    ///
+    pub fn init_dump(&self, mut sink: impl FnMut(instruction::Instruction, &[u8])) {
+        let _ = for_each_init_write::<()>(self.init_settings(), |command, data| {
+            sink(command, data);
+            Ok(())
+        });
+        sink(DISPON, &[]);
+    }
+
+    // The settings init sends before the display is switched on.
+    fn init_settings(&self) -> InitSettings {
+        InitSettings {
+            madctl: self.madctl(),
+            colmod: self.pixel_format.to_colmod(),
+            sequence: self.init_sequence,
+            inverted: self.inverted,
+        }
+    }
+
+    ///
+    /// Sends a no operation command (NOP). The controller ignores it, but it
+    /// ends whatever command was being received, including an unfinished
+    /// parameter list or a memory write / read. This makes it a soft recovery
+    /// step after an aborted transfer, before resending the last command or
+    /// falling back to a hard reset, and a harmless bus keep-alive.
+    ///
+    pub fn nop(&mut self) -> Result<(), Error<PinE>> {
+        self.write_command(NOP)
+    }
+
     pub fn shutdown(&mut self) -> Result<(), Error<PinE>> {
 	self.write_command(SLPIN)?;
         Ok(())
@@ -131,18 +800,60 @@ where
     /// * `delay_source` - mutable reference to a delay provider
     ///
     pub fn hard_reset(&mut self, delay_source: &mut impl DelayUs<u32>) -> Result<(), Error<PinE>> {
-        self.rst.set_high().map_err(Error::Pin)?;
-        delay_source.delay_us(100); // ensure the pin change will get registered
-        self.rst.set_low().map_err(Error::Pin)?;
-        delay_source.delay_us(100); // ensure the pin change will get registered
-        self.rst.set_high().map_err(Error::Pin)?;
-        delay_source.delay_us(1000); // ensure the pin change will get registered
+        self.set_reset(false)?;
+        delay_source.delay_us(RESET_PULSE_US); // ensure the pin change will get registered
+        self.set_reset(true)?;
+        delay_source.delay_us(RESET_PULSE_US); // ensure the pin change will get registered
+        self.set_reset(false)?;
+        delay_source.delay_us(RESET_SETTLE_US); // ensure the pin change will get registered
+        self.reset_state();
 
         Ok(())
     }
 
     ///
-    /// Sets a pixel color at the given coords.
+    /// Performs a software reset (SWRESET). All registers go back to their
+    /// reset defaults like with `hard_reset`, but the reset pin isn't needed;
+    /// follow it with `init_commands`. Waits `SWRESET_DELAY_US`, as the
+    /// controller ignores commands for 5ms after the reset and sleep out
+    /// mustn't follow it within 120ms.
+    ///
+    /// # Arguments
+    ///
+    /// * `delay_source` - mutable reference to a delay provider
+    ///
+    pub fn soft_reset(&mut self, delay_source: &mut impl DelayUs<u32>) -> Result<(), Error<PinE>> {
+        self.write_command(SWRESET)?;
+        delay_source.delay_us(SWRESET_DELAY_US);
+        self.reset_state();
+        Ok(())
+    }
+
+    // Forgets the controller state a reset returns to its defaults.
+    fn reset_state(&mut self) {
+        self.scroll_offset = 0; // reset default
+        self.scroll_area = (0, RAM_HEIGHT);
+        self.ram_window = None;
+        self.frame_rate = None;
+        self.brightness = 0; // reset default
+        self.little_endian = false; // RAMCTRL reset default
+        self.initialized = false;
+    }
+
+    // Drives the reset pin to hold (true) or release (false) the controller
+    // in reset, honoring the configured polarity.
+    fn set_reset(&mut self, reset: bool) -> Result<(), Error<PinE>> {
+        if reset == self.reset_active_high {
+            self.rst.set_high().map_err(Error::Pin)
+        } else {
+            self.rst.set_low().map_err(Error::Pin)
+        }
+    }
+
+    ///
+    /// Sets a pixel color at the given coords, in the current orientation.
+    /// Pixels outside the visible area are ignored, unless y wraps around in
+    /// the scroll area (see `set_scroll_wrap`).
     ///
     /// # Arguments
     ///
@@ -151,17 +862,33 @@ where
     /// * `color` - the Rgb565 color value
     ///
     pub fn set_pixel(&mut self, x: u16, y: u16, color: u16) -> Result<(), Error<PinE>> {
+        if x >= self.size_x {
+            return Ok(());
+        }
+        if let Some(region) = self.wrap_region()? {
+            return self.fill_rect_wrapped(x, y, 1, 1, color, region);
+        }
+        if y >= self.size_y {
+            return Ok(());
+        }
         self.set_address_window(x, y, x, y)?;
         self.write_command(RAMWR)?;
-        self.di
-            .send_data(U16BEIter(&mut once(color)))
-            .map_err(|_| Error::DisplayError)?;
-
-        Ok(())
+        self.write_pixels(once(color))
     }
 
     ///
-    /// Sets pixel colors in given rectangle bounds.
+    /// Sets pixel colors in given rectangle bounds. The end coordinates are
+    /// inclusive; a rectangle with `ex < sx` or `ey < sy` is empty and
+    /// nothing is sent.
+    ///
+    /// The colors are pulled from the iterator one at a time while they are
+    /// sent, so lazy sources such as a frame buffer in external memory are
+    /// read on demand and never copied or collected. Every item is taken
+    /// exactly once and in order; the iterator doesn't need to be `Clone`
+    /// and isn't asked for its length. With a transfer size limit (see
+    /// `set_max_transfer_size`) the first color of the next part is fetched
+    /// right after a part is sent, to see whether another one follows. After
+    /// an interface error the remaining colors are left unread.
     ///
     /// # Arguments
     ///
@@ -182,88 +909,1552 @@ where
     where
         T: IntoIterator<Item = u16>,
     {
+        if ex < sx || ey < sy {
+            return Ok(()); // empty rectangle
+        }
         self.set_address_window(sx, sy, ex, ey)?;
         self.write_command(RAMWR)?;
-        self.di
-            .send_data(U16BEIter(&mut colors.into_iter()))
-            .map_err(|_| Error::DisplayError)
+        self.write_pixels(colors)
     }
 
     ///
-    /// Blits raw pixel data to the display. The burden of choosing the correct
-    /// pixel format is completely on the caller - on the other hand, this is
-    /// probably the only way to get acceptable (or *any*, for that matter)
-    /// DMA performance.
+    /// Sets pixel colors of a rectangle `width` pixels wide, with the height
+    /// taken from the number of colors: `colors` fills the rows top to bottom
+    /// as for `set_pixels`, without computing the end coordinates. Fails with
+    /// `DisplayError` if the number of colors isn't a multiple of `width`.
+    /// No colors are a no-op.
+    ///
+    /// # Arguments
+    ///
+    /// * `x` - x coordinate start
+    /// * `y` - y coordinate start
+    /// * `width` - width of the rectangle
+    /// * `colors` - Rgb565 colors, row by row
+    ///
+    pub fn set_pixels_wrapped<T>(&mut self, x: u16, y: u16, width: u16, colors: T) -> Result<(), Error<PinE>>
+    where
+        T: ExactSizeIterator<Item = u16>,
+    {
+        let len = colors.len();
+        if len == 0 {
+            return Ok(());
+        }
+        if width == 0 || !len.is_multiple_of(width.into()) {
+            return Err(misuse(Error::DisplayError));
+        }
+        let height = u16::try_from(len / usize::from(width)).map_err(|_| misuse(Error::OutOfBounds))?;
+        let (ex, ey) = end_point(x, y, width, height)?;
+        self.set_pixels(x, y, ex, ey, colors)
+    }
+
+    ///
+    /// Sets pixel colors in given rectangle bounds, column by column: the
+    /// colors fill the first column top to bottom, then the next one
+    /// to the right, and so on.
+    ///
+    /// This temporarily toggles the row/column exchange (MV) bit of MADCTL so
+    /// the controller advances down the columns; MADCTL is restored
+    /// afterwards. Coordinates are in the current orientation, mirroring is
+    /// honored.
     ///
     /// # Arguments
     ///
     /// * `sx` - x coordinate start
     /// * `sy` - y coordinate start
-    /// * `dx` - width
-    /// * `dy` - height
-    /// * `data` - u8 slice containing raw pixel data
+    /// * `ex` - x coordinate end
+    /// * `ey` - y coordinate end
+    /// * `colors` - anything that can provide `IntoIterator<Item = u16>` to iterate over pixel data
     ///
-    pub fn blit_pixels(
+    pub fn set_pixels_columnwise<T>(
         &mut self,
         sx: u16,
         sy: u16,
-        dx: u16,
-        dy: u16,
-        data: &[u8]
-    ) -> Result<(), Error<PinE>> {
-        use display_interface::DataFormat::U8;
+        ex: u16,
+        ey: u16,
+        colors: T,
+    ) -> Result<(), Error<PinE>>
+    where
+        T: IntoIterator<Item = u16>,
+    {
+        if ex < sx || ey < sy {
+            return Ok(()); // empty rectangle
+        }
+        let (sx0, sy0, ex0, ey0) = self.ram_window(sx, sy, ex, ey)?;
 
-	if data.len() != (dx*dy*2) as usize {
-		return Err(Error::DisplayError);
-	}
-        self.set_address_window(sx, sy, sx+dx-1, sy+dy-1)?;
+        // with MV toggled, CASET addresses what RASET did before and vice versa
+        self.write_command(MADCTL)?;
+        self.write_data(&[self.madctl() ^ 0x20])?;
+        self.write_ram_window(sy0, sx0, ey0, ex0)?;
+        #[cfg(feature = "shadow")]
+        if let Some(shadow) = &mut self.shadow {
+            shadow.set_window(Some((sx, sy, ex, ey)), true);
+        }
         self.write_command(RAMWR)?;
-        self.di.send_data(U8(data)).map_err(|_| Error::DisplayError)
+        self.write_pixels(colors)?;
+        self.write_command(MADCTL)?;
+        self.write_data(&[self.madctl()])
     }
 
     ///
-    /// Sets scroll offset "shifting" the displayed picture
+    /// Fills a rectangle with a single color. The coordinates are in the
+    /// current orientation and the rectangle is clipped to the visible area;
+    /// nothing is sent if it is empty. With `set_scroll_wrap` enabled, y
+    /// wraps around in the scroll area instead of being clipped.
+    ///
     /// # Arguments
     ///
-    /// * `offset` - scroll offset in pixels
+    /// * `x` - x coordinate start
+    /// * `y` - y coordinate start
+    /// * `w` - width
+    /// * `h` - height
+    /// * `color` - the Rgb565 color value
     ///
-    pub fn set_scroll_offset(&mut self, offset: u16) -> Result<(), Error<PinE>> {
-        self.write_command(VSCSAD)?;
-        self.write_data(&offset.to_be_bytes())
+    pub fn fill_rect(&mut self, x: u16, y: u16, w: u16, h: u16, color: u16) -> Result<(), Error<PinE>> {
+        self.reinit_on_error(|display| display.fill_rect_once(x, y, w, h, color))
     }
 
-    ///
-    /// Release resources allocated to this driver back.
-    /// This returns the display interface and the RST pin deconstructing the driver.
-    ///
+    fn fill_rect_once(&mut self, x: u16, y: u16, w: u16, h: u16, color: u16) -> Result<(), Error<PinE>> {
+        if let Some(region) = self.wrap_region()? {
+            let w = w.min(self.size_x.saturating_sub(x));
+            if w == 0 || h == 0 {
+                return Ok(());
+            }
+            return self.fill_rect_wrapped(x, y, w, h, color, region);
+        }
+        if x >= self.size_x || y >= self.size_y {
+            return Ok(());
+        }
+        let w = w.min(self.size_x - x);
+        let h = h.min(self.size_y - y);
+        if w == 0 || h == 0 {
+            return Ok(());
+        }
+        self.set_address_window(x, y, x + w - 1, y + h - 1)?;
+        self.write_command(RAMWR)?;
+        self.write_solid(color, w as usize * h as usize)
+    }
+
+    ///
+    /// Fills a rectangle of the given size centered on the visible area.
+    ///
+    /// # Arguments
+    ///
+    /// * `w` - width
+    /// * `h` - height
+    /// * `color` - the Rgb565 color value
+    ///
+    pub fn fill_rect_centered(&mut self, w: u16, h: u16, color: u16) -> Result<(), Error<PinE>> {
+        let x = self.size_x.saturating_sub(w) / 2;
+        let y = self.size_y.saturating_sub(h) / 2;
+        self.fill_rect(x, y, w, h, color)
+    }
+
+    ///
+    /// Blits raw pixel data to the display. The burden of choosing the correct
+    /// pixel format is completely on the caller - on the other hand, this is
+    /// probably the only way to get acceptable (or *any*, for that matter)
+    /// DMA performance.
+    ///
+    /// A zero width or height is a no-op, `data` must be empty then.
+    ///
+    /// # Arguments
+    ///
+    /// * `sx` - x coordinate start
+    /// * `sy` - y coordinate start
+    /// * `dx` - width
+    /// * `dy` - height
+    /// * `data` - u8 slice containing raw pixel data
+    ///
+    pub fn blit_pixels(
+        &mut self,
+        sx: u16,
+        sy: u16,
+        dx: u16,
+        dy: u16,
+        data: &[u8]
+    ) -> Result<(), Error<PinE>> {
+        let (ex, ey) = match self.blit_window(sx, sy, dx, dy, data.len())? {
+            Some(end) => end,
+            None => return Ok(()), // empty rectangle
+        };
+        self.reinit_on_error(|display| {
+            // a failed data transfer leaves the RAM pointer in an unknown
+            // place, so retries restart with the address window
+            let mut retries = display.retries;
+            loop {
+                display.start_blit(sx, sy, ex, ey)?;
+                match display.write_pixel_bytes(data) {
+                    Err(Error::DisplayError) if retries > 0 => retries -= 1,
+                    result => return result,
+                }
+            }
+        })
+    }
+
+    ///
+    /// Runs the checks of `blit_pixels` without sending anything and returns
+    /// the inclusive window `(sx, sy, ex, ey)` it would write, or `None` for
+    /// an empty rectangle. `data` must hold exactly `dx * dy` pixels in the
+    /// active pixel format and the window must not exceed the u16 coordinate
+    /// range; like `blit_pixels`, windows reaching past the visible area are
+    /// not rejected. With the `strict` feature invalid arguments panic in
+    /// debug builds, as they would in the blit.
+    ///
+    /// # Arguments
+    ///
+    /// * `sx` - x coordinate start
+    /// * `sy` - y coordinate start
+    /// * `dx` - width
+    /// * `dy` - height
+    /// * `data` - u8 slice containing raw pixel data
+    ///
+    pub fn prepare_blit(
+        &self,
+        sx: u16,
+        sy: u16,
+        dx: u16,
+        dy: u16,
+        data: &[u8],
+    ) -> Result<Option<(u16, u16, u16, u16)>, Error<PinE>> {
+        let end = self.blit_window(sx, sy, dx, dy, data.len())?;
+        Ok(end.map(|(ex, ey)| (sx, sy, ex, ey)))
+    }
+
+    ///
+    /// Same as `blit_pixels`, but splits the pixel data into transfers of at
+    /// most `max_chunk` bytes, for DMA setups that can't handle one large
+    /// transfer. Every chunk after the first is started with a memory write
+    /// continue (WRMEMC), so the pixels stay contiguous. Chunks are rounded
+    /// down to whole pixels.
+    ///
+    /// # Arguments
+    ///
+    /// * `sx` - x coordinate start
+    /// * `sy` - y coordinate start
+    /// * `dx` - width
+    /// * `dy` - height
+    /// * `data` - u8 slice containing raw pixel data
+    /// * `max_chunk` - maximum number of bytes per transfer
+    ///
+    pub fn blit_pixels_chunked(
+        &mut self,
+        sx: u16,
+        sy: u16,
+        dx: u16,
+        dy: u16,
+        data: &[u8],
+        max_chunk: usize,
+    ) -> Result<(), Error<PinE>> {
+        let unit = self.pixel_format.unit_bytes();
+        if data.len() != self.data_len(dx as usize * dy as usize) || max_chunk < unit {
+            return Err(misuse(Error::DisplayError));
+        }
+        if dx == 0 || dy == 0 {
+            return Ok(()); // empty rectangle
+        }
+        let (ex, ey) = end_point(sx, sy, dx, dy)?;
+        self.set_address_window(sx, sy, ex, ey)?;
+        self.write_pixel_chunks(data, max_chunk - max_chunk % unit)
+    }
+
+    ///
+    /// Same as `blit_pixels`, but pulls the raw pixel data from `reader`
+    /// instead of a buffer in RAM, e.g. from external flash. The data is read
+    /// into a 128 byte buffer and sent in whole pixels, every transfer after
+    /// the first continued with WRMEMC.
+    ///
+    /// `reader` fills the start of the given buffer and returns the number of
+    /// bytes read. Reading 0 bytes before `dx * dy` pixels have been received
+    /// or a reader error fail with `DisplayError`.
+    ///
+    /// # Arguments
+    ///
+    /// * `sx` - x coordinate start
+    /// * `sy` - y coordinate start
+    /// * `dx` - width
+    /// * `dy` - height
+    /// * `reader` - source of the raw pixel data
+    ///
+    pub fn blit_from_reader(
+        &mut self,
+        sx: u16,
+        sy: u16,
+        dx: u16,
+        dy: u16,
+        mut reader: impl FnMut(&mut [u8]) -> Result<usize, ()>,
+    ) -> Result<(), Error<PinE>> {
+        if dx == 0 || dy == 0 {
+            return Ok(()); // empty rectangle
+        }
+        let (ex, ey) = end_point(sx, sy, dx, dy)?;
+        let unit = self.pixel_format.unit_bytes();
+        let mut remaining = self.data_len(dx as usize * dy as usize);
+        let mut buf = [0u8; READER_CHUNK];
+        let mut filled = 0;
+        let mut first = true;
+        self.set_address_window(sx, sy, ex, ey)?;
+        while remaining > 0 {
+            let end = READER_CHUNK.min(filled + remaining);
+            let read = reader(&mut buf[filled..end]).map_err(|_| Error::DisplayError)?;
+            if read == 0 || read > end - filled {
+                return Err(Error::DisplayError);
+            }
+            filled += read;
+            remaining -= read;
+            // a pixel split by WRMEMC would be garbled, keep the partial one
+            // (only the end of a 12bpp run may hold a single pixel)
+            let whole = if remaining == 0 { filled } else { filled - filled % unit };
+            if whole > 0 {
+                self.write_pixel_chunk(first, &buf[..whole])?;
+                first = false;
+                buf.copy_within(whole..filled, 0);
+                filled -= whole;
+            }
+        }
+        Ok(())
+    }
+
+    ///
+    /// Same as `blit_pixels`, but for Rgb565 data stored little endian (low
+    /// byte first). The byte pairs are swapped while streaming, so no copy of
+    /// the buffer is needed; this goes through the iterator path though and
+    /// can't use DMA.
+    ///
+    /// # Arguments
+    ///
+    /// * `sx` - x coordinate start
+    /// * `sy` - y coordinate start
+    /// * `dx` - width
+    /// * `dy` - height
+    /// * `data` - u8 slice containing little endian Rgb565 pixel data
+    ///
+    pub fn blit_pixels_le(
+        &mut self,
+        sx: u16,
+        sy: u16,
+        dx: u16,
+        dy: u16,
+        data: &[u8]
+    ) -> Result<(), Error<PinE>> {
+        if self.pixel_format != PixelFormat::Rgb565 || data.len() != dx as usize * dy as usize * 2 {
+            return Err(misuse(Error::DisplayError));
+        }
+        if dx == 0 || dy == 0 {
+            return Ok(()); // empty rectangle
+        }
+        let (ex, ey) = end_point(sx, sy, dx, dy)?;
+        let colors = data.chunks_exact(2).map(|p| u16::from_le_bytes([p[0], p[1]]));
+        self.set_pixels(sx, sy, ex, ey, colors)
+    }
+
+    ///
+    /// Same as `blit_pixels`, but with coordinates in controller RAM space:
+    /// the panel offsets are not applied, so regions outside the visible
+    /// area (e.g. the off-screen part used by vertical scrolling) can be written.
+    ///
+    /// # Arguments
+    ///
+    /// * `sx` - x coordinate start in controller RAM
+    /// * `sy` - y coordinate start in controller RAM
+    /// * `dx` - width
+    /// * `dy` - height
+    /// * `data` - u8 slice containing raw pixel data
+    ///
+    pub fn blit_pixels_raw(
+        &mut self,
+        sx: u16,
+        sy: u16,
+        dx: u16,
+        dy: u16,
+        data: &[u8]
+    ) -> Result<(), Error<PinE>> {
+        if data.len() != self.data_len(dx as usize * dy as usize) {
+            return Err(misuse(Error::DisplayError));
+        }
+        if dx == 0 || dy == 0 {
+            return Ok(()); // empty rectangle
+        }
+        let (ex, ey) = end_point(sx, sy, dx, dy)?;
+        self.set_address_window_raw(sx, sy, ex, ey)?;
+        self.write_command(RAMWR)?;
+        self.write_pixel_bytes(data)
+    }
+
+    ///
+    /// Sets the address window in controller RAM space, without applying the
+    /// panel offsets. The coordinates still follow the current orientation
+    /// (MADCTL), as the controller maps them. All end coordinates are inclusive.
+    ///
+    /// Like all windowed writes of the driver, this skips CASET/RASET when
+    /// the controller still holds the same window, e.g. for repeated full
+    /// screen blits. The driver forgets the window on `hard_reset` and when
+    /// CASET, RASET or SWRESET is sent via `send_raw_command`.
+    ///
+    /// # Arguments
+    ///
+    /// * `sx` - x coordinate start in controller RAM
+    /// * `sy` - y coordinate start in controller RAM
+    /// * `ex` - x coordinate end in controller RAM
+    /// * `ey` - y coordinate end in controller RAM
+    ///
+    pub fn set_address_window_raw(&mut self, sx: u16, sy: u16, ex: u16, ey: u16) -> Result<(), Error<PinE>> {
+        self.write_ram_window(sx, sy, ex, ey)
+    }
+
+    ///
+    /// Resets the address window to the whole visible area, so a following
+    /// RAMWR starts at the top left corner.
+    ///
+    /// Every windowed write leaves the window and RAM pointer where it ended.
+    /// The driver's own draw methods always set a fresh window, so this is
+    /// only needed before writing pixels via `send_raw_command` and
+    /// `send_raw_data`.
+    ///
+    pub fn reset_ram_pointer(&mut self) -> Result<(), Error<PinE>> {
+        if self.size_x == 0 || self.size_y == 0 {
+            return Ok(());
+        }
+        self.set_address_window(0, 0, self.size_x - 1, self.size_y - 1)
+    }
+
+    ///
+    /// Fills the entire 240x320 controller RAM with a solid color, ignoring
+    /// the panel offsets. This also clears the parts of RAM outside the
+    /// visible area, which would otherwise show garbage once they are
+    /// scrolled into view. Best done right after `init` and before enabling
+    /// vertical scrolling.
+    ///
+    /// # Arguments
+    ///
+    /// * `color` - the Rgb565 color to fill with
+    ///
+    pub fn clear_full_ram(&mut self, color: u16) -> Result<(), Error<PinE>> {
+        // with row/column exchange the columns run along the long side
+        let (cols, rows) = if self.madctl() & 0x20 != 0 {
+            (RAM_HEIGHT, RAM_WIDTH)
+        } else {
+            (RAM_WIDTH, RAM_HEIGHT)
+        };
+        self.write_ram_window(0, 0, cols - 1, rows - 1)?;
+        self.write_command(RAMWR)?;
+        self.write_solid(color, cols as usize * rows as usize)
+    }
+
+    ///
+    /// Blits a sub-rectangle of a larger raw pixel buffer to the display, reading
+    /// each row `stride` pixels after the previous one. Like `blit_pixels` the
+    /// data must already be in the display's pixel format.
+    ///
+    /// # Arguments
+    ///
+    /// * `sx` - x coordinate start
+    /// * `sy` - y coordinate start
+    /// * `dx` - width
+    /// * `dy` - height
+    /// * `data` - u8 slice containing raw pixel data, starting at the first pixel to send
+    /// * `stride` - distance between the starts of two rows in `data`, in pixels
+    ///
+    pub fn blit_pixels_strided(
+        &mut self,
+        sx: u16,
+        sy: u16,
+        dx: u16,
+        dy: u16,
+        data: &[u8],
+        stride: usize,
+    ) -> Result<(), Error<PinE>> {
+        if dx == 0 || dy == 0 {
+            return Ok(());
+        }
+        let format = self.pixel_format;
+        let (row_len, stride_len) = match (format.byte_offset(dx.into()), format.byte_offset(stride)) {
+            (Some(row_len), Some(stride_len)) => (row_len, stride_len),
+            _ => return Err(misuse(Error::DisplayError)), // rows not byte aligned
+        };
+        if stride < dx as usize || data.len() < (dy as usize - 1) * stride_len + row_len {
+            return Err(misuse(Error::DisplayError));
+        }
+        let (ex, ey) = end_point(sx, sy, dx, dy)?;
+        self.set_address_window(sx, sy, ex, ey)?;
+        self.write_command(RAMWR)?;
+        for row in data.chunks(stride_len).take(dy as usize) {
+            self.write_pixel_bytes(&row[..row_len])?;
+        }
+        Ok(())
+    }
+
+    ///
+    /// Writes a full frame of raw pixel data, starting the transfer only once
+    /// `wait_te` reports the tearing effect signal. The address window is set
+    /// up beforehand, so only RAMWR and the pixel data follow the TE edge.
+    ///
+    /// Requires the tearing effect output to be enabled via `set_tearing_effect`.
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - u8 slice containing raw pixel data for the whole visible area
+    /// * `wait_te` - polled until it returns true, e.g. by reading the TE pin
+    ///
+    pub fn present_synced(
+        &mut self,
+        data: &[u8],
+        mut wait_te: impl FnMut() -> bool,
+    ) -> Result<(), Error<PinE>> {
+        if data.len() != self.framebuffer_len() {
+            return Err(misuse(Error::DisplayError));
+        }
+        if data.is_empty() {
+            return Ok(());
+        }
+        self.set_address_window(0, 0, self.size_x - 1, self.size_y - 1)?;
+        while !wait_te() {}
+        self.write_command(RAMWR)?;
+        self.write_pixel_bytes(data)
+    }
+
+    ///
+    /// Writes a full frame without tearing: enables the tearing effect
+    /// output (V-blank only), waits for the start of the next vertical
+    /// blanking period and only then starts one contiguous transfer of the
+    /// whole frame, so the controller's scan follows behind the write.
+    ///
+    /// The TE pin of the panel has to be wired to an input of the MCU, whose
+    /// level `te` reports; not all modules bring it out. The start is taken
+    /// from a rising edge, so a blanking period that is already running is
+    /// skipped. The result is only tear free if the transfer is faster than
+    /// the panel's scan of the frame, i.e. takes less than
+    /// `frame_period_us`, otherwise the scan overtakes the write.
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - u8 slice containing raw pixel data for the whole visible area
+    /// * `te` - returns whether the TE pin is high
+    ///
+    pub fn present_tear_free(&mut self, data: &[u8], mut te: impl FnMut() -> bool) -> Result<(), Error<PinE>> {
+        self.set_tearing_effect(true)?;
+        let mut seen_low = false;
+        self.present_synced(data, || {
+            let high = te();
+            let edge = high && seen_low;
+            seen_low |= !high;
+            edge
+        })
+    }
+
+    ///
+    /// Writes a full frame from a buffer of Rgb565 colors in native byte
+    /// order, e.g. a `u16` frame buffer on a little endian MCU. The colors
+    /// are streamed through the interface as 16 bit data, which puts them on
+    /// the bus in the byte order the controller expects (see
+    /// `set_little_endian_pixels`), so the buffer never has to be converted.
+    ///
+    /// # Arguments
+    ///
+    /// * `buf` - the colors of the whole visible area, row by row
+    ///
+    pub fn present_u16(&mut self, buf: &[u16]) -> Result<(), Error<PinE>> {
+        if buf.len() != self.size_x as usize * self.size_y as usize {
+            return Err(misuse(Error::DisplayError));
+        }
+        if buf.is_empty() {
+            return Ok(());
+        }
+        self.set_pixels(0, 0, self.size_x - 1, self.size_y - 1, buf.iter().copied())
+    }
+
+    ///
+    /// Writes the changes between two full frames, e.g. the old and new
+    /// buffer of a double-buffered UI: both are compared in tiles of
+    /// `tile` x `tile` pixels (smaller at the right and bottom edges) and
+    /// only the tiles of `new` that differ are sent, each as its own window
+    /// like `present_u16`. Mostly static screens so cost a few small
+    /// transfers instead of a full frame. Fails with `DisplayError` if a
+    /// buffer doesn't hold the whole visible area or `tile` is 0.
+    ///
+    /// # Arguments
+    ///
+    /// * `new` - the colors of the frame to show, row by row
+    /// * `old` - the colors of the frame currently shown, row by row
+    /// * `tile` - width and height of the tiles
+    ///
+    pub fn present_diff(&mut self, new: &[u16], old: &[u16], tile: u16) -> Result<(), Error<PinE>> {
+        let (width, height) = (usize::from(self.size_x), usize::from(self.size_y));
+        if new.len() != width * height || old.len() != new.len() || tile == 0 {
+            return Err(misuse(Error::DisplayError));
+        }
+        for sy in (0..self.size_y).step_by(tile.into()) {
+            let ey = sy.saturating_add(tile - 1).min(self.size_y - 1);
+            for sx in (0..self.size_x).step_by(tile.into()) {
+                let ex = sx.saturating_add(tile - 1).min(self.size_x - 1);
+                let window = (sx, sy, ex, ey);
+                if tile_rows(new, width, window).eq(tile_rows(old, width, window)) {
+                    continue;
+                }
+                self.set_pixels(sx, sy, ex, ey, tile_rows(new, width, window).flatten().copied())?;
+            }
+        }
+        Ok(())
+    }
+
+    ///
+    /// Enables or disables the tearing effect output line (V-blank only).
+    ///
+    /// # Arguments
+    ///
+    /// * `enable` - whether the TE line should be driven
+    ///
+    pub fn set_tearing_effect(&mut self, enable: bool) -> Result<(), Error<PinE>> {
+        if enable {
+            self.write_command(TEON)?;
+            self.write_data(&[0x00])
+        } else {
+            self.write_command(TEOFF)
+        }
+    }
+
+    ///
+    /// Sets scroll offset "shifting" the displayed picture
+    ///
+    /// The offset is the controller RAM row shown at the start of the scroll
+    /// area and must lie within the area set with `set_scroll_area`, i.e. in
+    /// `top_fixed..320 - bottom_fixed`, or `0..320` if no area was set.
+    /// Other offsets garble the picture, so they fail with `OutOfBounds`
+    /// without sending anything.
+    ///
+    /// # Arguments
+    ///
+    /// * `offset` - scroll offset in pixels
+    ///
+    pub fn set_scroll_offset(&mut self, offset: u16) -> Result<(), Error<PinE>> {
+        let (top, height) = self.scroll_area;
+        if offset < top || offset - top >= height {
+            return Err(misuse(Error::OutOfBounds));
+        }
+        self.write_command(VSCSAD)?;
+        self.write_data(&offset.to_be_bytes())?;
+        self.scroll_offset = offset;
+        Ok(())
+    }
+
+    ///
+    /// Returns the scroll offset last set with `set_scroll_offset`, 0 after
+    /// construction or a hard reset.
+    ///
+    pub fn scroll_offset(&self) -> u16 {
+        self.scroll_offset
+    }
+
+    ///
+    /// Release resources allocated to this driver back.
+    /// This returns the display interface and the RST pin deconstructing the driver.
+    ///
     pub fn release(self) -> (DI, RST) {
         (self.di, self.rst)
     }
 
-    pub fn get_orientation(&self) -> u8 {
-        self.orientation as _
+    ///
+    /// Holds the controller in reset (RST low, or high with
+    /// `with_reset_active_high`) until the next `hard_reset` or `init`,
+    /// which minimizes its current draw. The picture is lost.
+    ///
+    pub fn enter_reset(&mut self) -> Result<(), Error<PinE>> {
+        self.set_reset(true)
+    }
+
+    ///
+    /// Same as `release`, but holds the controller in reset first (see
+    /// `enter_reset`) for a clean power down; the pin keeps that level after
+    /// the release. To keep the driver if the pin fails, call `enter_reset`
+    /// and `release` separately.
+    ///
+    pub fn release_in_reset(mut self) -> Result<(DI, RST), Error<PinE>> {
+        self.enter_reset()?;
+        Ok(self.release())
+    }
+
+    ///
+    /// Same as `release`, but also returns the current display settings, e.g.
+    /// for handing the SPI bus to another device for a while. The driver can
+    /// be rebuilt in the same state with `new_with_config` and the original
+    /// configuration, followed by `restore_state`; the display itself keeps
+    /// its registers and RAM as long as it isn't reset, so `init` isn't needed.
+    ///
+    /// The driver doesn't own a backlight pin, so there is none to return.
+    /// Settings outside of `DisplayState` (offset overrides, scroll state,
+    /// hooks) have to be reapplied by the caller.
+    ///
+    pub fn release_full(self) -> (DI, RST, DisplayState) {
+        let state = self.display_state();
+        (self.di, self.rst, state)
+    }
+
+    ///
+    /// Returns the number of bytes per pixel of the active pixel format:
+    /// 2 for Rgb565 and 3 for Rgb666. Raw pixel data passed to the blit
+    /// methods must use this many bytes per pixel. Rgb444 packs two pixels
+    /// into 3 bytes, for it this returns the rounded up 2; use `data_len` to
+    /// size buffers.
+    ///
+    pub fn bytes_per_pixel(&self) -> usize {
+        self.pixel_format.bytes_per_pixel()
+    }
+
+    ///
+    /// Returns the number of bytes of raw pixel data for `pixels` pixels in
+    /// the active pixel format, as expected by `blit_pixels`.
+    ///
+    /// # Arguments
+    ///
+    /// * `pixels` - number of pixels
+    ///
+    pub fn data_len(&self, pixels: usize) -> usize {
+        self.pixel_format.data_len(pixels)
+    }
+
+    ///
+    /// Returns the number of bytes a buffer covering the whole visible area
+    /// must have to be accepted by `blit_pixels`.
+    ///
+    /// The byte count depends on the configured pixel format.
+    ///
+    pub fn framebuffer_len(&self) -> usize {
+        self.data_len(self.size_x as usize * self.size_y as usize)
+    }
+
+    ///
+    /// Returns the visible width in the current orientation.
+    ///
+    pub fn width(&self) -> u16 {
+        self.size_x
+    }
+
+    ///
+    /// Returns the visible height in the current orientation.
+    ///
+    pub fn height(&self) -> u16 {
+        self.size_y
+    }
+
+    ///
+    /// Returns the center of the visible area in the current orientation.
+    ///
+    pub fn center(&self) -> (u16, u16) {
+        (self.size_x / 2, self.size_y / 2)
+    }
+
+    ///
+    /// Maps a point in logical coordinates (current orientation, as taken by
+    /// the drawing methods) to the physical controller RAM position it is
+    /// stored at, as (column, row) in the native portrait layout of
+    /// `RAM_WIDTH` x `RAM_HEIGHT`. This applies the offsets, the rotation and
+    /// the mirroring; the scroll offset is not taken into account.
+    ///
+    /// Returns `None` for points outside the visible area.
+    ///
+    /// # Arguments
+    ///
+    /// * `x` - logical x coordinate
+    /// * `y` - logical y coordinate
+    ///
+    pub fn to_physical(&self, x: u16, y: u16) -> Option<(u16, u16)> {
+        if x >= self.size_x || y >= self.size_y {
+            return None;
+        }
+        let (off_x, off_y) = self.window_offsets();
+        let (ax, ay) = (off_x.checked_add(x)?, off_y.checked_add(y)?);
+        let madctl = self.madctl();
+        // with MV set the column address selects the RAM row
+        let (col, row) = if madctl & 0x20 != 0 { (ay, ax) } else { (ax, ay) };
+        let col = if madctl & 0x40 != 0 { (RAM_WIDTH - 1).checked_sub(col)? } else { col };
+        let row = if madctl & 0x80 != 0 { (RAM_HEIGHT - 1).checked_sub(row)? } else { row };
+        if col >= RAM_WIDTH || row >= RAM_HEIGHT {
+            return None;
+        }
+        Some((col, row))
+    }
+
+    ///
+    /// The inverse of `to_physical`: maps a physical controller RAM position,
+    /// e.g. from a touch controller mounted in the panel's native portrait
+    /// orientation, to logical coordinates in the current orientation.
+    ///
+    /// Returns `None` for positions outside the visible area.
+    ///
+    /// # Arguments
+    ///
+    /// * `col` - RAM column, 0 to `RAM_WIDTH - 1`
+    /// * `row` - RAM row, 0 to `RAM_HEIGHT - 1`
+    ///
+    pub fn to_logical(&self, col: u16, row: u16) -> Option<(u16, u16)> {
+        if col >= RAM_WIDTH || row >= RAM_HEIGHT {
+            return None;
+        }
+        let madctl = self.madctl();
+        let col = if madctl & 0x40 != 0 { RAM_WIDTH - 1 - col } else { col };
+        let row = if madctl & 0x80 != 0 { RAM_HEIGHT - 1 - row } else { row };
+        let (ax, ay) = if madctl & 0x20 != 0 { (row, col) } else { (col, row) };
+        let (off_x, off_y) = self.window_offsets();
+        let (x, y) = (ax.checked_sub(off_x)?, ay.checked_sub(off_y)?);
+        if x >= self.size_x || y >= self.size_y {
+            return None;
+        }
+        Some((x, y))
+    }
+
+    pub fn get_orientation(&self) -> u8 {
+        self.view_orientation() as _
+    }
+
+    pub fn flip_view(&mut self) -> Result<(), Error<PinE>> {
+        self.set_orientation(self.view_orientation().flipped())
+    }
+
+    ///
+    /// Sets the display orientation, selecting the matching offsets and
+    /// swapping the visible size when switching between landscape and portrait.
+    ///
+    /// # Arguments
+    ///
+    /// * `orientation` - the new orientation
+    ///
+    pub fn set_orientation(&mut self, orientation: Orientation) -> Result<(), Error<PinE>> {
+        self.select_orientation(orientation);
+        self.write_command(MADCTL)?;
+        self.write_data(&[self.madctl()])
+    }
+
+    ///
+    /// Mirrors the picture along the x and/or y axis of the current orientation.
+    /// Offsets are recomputed so the mirrored picture still lands in the
+    /// visible area.
+    ///
+    /// # Arguments
+    ///
+    /// * `mirror_x` - mirror horizontally (reverse x addressing)
+    /// * `mirror_y` - mirror vertically (reverse y addressing)
+    ///
+    pub fn set_mirror(&mut self, mirror_x: bool, mirror_y: bool) -> Result<(), Error<PinE>> {
+        self.mirror_x = mirror_x;
+        self.mirror_y = mirror_y;
+        self.write_command(MADCTL)?;
+        self.write_data(&[self.madctl()])
+    }
+
+    ///
+    /// Sets the order in which the panel's lines are refreshed (the ML bit of
+    /// MADCTL), independent of orientation and mirroring: `true` refreshes
+    /// from the last gate line to the first. RAM addressing isn't affected,
+    /// so drawing coordinates stay the same. On modules whose gate lines are
+    /// wired in reverse this makes the refresh, and with it hardware
+    /// scrolling, run in the expected direction again.
+    ///
+    /// # Arguments
+    ///
+    /// * `reversed` - whether lines are refreshed bottom to top
+    ///
+    pub fn set_scan_direction(&mut self, reversed: bool) -> Result<(), Error<PinE>> {
+        self.scan_reversed = reversed;
+        self.write_command(MADCTL)?;
+        self.write_data(&[self.madctl()])
+    }
+
+    ///
+    /// Enables or disables display inversion.
+    ///
+    /// # Arguments
+    ///
+    /// * `inverted` - whether colors are inverted (INVON)
+    ///
+    pub fn set_inversion(&mut self, inverted: bool) -> Result<(), Error<PinE>> {
+        self.inverted = inverted;
+        self.write_command(if inverted { INVON } else { INVOFF })
+    }
+
+    ///
+    /// Sets the color component order of the panel (the BGR bit of MADCTL).
+    ///
+    /// # Arguments
+    ///
+    /// * `color_order` - the order of the color components
+    ///
+    pub fn set_color_order(&mut self, color_order: ColorOrder) -> Result<(), Error<PinE>> {
+        self.color_order = color_order;
+        self.write_command(MADCTL)?;
+        self.write_data(&[self.madctl()])
+    }
+
+    ///
+    /// Returns the current display settings, to be reapplied later with `restore_state`.
+    ///
+    pub fn display_state(&self) -> DisplayState {
+        DisplayState {
+            orientation: self.view_orientation(),
+            color_order: self.color_order,
+            inverted: self.inverted,
+            mirror: (self.mirror_x, self.mirror_y),
+            pixel_format: self.pixel_format,
+            little_endian: self.little_endian,
+            initialized: self.initialized,
+        }
+    }
+
+    ///
+    /// Restores settings captured by `display_state`, reissuing MADCTL, COLMOD
+    /// and the inversion command. The driver also takes over the byte order
+    /// of the pixel data, which the controller keeps in RAMCTRL, and whether
+    /// the controller was initialized, so a display handed over with
+    /// `release_full` can be drawn to again without another `init`.
+    ///
+    /// # Arguments
+    ///
+    /// * `state` - the settings to restore
+    ///
+    pub fn restore_state(&mut self, state: DisplayState) -> Result<(), Error<PinE>> {
+        self.select_orientation(state.orientation);
+        self.color_order = state.color_order;
+        self.mirror_x = state.mirror.0;
+        self.mirror_y = state.mirror.1;
+        self.pixel_format = state.pixel_format;
+        self.little_endian = state.little_endian;
+        self.initialized = state.initialized;
+        self.write_command(MADCTL)?;
+        self.write_data(&[self.madctl()])?;
+        self.write_command(COLMOD)?;
+        self.write_data(&[state.pixel_format.to_colmod()])?;
+        self.set_inversion(state.inverted)
+    }
+
+    ///
+    /// Overrides the offsets used for the given orientation, for panels whose
+    /// visible area isn't placed symmetrically in controller RAM.
+    ///
+    /// # Arguments
+    ///
+    /// * `orientation` - orientation the offsets apply to
+    /// * `off_x` - x offset of the visible area in controller RAM
+    /// * `off_y` - y offset of the visible area in controller RAM
+    ///
+    pub fn set_offsets(&mut self, orientation: Orientation, off_x: u16, off_y: u16) {
+        self.offsets[orientation as usize] = (off_x, off_y);
+        if orientation == self.orientation {
+            self.off_x = off_x;
+            self.off_y = off_y;
+        }
+    }
+
+    ///
+    /// Shifts the end coordinates of every address window in the given
+    /// orientation, on top of the offset applied to both start and end. This
+    /// is for panels whose visible area doesn't match a plain shift of the
+    /// window, which otherwise show a stray row or column at the far edge.
+    /// Defaults to no adjustment.
+    ///
+    /// # Arguments
+    ///
+    /// * `orientation` - orientation the adjustment applies to
+    /// * `adjust_x` - added to the x end coordinate (CASET end in landscape)
+    /// * `adjust_y` - added to the y end coordinate
+    ///
+    pub fn set_end_adjust(&mut self, orientation: Orientation, adjust_x: i16, adjust_y: i16) {
+        self.end_adjust[orientation as usize] = (adjust_x, adjust_y);
+    }
+
+    ///
+    /// Sets how often a failed command or data write is repeated before the
+    /// error is returned. `blit_pixels` repeats the whole transfer, including
+    /// the address window. Pixel data streamed from iterators can't be
+    /// replayed and is never retried. Defaults to 0.
+    ///
+    /// # Arguments
+    ///
+    /// * `retries` - number of additional attempts
+    ///
+    pub fn set_retry_count(&mut self, retries: u8) {
+        self.retries = retries;
+    }
+
+    ///
+    /// Sets what `blit_pixels` and `fill_rect` do when they fail with
+    /// `DisplayError` after any retries (see `set_retry_count`). With
+    /// `ErrorPolicy::Reinit` the controller is reinitialized and the
+    /// operation repeated once, so a transient glitch on a marginal
+    /// connection doesn't leave the display in an unknown state; if the
+    /// reinit or the repeat fails too, the original error is returned.
+    ///
+    /// The reinit soft resets the controller, which returns the scroll offset
+    /// and area, the pixel byte order (RAMCTRL), brightness, frame rate and
+    /// partial and idle mode to their defaults; the driver forgets its copies
+    /// of them as well. Settings changed from the defaults have to be applied
+    /// again and the visible area redrawn after a reinit. Defaults to
+    /// `ErrorPolicy::Propagate`.
+    ///
+    /// # Arguments
+    ///
+    /// * `policy` - the error policy
+    ///
+    pub fn set_error_policy(&mut self, policy: ErrorPolicy) {
+        self.error_policy = policy;
+    }
+
+    ///
+    /// Returns which command and transfer stage the most recent interface
+    /// failure happened in, or `None` if no write has failed yet. The context
+    /// is kept until the next failure.
+    ///
+    pub fn last_error_context(&self) -> Option<ErrorContext> {
+        self.error_context
+    }
+
+    ///
+    /// Sends a single command byte to the display, without any parameters.
+    ///
+    /// # Arguments
+    ///
+    /// * `command` - the instruction to send
+    ///
+    pub fn send_raw_command(&mut self, command: instruction::Instruction) -> Result<(), Error<PinE>> {
+        self.write_command(command)
+    }
+
+    ///
+    /// Passes data straight through to the display interface in the given
+    /// format, e.g. a contiguous `U16` slice where that is cheapest for the
+    /// interface.
+    ///
+    /// The data is interpreted as parameters of the most recently sent
+    /// command, so this must follow a command taking data, such as RAMWR sent
+    /// via `send_raw_command` after the address window has been set. The
+    /// driver doesn't track what is sent here, and the bytes bypass the trace
+    /// callback.
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - the data to send
+    ///
+    pub fn send_raw_data(&mut self, data: DataFormat<'_>) -> Result<(), Error<PinE>> {
+        self.di.send_data(data).map_err(|_| Error::DisplayError)
+    }
+
+    ///
+    /// Returns the bytes of a Rgb565 color in the byte order selected with
+    /// `set_little_endian_pixels`, for building raw pixel data for
+    /// `blit_pixels` that matches how the driver sends u16 colors. The white
+    /// balance isn't applied.
+    ///
+    /// # Arguments
+    ///
+    /// * `color` - the Rgb565 color
+    ///
+    pub fn color_bytes(&self, color: u16) -> [u8; 2] {
+        self.encode_pixel(color)
+    }
+
+    ///
+    /// Sets a software white balance for the u16 colors the driver sends
+    /// (`set_pixel`, `set_pixels`, fills and all other methods taking u16
+    /// colors), to correct a panel's color cast without tuning its
+    /// registers. Each channel is scaled by its gain / 255 with
+    /// `colors::white_balance`, so gains of 255 leave the colors unchanged
+    /// and disable the correction. Raw pixel data passed to `blit_pixels` and
+    /// friends is sent as it is. Defaults to unity gains.
+    ///
+    /// # Arguments
+    ///
+    /// * `r_gain` - red gain
+    /// * `g_gain` - green gain
+    /// * `b_gain` - blue gain
+    ///
+    pub fn set_white_balance(&mut self, r_gain: u8, g_gain: u8, b_gain: u8) {
+        let gains = [r_gain, g_gain, b_gain];
+        self.white_balance = if gains == [u8::MAX; 3] { None } else { Some(gains) };
+    }
+
+    ///
+    /// Selects the byte order of 16bpp colors the driver generates itself
+    /// (`set_pixel`, `set_pixels`, fills and all other methods taking u16
+    /// colors). This only tells the driver how the controller is set up, the
+    /// controller itself is switched with the ENDIAN bit of `set_ram_control`;
+    /// both must agree or red and blue come out swapped with parts of green.
+    /// Raw pixel data passed to `blit_pixels` and friends is sent as it is and
+    /// must already be in the controller's byte order. Defaults to big endian,
+    /// the controller's reset setting, and returns to it with every reset.
+    ///
+    /// # Arguments
+    ///
+    /// * `little_endian` - whether colors are sent low byte first
+    ///
+    pub fn set_little_endian_pixels(&mut self, little_endian: bool) {
+        self.little_endian = little_endian;
+    }
+
+    ///
+    /// Limits the size of single pixel data transfers, for display interfaces
+    /// that can't send more at once (e.g. bridges with a small buffer). Pixel
+    /// data above the limit is split transparently, continuing every further
+    /// part with a memory write continue (WRMEMC), after the hook set with
+    /// `set_chunk_pacing`. The limit is rounded down to whole pixels. Command
+    /// parameters are always sent in one transfer of at most 14 bytes (the
+    /// gamma tables). `None` removes the limit.
+    ///
+    /// # Arguments
+    ///
+    /// * `max_transfer` - maximum number of bytes per transfer
+    ///
+    pub fn set_max_transfer_size(&mut self, max_transfer: Option<usize>) {
+        self.max_transfer = max_transfer;
+    }
+
+    ///
+    /// Installs a hook for `wait_idle` that blocks until the interface has
+    /// finished all pending transfers, e.g. by waiting for the DMA channel
+    /// and the SPI busy flag. `None` makes `wait_idle` a no-op.
+    ///
+    /// # Arguments
+    ///
+    /// * `idle_wait` - waits on the interface until it is idle
+    ///
+    pub fn set_idle_wait(&mut self, idle_wait: Option<fn(&mut DI)>) {
+        self.idle_wait = idle_wait;
+    }
+
+    ///
+    /// Blocks until the display interface is idle, using the hook installed
+    /// with `set_idle_wait` (a no-op without one).
+    ///
+    /// The driver only orders its writes by calling the interface one after
+    /// the other. With a blocking interface every write is on the bus before
+    /// the next one starts, but an interface that returns while a DMA transfer
+    /// is still running can let the next command overtake it. Call this
+    /// after a blit that may still be in flight and before:
+    ///
+    /// * anything changing how the pixel data is interpreted (`set_orientation`,
+    ///   `set_mirror`, `restore_state` or `set_pixels_columnwise`)
+    /// * sleep, display off and power mode commands (`shutdown` and similar)
+    /// * `release`, or using the bus for another device
+    ///
+    pub fn wait_idle(&mut self) {
+        if let Some(idle_wait) = self.idle_wait {
+            idle_wait(&mut self.di);
+        }
+    }
+
+    ///
+    /// Installs a hook called between the transfers of a split pixel write,
+    /// e.g. a short busy wait to space out bursts of bus activity in EMC
+    /// sensitive designs. It runs before every memory write continue
+    /// (WRMEMC): between the chunks of `blit_pixels_chunked` and
+    /// `blit_from_reader`, and between the parts of any pixel write
+    /// (`blit_pixels`, `set_pixels`, fills and so on) split by
+    /// `set_max_transfer_size`. Writes that aren't split are not affected.
+    /// `None` removes the hook.
+    ///
+    /// # Arguments
+    ///
+    /// * `pacing` - called before every chunk but the first
+    ///
+    pub fn set_chunk_pacing(&mut self, pacing: Option<fn()>) {
+        self.chunk_pacing = pacing;
+    }
+
+    ///
+    /// Installs a callback receiving every command and data byte sent to the
+    /// display, or removes it when passed `None`.
+    ///
+    #[cfg(feature = "trace")]
+    pub fn set_trace(&mut self, trace: Option<TraceFn>) {
+        self.trace = trace;
+    }
+
+    ///
+    /// Returns how many pixel data transfers went through the contiguous
+    /// (DMA friendly) and the iterator path since construction or the last reset.
+    ///
+    #[cfg(feature = "stats")]
+    pub fn transfer_stats(&self) -> TransferStats {
+        self.stats
+    }
+
+    ///
+    /// Resets the pixel transfer counters.
+    ///
+    #[cfg(feature = "stats")]
+    pub fn reset_transfer_stats(&mut self) {
+        self.stats = TransferStats::default();
+    }
+
+    ///
+    /// Writes a full frame of raw pixel data like `blit_pixels` for the whole
+    /// visible area and returns how long it took, for tuning the SPI clock,
+    /// the transfer size and chunking on real hardware.
+    ///
+    /// embedded-hal has no clock trait, so the time comes from `now`, which
+    /// must read a monotonic timer, e.g. a free running microsecond counter.
+    /// The result is in units of that timer; wrap-around is handled as long
+    /// as the blit takes less than one full timer period.
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - u8 slice containing raw pixel data for the whole visible area
+    /// * `now` - returns the current timer value
+    ///
+    #[cfg(feature = "timing")]
+    pub fn time_full_frame(&mut self, data: &[u8], mut now: impl FnMut() -> u32) -> Result<u32, Error<PinE>> {
+        let start = now();
+        self.blit_pixels(0, 0, self.size_x, self.size_y, data)?;
+        Ok(now().wrapping_sub(start))
+    }
+
+    fn write_command(&mut self, command: instruction::Instruction) -> Result<(), Error<PinE>> {
+        #[cfg(feature = "trace")]
+        if let Some(trace) = self.trace {
+            trace(TraceKind::Command, &[command as u8]);
+        }
+        self.last_command = Some(command);
+        if let CASET | RASET | SWRESET = command {
+            self.ram_window = None;
+        }
+        // catch drawing before init in debug builds; a custom init sent with
+        // send_raw_command counts once it includes sleep out
+        match command {
+            SLPOUT => self.initialized = true,
+            SWRESET => self.initialized = false,
+            RAMWR | WRMEMC => debug_assert!(self.initialized, "drawing before init, call init or init_commands first"),
+            _ => {}
+        }
+        #[cfg(feature = "shadow")]
+        if let (Some(shadow), RAMWR) = (&mut self.shadow, command) {
+            shadow.restart();
+        }
+        let result = self.with_retries(|di| di.send_commands(U8Iter(&mut once(command as u8))));
+        self.note_failure(result, ErrorStage::Command)
     }
 
-    pub fn flip_view(&mut self) -> Result<(), Error<PinE>> {
-        if self.orientation == Orientation::Landscape {
-		self.orientation = Orientation::LandscapeFlipped; self.off_y -= 1;
-	} else {
-		self.orientation = Orientation::Landscape; self.off_y += 1;
-	}
-	self.write_command(MADCTL)?; self.write_data(&[self.orientation.to_madctl()])
+    fn write_data(&mut self, data: &[u8]) -> Result<(), Error<PinE>> {
+        #[cfg(feature = "trace")]
+        if let Some(trace) = self.trace {
+            trace(TraceKind::Data, data);
+        }
+        let result = self.with_retries(|di| di.send_data(U8Iter(&mut data.iter().cloned())));
+        self.note_failure(result, ErrorStage::Data)
     }
 
-    fn write_command(&mut self, command: instruction::Instruction) -> Result<(), Error<PinE>> {
-        self.di
-            .send_commands(U8Iter(&mut once(command as u8)))
-            .map_err(|_| Error::DisplayError)?;
+    // Runs an operation under the error policy, reinitializing and
+    // repeating it once if it fails with DisplayError.
+    fn reinit_on_error(
+        &mut self,
+        mut op: impl FnMut(&mut Self) -> Result<(), Error<PinE>>,
+    ) -> Result<(), Error<PinE>> {
+        let wait = match (op(self), self.error_policy) {
+            (Err(Error::DisplayError), ErrorPolicy::Reinit(wait)) => wait,
+            (result, _) => return result,
+        };
+        let reinit = self
+            .soft_reset(&mut delay::FnDelay(wait))
+            .and_then(|()| self.init_commands());
+        if reinit.is_err() {
+            return Err(Error::DisplayError);
+        }
+        wait(DISPON_DELAY_US);
+        op(self).map_err(|_| Error::DisplayError)
+    }
+
+    // Records where a failed write happened, for `last_error_context`.
+    fn note_failure(&mut self, result: Result<(), Error<PinE>>, stage: ErrorStage) -> Result<(), Error<PinE>> {
+        if result.is_err() {
+            self.error_context = Some(ErrorContext {
+                command: self.last_command,
+                stage,
+            });
+        }
+        result
+    }
+
+    // Runs an interface operation, repeating it up to `retries` times on failure.
+    fn with_retries(
+        &mut self,
+        mut op: impl FnMut(&mut DI) -> Result<(), display_interface::DisplayError>,
+    ) -> Result<(), Error<PinE>> {
+        let mut retries = self.retries;
+        loop {
+            match op(&mut self.di) {
+                Ok(()) => return Ok(()),
+                Err(_) if retries > 0 => retries -= 1,
+                Err(_) => return Err(Error::DisplayError),
+            }
+        }
+    }
+
+    // Sends pixel colors following a RAMWR, big endian.
+    fn write_pixels<T>(&mut self, colors: T) -> Result<(), Error<PinE>>
+    where
+        T: IntoIterator<Item = u16>,
+    {
+        self.check_color_format()?;
+        // with a transfer size limit the pixels are sent in parts of at most
+        // that size, each continued with WRMEMC
+        let per_transfer = match self.max_transfer {
+            Some(limit) => (limit / 2).max(1),
+            None => usize::MAX,
+        };
+        let balance = self.white_balance;
+        let mut colors = colors.into_iter().peekable();
+        loop {
+            let part = colors.by_ref().take(per_transfer);
+            // the shadow keeps the colors as drawn, before white balance
+            #[cfg(feature = "shadow")]
+            let shadow = &mut self.shadow;
+            #[cfg(feature = "shadow")]
+            let part = part.inspect(move |color| {
+                if let Some(shadow) = shadow {
+                    shadow.push(*color);
+                }
+            });
+            let part = part.map(move |color| match balance {
+                Some(gains) => colors::white_balance(color, gains),
+                None => color,
+            });
+            #[cfg(feature = "trace")]
+            let (trace, little_endian) = (self.trace, self.little_endian);
+            #[cfg(feature = "trace")]
+            let part = part.inspect(move |color| {
+                if let Some(trace) = trace {
+                    let bytes = if little_endian { color.to_le_bytes() } else { color.to_be_bytes() };
+                    trace(TraceKind::Data, &bytes);
+                }
+            });
+            let mut part = part;
+            #[cfg(feature = "stats")]
+            {
+                self.stats.iterator = self.stats.iterator.wrapping_add(1);
+            }
+            let data = if self.little_endian {
+                U16LEIter(&mut part)
+            } else {
+                U16BEIter(&mut part)
+            };
+            let result = self.di.send_data(data).map_err(|_| Error::DisplayError);
+            self.note_failure(result, ErrorStage::Pixels)?;
+            if colors.peek().is_none() {
+                return Ok(());
+            }
+            self.continue_pixel_write()?;
+        }
+    }
+
+    // Sends raw pixel bytes following a RAMWR, split into transfers of at
+    // most the configured size (continued with WRMEMC) if there is one.
+    fn write_pixel_bytes(&mut self, data: &[u8]) -> Result<(), Error<PinE>> {
+        let unit = self.pixel_format.unit_bytes();
+        match self.max_transfer {
+            Some(limit) if data.len() > limit => {
+                // keep pixels whole, a pixel split by WRMEMC would be garbled
+                let limit = (limit - limit % unit).max(unit);
+                for (i, part) in data.chunks(limit).enumerate() {
+                    if i > 0 {
+                        self.continue_pixel_write()?;
+                    }
+                    self.write_pixel_transfer(part)?;
+                }
+                Ok(())
+            }
+            _ => self.write_pixel_transfer(data),
+        }
+    }
+
+    // Sends raw pixel bytes as a single contiguous transfer.
+    fn write_pixel_transfer(&mut self, data: &[u8]) -> Result<(), Error<PinE>> {
+        use display_interface::DataFormat::U8;
+
+        #[cfg(feature = "trace")]
+        if let Some(trace) = self.trace {
+            trace(TraceKind::Data, data);
+        }
+        #[cfg(feature = "stats")]
+        {
+            self.stats.contiguous = self.stats.contiguous.wrapping_add(1);
+        }
+        #[cfg(feature = "shadow")]
+        if let Some(shadow) = &mut self.shadow {
+            shadow.push_bytes(data, self.pixel_format, self.little_endian);
+        }
+        let result = self.di.send_data(U8(data)).map_err(|_| Error::DisplayError);
+        self.note_failure(result, ErrorStage::Pixels)
+    }
+
+    // Sends `count` pixels of a single color following a RAMWR. A small buffer
+    // of pre-encoded pixels is sent repeatedly as contiguous transfers, which
+    // is much cheaper per pixel than streaming from an iterator.
+    fn write_solid(&mut self, color: u16, count: usize) -> Result<(), Error<PinE>> {
+        self.check_color_format()?;
+        let balanced = match self.white_balance {
+            Some(gains) => colors::white_balance(color, gains),
+            None => color,
+        };
+        let mut buf = [0u8; FILL_CHUNK * 2];
+        for pixel in buf.chunks_exact_mut(2) {
+            pixel.copy_from_slice(&self.encode_pixel(balanced));
+        }
+        let mut remaining = count;
+        while remaining > 0 {
+            let n = remaining.min(FILL_CHUNK);
+            // the shadow records `color` itself rather than the balanced bytes
+            #[cfg(feature = "shadow")]
+            let shadow = self.shadow.take();
+            let result = self.write_pixel_bytes(&buf[..n * 2]);
+            #[cfg(feature = "shadow")]
+            {
+                self.shadow = shadow;
+                if let Some(shadow) = &mut self.shadow {
+                    (0..n).for_each(|_| shadow.push(color));
+                }
+            }
+            result?;
+            remaining -= n;
+        }
         Ok(())
     }
 
-    fn write_data(&mut self, data: &[u8]) -> Result<(), Error<PinE>> {
-        self.di
-            .send_data(U8Iter(&mut data.iter().cloned()))
-            .map_err(|_| Error::DisplayError)
+    // u16 colors are sent as 16bpp data, which only matches the RAM format
+    // with Rgb565; other formats can only be written with raw blits.
+    fn check_color_format(&self) -> Result<(), Error<PinE>> {
+        if self.pixel_format == PixelFormat::Rgb565 {
+            Ok(())
+        } else {
+            Err(misuse(Error::DisplayError))
+        }
+    }
+
+    // Bytes of a 16bpp color in the order the controller expects them.
+    fn encode_pixel(&self, color: u16) -> [u8; 2] {
+        colors::to_bytes(color, self.little_endian)
+    }
+
+    // Sends raw pixel bytes for the current window in transfers of at most
+    // `chunk` bytes, starting with RAMWR and continuing with WRMEMC.
+    fn write_pixel_chunks(&mut self, data: &[u8], chunk: usize) -> Result<(), Error<PinE>> {
+        for (i, part) in data.chunks(chunk).enumerate() {
+            self.write_pixel_chunk(i == 0, part)?;
+        }
+        Ok(())
+    }
+
+    // Sends one chunk of a chunked write, starting it with RAMWR if it is the
+    // first and continuing the write otherwise.
+    fn write_pixel_chunk(&mut self, first: bool, data: &[u8]) -> Result<(), Error<PinE>> {
+        if first {
+            self.write_command(RAMWR)?;
+        } else {
+            self.continue_pixel_write()?;
+        }
+        self.write_pixel_bytes(data)
+    }
+
+    // Continues a memory write with WRMEMC, after the pacing hook.
+    fn continue_pixel_write(&mut self) -> Result<(), Error<PinE>> {
+        if let Some(pacing) = self.chunk_pacing {
+            pacing();
+        }
+        self.write_command(WRMEMC)
+    }
+
+    // Updates size and offsets for a new orientation without touching the display.
+    // Orientation as seen by the viewer, i.e. without the upside down mounting.
+    fn view_orientation(&self) -> Orientation {
+        if self.upside_down {
+            self.orientation.flipped()
+        } else {
+            self.orientation
+        }
+    }
+
+    // Switches to an orientation as seen by the viewer, with its offsets.
+    fn select_orientation(&mut self, orientation: Orientation) {
+        let orientation = if self.upside_down { orientation.flipped() } else { orientation };
+        if orientation.is_landscape() != self.orientation.is_landscape() {
+            core::mem::swap(&mut self.size_x, &mut self.size_y);
+        }
+        self.orientation = orientation;
+        let (off_x, off_y) = self.offsets[orientation as usize];
+        self.off_x = off_x;
+        self.off_y = off_y;
+    }
+
+    // MADCTL value for the current orientation, mirroring and color order.
+    fn madctl(&self) -> u8 {
+        // in landscape (MV set) x runs along the controller rows
+        let (x_bit, y_bit) = if self.orientation.is_landscape() {
+            (0x80, 0x40)
+        } else {
+            (0x40, 0x80)
+        };
+        let mut madctl = self.orientation.to_madctl();
+        if self.color_order == ColorOrder::Bgr {
+            madctl |= 0x08;
+        }
+        if self.mirror_x {
+            madctl ^= x_bit;
+        }
+        if self.mirror_y {
+            madctl ^= y_bit;
+        }
+        if self.scan_reversed {
+            madctl |= 0x10;
+        }
+        madctl
+    }
+
+    // Offsets to apply to window coordinates; a mirrored axis starts counting
+    // from the opposite edge of controller RAM.
+    fn window_offsets(&self) -> (u16, u16) {
+        let (ram_x, ram_y) = if self.orientation.is_landscape() {
+            (RAM_HEIGHT, RAM_WIDTH)
+        } else {
+            (RAM_WIDTH, RAM_HEIGHT)
+        };
+        let off_x = if self.mirror_x {
+            ram_x.saturating_sub(self.size_x).saturating_sub(self.off_x)
+        } else {
+            self.off_x
+        };
+        let off_y = if self.mirror_y {
+            ram_y.saturating_sub(self.size_y).saturating_sub(self.off_y)
+        } else {
+            self.off_y
+        };
+        (off_x, off_y)
+    }
+
+    // Translates a window in visible coordinates to controller RAM, applying
+    // the offsets and the end adjustment of the current orientation.
+    fn ram_window(&self, sx: u16, sy: u16, ex: u16, ey: u16) -> Result<(u16, u16, u16, u16), Error<PinE>> {
+        let (off_x, off_y) = self.window_offsets();
+        let (adjust_x, adjust_y) = self.end_adjust[self.orientation as usize];
+        let offset = |off: u16, v: u16| off.checked_add(v).ok_or_else(|| misuse(Error::OutOfBounds));
+        let adjust = |v: u16, adj: i16| v.checked_add_signed(adj).ok_or_else(|| misuse(Error::OutOfBounds));
+        Ok((
+            offset(off_x, sx)?,
+            offset(off_y, sy)?,
+            adjust(offset(off_x, ex)?, adjust_x)?,
+            adjust(offset(off_y, ey)?, adjust_y)?,
+        ))
+    }
+
+    // Checks the size of raw pixel data for a blit and returns the inclusive
+    // end of the window, or None for an empty rectangle.
+    fn blit_window(&self, sx: u16, sy: u16, dx: u16, dy: u16, len: usize) -> Result<Option<(u16, u16)>, Error<PinE>> {
+        if len != self.data_len(dx as usize * dy as usize) {
+            return Err(misuse(Error::DisplayError));
+        }
+        if dx == 0 || dy == 0 {
+            return Ok(None);
+        }
+        end_point(sx, sy, dx, dy).map(Some)
+    }
+
+    // Sets the address window and starts a memory write into it.
+    fn start_blit(&mut self, sx: u16, sy: u16, ex: u16, ey: u16) -> Result<(), Error<PinE>> {
+        self.set_address_window(sx, sy, ex, ey)?;
+        self.write_command(RAMWR)
     }
 
     // Sets the address window for the display.
@@ -274,15 +2465,860 @@ where
         ex: u16,
         ey: u16,
     ) -> Result<(), Error<PinE>> {
-        let sx0 = self.off_x + sx;
-        let sy0 = self.off_y + sy;
-        let ex0 = self.off_x + ex;
-        let ey0 = self.off_y + ey;
+        let (sx0, sy0, ex0, ey0) = self.ram_window(sx, sy, ex, ey)?;
+        self.write_ram_window(sx0, sy0, ex0, ey0)?;
+        #[cfg(feature = "shadow")]
+        if let Some(shadow) = &mut self.shadow {
+            shadow.set_window(Some((sx, sy, ex, ey)), false);
+        }
+        Ok(())
+    }
+
+    // Sends CASET/RASET for a window in controller RAM coordinates, unless
+    // the controller is known to hold that window already.
+    fn write_ram_window(&mut self, sx: u16, sy: u16, ex: u16, ey: u16) -> Result<(), Error<PinE>> {
+        // a window in RAM coordinates can't be tracked by the shadow
+        #[cfg(feature = "shadow")]
+        if let Some(shadow) = &mut self.shadow {
+            shadow.set_window(None, false);
+        }
+        if self.ram_window == Some((sx, sy, ex, ey)) {
+            return Ok(());
+        }
         self.write_command(CASET)?;
-        self.write_data(&sx0.to_be_bytes())?;
-        self.write_data(&ex0.to_be_bytes())?;
+        self.write_data(&sx.to_be_bytes())?;
+        self.write_data(&ex.to_be_bytes())?;
         self.write_command(RASET)?;
-        self.write_data(&sy0.to_be_bytes())?;
-        self.write_data(&ey0.to_be_bytes())
+        self.write_data(&sy.to_be_bytes())?;
+        self.write_data(&ey.to_be_bytes())?;
+        self.ram_window = Some((sx, sy, ex, ey));
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::instruction::Instruction::*;
+    use crate::mock::{assert_misuse, assert_rejected, display, initialized, MockDelay, MockInterface, MockPin};
+    use crate::{ColorOrder, ConfigError, DisplayConfig, Error, Orientation, PanelType, ST7789};
+    use crate::{DISPON_DELAY_US, RESET_PULSE_US, RESET_SETTLE_US};
+    use std::string::ToString;
+
+    #[test]
+    fn window_bounds_follow_orientation() {
+        let expected = [
+            (Orientation::Landscape, (40, 53, 279, 187)),
+            (Orientation::Portrait, (52, 40, 186, 279)),
+            (Orientation::LandscapeFlipped, (40, 52, 279, 186)),
+            (Orientation::PortraitFlipped, (53, 40, 187, 279)),
+        ];
+        let mut display = initialized();
+        for (orientation, window) in expected {
+            display.set_orientation(orientation).unwrap();
+            display.set_pixels(0, 0, display.size_x - 1, display.size_y - 1, core::iter::empty()).unwrap();
+            assert_eq!(display.di.window(), window, "{:?}", orientation);
+        }
+    }
+
+    #[test]
+    fn offsets_can_be_overridden_per_orientation() {
+        let mut display = display();
+        display.set_offsets(Orientation::Portrait, 10, 20);
+        display.init_commands().unwrap();
+        display.set_orientation(Orientation::Portrait).unwrap();
+        display.set_pixel(0, 0, 0).unwrap();
+        assert_eq!(display.di.window(), (10, 20, 10, 20));
+        display.set_orientation(Orientation::Landscape).unwrap();
+        display.set_pixel(0, 0, 0).unwrap();
+        assert_eq!(display.di.window(), (40, 53, 40, 53));
+    }
+
+    #[test]
+    fn errors_display_their_cause() {
+        assert_eq!(Error::<()>::DisplayError.to_string(), "display interface error");
+        assert_eq!(Error::<()>::OutOfBounds.to_string(), "coordinates out of bounds");
+        assert_eq!(Error::Pin(7).to_string(), "pin error: 7");
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn errors_convert_to_std_errors() {
+        let error: std::boxed::Box<dyn std::error::Error> = Error::<()>::OutOfBounds.into();
+        assert_eq!(error.to_string(), "coordinates out of bounds");
+    }
+
+    #[test]
+    fn mirrored_windows_use_the_complementary_offset() {
+        let mut display = initialized();
+        display.set_mirror(false, true).unwrap();
+        display.set_pixels(0, 0, 239, 134, core::iter::empty()).unwrap();
+        assert_eq!(display.di.window(), (40, 52, 279, 186));
+
+        display.set_orientation(Orientation::Portrait).unwrap();
+        display.set_mirror(true, false).unwrap();
+        display.set_pixels(0, 0, 134, 239, core::iter::empty()).unwrap();
+        assert_eq!(display.di.window(), (53, 40, 187, 279));
+        display.set_pixel(0, 0, 0).unwrap();
+        assert_eq!(display.di.window(), (53, 40, 53, 40));
+    }
+
+    #[test]
+    fn windows_past_u16_max_fail_cleanly() {
+        let mut display = initialized();
+        assert_rejected(&mut display, Error::OutOfBounds, |d| d.set_pixels(u16::MAX - 1, 0, u16::MAX, 0, [0, 0]));
+        assert_rejected(&mut display, Error::OutOfBounds, |d| d.blit_pixels(u16::MAX, 0, 2, 1, &[0; 4]));
+        assert_rejected(&mut display, Error::OutOfBounds, |d| d.blit_pixels(0, u16::MAX, 1, 2, &[0; 4]));
+    }
+
+    #[test]
+    fn init_sends_the_selected_inversion() {
+        for (inverted, sent, skipped) in [(true, INVON, INVOFF), (false, INVOFF, INVON)] {
+            let mut display = display().with_inversion(inverted);
+            display.init_commands().unwrap();
+            let commands = display.di.commands();
+            assert_eq!(display.di.count(sent), 1);
+            assert_eq!(display.di.count(skipped), 0);
+            // right before sleep out and display on
+            assert_eq!(commands[commands.len() - 3..], [sent as u8, SLPOUT as u8, DISPON as u8]);
+        }
+    }
+
+    #[test]
+    fn columnwise_pixels_fill_columns_first() {
+        let mut display = initialized();
+        display.set_pixels_columnwise(1, 1, 2, 3, 1..=6).unwrap();
+        // MV toggled for the write and restored afterwards
+        assert_eq!(display.di.commands()[0], MADCTL as u8);
+        assert_eq!(display.di.log[1], crate::mock::Transfer::Data(std::vec![0x40]));
+        assert_eq!(display.di.params(MADCTL), [0x60]);
+        // with MV cleared the RAM is addressed as (y, x)
+        assert_eq!(display.di.window(), (54, 41, 56, 42));
+        for x in 0..2 {
+            for y in 0..3 {
+                assert_eq!(display.di.pixel(54 + y, 41 + x), x * 3 + y + 1);
+            }
+        }
+    }
+
+    #[test]
+    fn retries_mask_a_failed_transfer() {
+        let mut display = initialized();
+        display.di.fail_next = 1;
+        assert!(matches!(display.set_tearing_effect(false), Err(Error::DisplayError)));
+
+        display.set_retry_count(1);
+        display.di.fail_next = 1;
+        display.set_tearing_effect(false).unwrap();
+        display.di.fail_next = 1;
+        display.blit_pixels(0, 0, 1, 1, &[0x12, 0x34]).unwrap();
+        assert_eq!(display.di.commands(), [TEOFF as u8, CASET as u8, RASET as u8, RAMWR as u8]);
+        assert_eq!(display.di.at(0, 0), 0x1234);
+
+        display.di.fail_next = 2;
+        assert!(matches!(display.set_tearing_effect(false), Err(Error::DisplayError)));
+    }
+
+    #[test]
+    fn little_endian_blit_swaps_byte_pairs() {
+        let mut display = initialized();
+        let data = [0x34, 0x12, 0x78, 0x56, 0xbc, 0x9a, 0xf0, 0xde];
+        display.blit_pixels_le(0, 0, 2, 2, &data).unwrap();
+        assert_eq!(display.di.pixel_data(), [0x1234, 0x5678, 0x9abc, 0xdef0]);
+        assert_eq!([display.di.at(0, 1), display.di.at(1, 1)], [0x9abc, 0xdef0]);
+    }
+
+    #[test]
+    fn little_endian_blit_checks_the_length() {
+        let mut display = initialized();
+        assert_rejected(&mut display, Error::DisplayError, |d| d.blit_pixels_le(0, 0, 2, 2, &[0; 7]));
+    }
+
+    #[test]
+    fn empty_rectangles_send_nothing() {
+        let mut display = initialized();
+        display.set_pixels(5, 0, 4, 0, [1]).unwrap();
+        display.set_pixels(0, 5, 0, 4, [1]).unwrap();
+        display.fill_rect(0, 0, 0, 10, 1).unwrap();
+        display.fill_rect(0, 0, 10, 0, 1).unwrap();
+        display.blit_pixels(0, 0, 0, 10, &[]).unwrap();
+        display.blit_pixels(0, 0, 10, 0, &[]).unwrap();
+        assert!(display.di.log.is_empty());
+    }
+
+    #[test]
+    fn chunked_blit_continues_with_wrmemc() {
+        let mut display = initialized();
+        let data: std::vec::Vec<u8> = (1..=12).collect();
+        // rounded down to whole pixels
+        display.blit_pixels_chunked(1, 1, 3, 2, &data, 5).unwrap();
+        assert_eq!(
+            display.di.commands(),
+            [CASET as u8, RASET as u8, RAMWR as u8, WRMEMC as u8, WRMEMC as u8]
+        );
+        assert_eq!(display.di.data_sizes()[4..], [4, 4, 4]);
+        let pixels: std::vec::Vec<_> = (1..3).flat_map(|y| (1..4).map(move |x| (x, y))).collect();
+        for (i, (x, y)) in pixels.into_iter().enumerate() {
+            assert_eq!(display.di.at(x, y), u16::from_be_bytes([data[i * 2], data[i * 2 + 1]]));
+        }
+
+        display.di.clear();
+        display.blit_pixels_chunked(0, 0, 0, 0, &[], 16).unwrap();
+        assert!(display.di.log.is_empty());
+    }
+
+    #[test]
+    fn set_pixel_takes_logical_coordinates() {
+        let cases = [
+            (Orientation::Landscape, 0x60, (240, 135), (40, 53)),
+            (Orientation::Portrait, 0x00, (135, 240), (52, 40)),
+            (Orientation::LandscapeFlipped, 0xa0, (240, 135), (40, 52)),
+            (Orientation::PortraitFlipped, 0xc0, (135, 240), (53, 40)),
+        ];
+        let mut display = initialized();
+        for (orientation, madctl, (width, height), (off_x, off_y)) in cases {
+            display.set_orientation(orientation).unwrap();
+            assert_eq!(display.di.params(MADCTL), [madctl]);
+            assert_eq!((display.width(), display.height()), (width, height));
+            display.set_pixel(width - 1, height - 1, 1).unwrap();
+            let (x, y) = (off_x + width - 1, off_y + height - 1);
+            assert_eq!(display.di.window(), (x, y, x, y), "{:?}", orientation);
+
+            display.di.clear();
+            display.set_pixel(width, 0, 1).unwrap();
+            display.set_pixel(0, height, 1).unwrap();
+            assert!(display.di.log.is_empty(), "{:?}", orientation);
+        }
+    }
+
+    #[test]
+    fn pacing_runs_before_every_continuation() {
+        use core::sync::atomic::{AtomicUsize, Ordering};
+        static PACED: AtomicUsize = AtomicUsize::new(0);
+        fn pace() {
+            PACED.fetch_add(1, Ordering::Relaxed);
+        }
+
+        let mut display = initialized();
+        display.set_chunk_pacing(Some(pace));
+        display.blit_pixels_chunked(0, 0, 4, 1, &[0; 8], 2).unwrap();
+        assert_eq!(PACED.load(Ordering::Relaxed), 3);
+
+        // parts split by the transfer size limit are paced too
+        display.set_max_transfer_size(Some(4));
+        display.blit_pixels(0, 0, 4, 1, &[0; 8]).unwrap();
+        display.set_pixels(0, 0, 3, 0, [0; 4]).unwrap();
+        assert_eq!(PACED.load(Ordering::Relaxed), 5);
+        assert_eq!(display.di.count(WRMEMC), 5);
+
+        // single transfers aren't
+        display.set_max_transfer_size(None);
+        display.blit_pixels(0, 0, 4, 1, &[0; 8]).unwrap();
+        assert_eq!(PACED.load(Ordering::Relaxed), 5);
+    }
+
+    #[test]
+    fn reader_blit_streams_odd_sized_reads() {
+        let mut display = initialized();
+        let data: std::vec::Vec<u8> = (1..=16).collect();
+        let mut pos = 0;
+        display
+            .blit_from_reader(0, 0, 4, 2, |buf| {
+                let n = buf.len().min(3).min(data.len() - pos);
+                buf[..n].copy_from_slice(&data[pos..pos + n]);
+                pos += n;
+                Ok(n)
+            })
+            .unwrap();
+        let expected: std::vec::Vec<u16> = data.chunks(2).map(|p| u16::from_be_bytes([p[0], p[1]])).collect();
+        assert_eq!(display.di.pixel_data(), expected);
+        assert_eq!(display.di.at(3, 1), 0x0f10);
+        // every transfer holds whole pixels
+        assert!(display.di.data_sizes()[4..].iter().all(|size| size % 2 == 0));
+
+        let mut left = 4;
+        let result = display.blit_from_reader(0, 0, 4, 2, |buf| {
+            let n = buf.len().min(left);
+            left -= n;
+            Ok(n)
+        });
+        assert!(matches!(result, Err(Error::DisplayError)));
+    }
+
+    #[test]
+    fn try_new_rejects_impossible_configurations() {
+        let try_new = |size_x, size_y, off_x, off_y| {
+            ST7789::try_new(MockInterface::default(), MockPin::default(), size_x, size_y, off_x, off_y).err()
+        };
+        assert_eq!(try_new(240, 135, 40, 53), None);
+        assert_eq!(try_new(320, 240, 0, 0), None);
+        assert_eq!(try_new(0, 135, 40, 53), Some(ConfigError::ZeroSize));
+        assert_eq!(try_new(321, 135, 0, 0), Some(ConfigError::SizeTooLarge));
+        assert_eq!(try_new(240, 241, 0, 0), Some(ConfigError::SizeTooLarge));
+        assert_eq!(try_new(240, 135, 81, 53), Some(ConfigError::OffsetTooLarge));
+        assert_eq!(try_new(240, 135, 40, 106), Some(ConfigError::OffsetTooLarge));
+    }
+
+    #[test]
+    fn pixel_writes_respect_the_transfer_size_limit() {
+        let colors = [1, 2, 3, 4, 5, 6];
+        let mut display = initialized();
+        display.di.max_transfer = Some(5);
+        assert!(display.set_pixels(0, 0, 2, 1, colors).is_err());
+
+        display.set_max_transfer_size(Some(5));
+        display.set_pixels(0, 0, 2, 1, colors).unwrap();
+        assert_eq!([0, 1, 2].map(|x| display.di.at(x, 0)), [1, 2, 3]);
+        assert_eq!([0, 1, 2].map(|x| display.di.at(x, 1)), [4, 5, 6]);
+        let bytes: std::vec::Vec<u8> = colors.iter().flat_map(|c: &u16| c.to_be_bytes()).collect();
+        display.blit_pixels(4, 0, 3, 2, &bytes).unwrap();
+        assert_eq!([4, 5, 6].map(|x| display.di.at(x, 1)), [4, 5, 6]);
+        display.fill_rect(0, 2, 7, 3, 0xf800).unwrap();
+        assert_eq!(display.di.at(6, 4), 0xf800);
+        assert!(display.di.data_sizes().iter().all(|&size| size <= 5));
+        assert!(display.di.count(WRMEMC) > 0);
+    }
+
+    #[test]
+    fn hard_reset_follows_the_reset_polarity() {
+        let mut delay = MockDelay::default();
+        let mut display = display();
+        display.hard_reset(&mut delay).unwrap();
+        assert_eq!(display.rst.levels, [true, false, true]);
+        assert_eq!(delay.delays, [RESET_PULSE_US, RESET_PULSE_US, RESET_SETTLE_US]);
+
+        let mut inverted = crate::mock::display().with_reset_active_high(true);
+        inverted.hard_reset(&mut delay).unwrap();
+        assert_eq!(inverted.rst.levels, [false, true, false]);
+    }
+
+    #[test]
+    fn pixel_byte_order_follows_the_endianness_setting() {
+        let mut display = initialized();
+        display.set_pixels(0, 0, 1, 0, [0x1234, 0xabcd]).unwrap();
+        assert_eq!(display.di.params(RAMWR), [0x12, 0x34, 0xab, 0xcd]);
+        display.fill_rect(0, 0, 1, 1, 0x1234).unwrap();
+        assert_eq!(display.di.params(RAMWR), [0x12, 0x34]);
+
+        display.set_little_endian_pixels(true);
+        display.set_pixels(0, 0, 1, 0, [0x1234, 0xabcd]).unwrap();
+        assert_eq!(display.di.params(RAMWR), [0x34, 0x12, 0xcd, 0xab]);
+        display.fill_rect(0, 0, 1, 1, 0x1234).unwrap();
+        assert_eq!(display.di.params(RAMWR), [0x34, 0x12]);
+    }
+
+    #[test]
+    fn end_adjustment_moves_only_the_window_end() {
+        let mut display = initialized();
+        display.set_end_adjust(Orientation::Landscape, -1, 1);
+        display.fill_rect(0, 0, 240, 135, 0).unwrap();
+        assert_eq!(display.di.window(), (40, 53, 278, 188));
+        display.fill_rect(5, 6, 10, 10, 0).unwrap();
+        assert_eq!(display.di.window(), (45, 59, 53, 69));
+
+        // other orientations keep their own adjustment
+        display.set_orientation(Orientation::Portrait).unwrap();
+        display.fill_rect(0, 0, 135, 240, 0).unwrap();
+        assert_eq!(display.di.window(), (52, 40, 186, 279));
+    }
+
+    #[test]
+    fn end_adjustment_below_zero_fails() {
+        let mut display = initialized();
+        display.set_offsets(Orientation::Landscape, 0, 0);
+        display.set_end_adjust(Orientation::Landscape, -1, 0);
+        assert_rejected(&mut display, Error::OutOfBounds, |d| d.set_pixel(0, 0, 0));
+    }
+
+    #[test]
+    fn nine_bit_interfaces_flag_every_data_byte() {
+        use display_interface::{DataFormat, DisplayError, WriteOnlyDataCommand};
+
+        // 3-wire SPI: the 9th bit is set for data, clear for commands
+        #[derive(Default)]
+        struct NineBit(std::vec::Vec<u16>);
+
+        impl WriteOnlyDataCommand for NineBit {
+            fn send_commands(&mut self, cmd: DataFormat<'_>) -> Result<(), DisplayError> {
+                self.0.extend(crate::mock::bytes(cmd)?.into_iter().map(u16::from));
+                Ok(())
+            }
+
+            fn send_data(&mut self, buf: DataFormat<'_>) -> Result<(), DisplayError> {
+                self.0.extend(crate::mock::bytes(buf)?.into_iter().map(|b| 0x100 | u16::from(b)));
+                Ok(())
+            }
+        }
+
+        let mut display = ST7789::new(NineBit::default(), MockPin::default(), 240, 135, 40, 53);
+        display.init_commands().unwrap();
+        display.di.0.clear();
+        display.set_pixel(0, 0, 0x1234).unwrap();
+        let expected = [0x2a, 0x100, 0x128, 0x100, 0x128, 0x2b, 0x100, 0x135, 0x100, 0x135, 0x2c, 0x112, 0x134];
+        assert_eq!(display.di.0, expected);
+    }
+
+    #[test]
+    fn init_is_a_reset_the_commands_and_the_delays() {
+        let mut delay = MockDelay::default();
+        let mut combined = display();
+        combined.init(&mut delay).unwrap();
+        assert_eq!(delay.delays, [RESET_PULSE_US, RESET_PULSE_US, RESET_SETTLE_US, DISPON_DELAY_US]);
+
+        let mut split = display();
+        split.hard_reset(&mut MockDelay::default()).unwrap();
+        split.init_commands().unwrap();
+        assert_eq!(combined.di.log, split.di.log);
+        assert_eq!(combined.rst.levels, split.rst.levels);
+    }
+
+    #[test]
+    fn coordinates_round_trip_in_every_orientation() {
+        let mut display = initialized();
+        for orientation in [
+            Orientation::Landscape,
+            Orientation::Portrait,
+            Orientation::LandscapeFlipped,
+            Orientation::PortraitFlipped,
+        ] {
+            display.set_orientation(orientation).unwrap();
+            let (w, h) = (display.width(), display.height());
+            for (x, y) in [(0, 0), (w - 1, 0), (0, h - 1), (w - 1, h - 1), (17, 23)] {
+                let (col, row) = display.to_physical(x, y).unwrap();
+                assert_eq!(display.to_logical(col, row), Some((x, y)), "{:?}", orientation);
+            }
+            assert_eq!(display.to_physical(w, 0), None);
+            assert_eq!(display.to_physical(0, h), None);
+        }
+
+        display.set_orientation(Orientation::Portrait).unwrap();
+        assert_eq!(display.to_physical(0, 0), Some((52, 40)));
+        assert_eq!(display.to_logical(0, 0), None);
+        display.set_orientation(Orientation::Landscape).unwrap();
+        assert_eq!(display.to_physical(0, 0), Some((186, 40)));
+    }
+
+    #[test]
+    fn repeated_windows_are_sent_once() {
+        let mut display = initialized();
+        let frame = std::vec![0; 240 * 135 * 2];
+        display.blit_pixels(0, 0, 240, 135, &frame).unwrap();
+        display.blit_pixels(0, 0, 240, 135, &frame).unwrap();
+        assert_eq!((display.di.count(CASET), display.di.count(RASET)), (1, 1));
+        assert_eq!(display.di.count(RAMWR), 2);
+
+        // a different window in between has to be replaced again
+        display.set_pixel(1, 1, 0).unwrap();
+        display.blit_pixels(0, 0, 240, 135, &frame).unwrap();
+        assert_eq!((display.di.count(CASET), display.di.count(RASET)), (3, 3));
+        assert_eq!(display.di.window(), (40, 53, 279, 187));
+
+        // so do windows of raw commands
+        display.send_raw_command(CASET).unwrap();
+        display.send_raw_data(display_interface::DataFormat::U8(&[0, 0, 0, 0])).unwrap();
+        display.blit_pixels(0, 0, 240, 135, &frame).unwrap();
+        assert_eq!(display.di.count(CASET), 5);
+    }
+
+    #[test]
+    fn lazy_iterators_are_read_once_in_order() {
+        use core::cell::Cell;
+
+        // stands in for external memory read one pixel at a time; not Clone
+        struct Counter<'a> {
+            next: u16,
+            end: u16,
+            reads: &'a Cell<usize>,
+        }
+
+        impl Iterator for Counter<'_> {
+            type Item = u16;
+
+            fn next(&mut self) -> Option<u16> {
+                if self.next == self.end {
+                    return None;
+                }
+                self.reads.set(self.reads.get() + 1);
+                self.next += 1;
+                Some(self.next - 1)
+            }
+        }
+
+        let reads = Cell::new(0);
+        let mut display = initialized();
+        display.set_max_transfer_size(Some(4));
+        display.set_pixels(0, 0, 2, 1, Counter { next: 10, end: 16, reads: &reads }).unwrap();
+        assert_eq!(reads.get(), 6);
+        assert_eq!(display.di.pixel_data(), [10, 11, 12, 13, 14, 15]);
+        assert_eq!([display.di.at(0, 1), display.di.at(2, 1)], [13, 15]);
+    }
+
+    #[test]
+    fn panel_types_select_the_inversion() {
+        for (panel, sent, skipped) in [(PanelType::Ips, INVON, INVOFF), (PanelType::Tn, INVOFF, INVON)] {
+            let config = DisplayConfig::new(240, 135, 40, 53).with_panel_type(panel);
+            let mut display = ST7789::new_with_config(MockInterface::default(), MockPin::default(), config);
+            display.init_commands().unwrap();
+            assert_eq!(display.di.count(sent), 1, "{:?}", panel);
+            assert_eq!(display.di.count(skipped), 0, "{:?}", panel);
+        }
+    }
+
+    #[test]
+    fn u16_frames_cover_the_visible_area() {
+        let mut display = ST7789::new(MockInterface::default(), MockPin::default(), 3, 2, 1, 1);
+        display.init_commands().unwrap();
+        display.di.clear();
+        display.present_u16(&[1, 2, 3, 4, 5, 6]).unwrap();
+        assert_eq!(display.di.window(), (1, 1, 3, 2));
+        assert_eq!(display.di.params(RAMWR), [0, 1, 0, 2, 0, 3, 0, 4, 0, 5, 0, 6]);
+    }
+
+    #[test]
+    fn u16_frames_must_match_the_size() {
+        let mut display = initialized();
+        assert_rejected(&mut display, Error::DisplayError, |d| d.present_u16(&[0; 240 * 134]));
+    }
+
+    #[test]
+    fn release_full_round_trips_the_state() {
+        let mut display = initialized();
+        display.set_orientation(Orientation::Portrait).unwrap();
+        display.set_color_order(ColorOrder::Bgr).unwrap();
+        display.set_mirror(true, false).unwrap();
+        display.set_inversion(false).unwrap();
+        display.set_little_endian_pixels(true);
+        let madctl = display.di.params(MADCTL);
+        let (mut di, rst, state) = display.release_full();
+        assert!(state.little_endian);
+
+        di.clear();
+        let config = DisplayConfig::new(240, 135, 40, 53);
+        let mut display = ST7789::new_with_config(di, rst, config);
+        display.restore_state(state).unwrap();
+        assert_eq!(display.display_state(), state);
+        assert_eq!(display.di.commands(), [MADCTL, COLMOD, INVOFF].map(|c| c as u8));
+        assert_eq!(display.di.params(MADCTL), madctl);
+        display.set_pixel(0, 0, 0x1234).unwrap();
+        assert_eq!(display.di.params(RAMWR), [0x34, 0x12]);
+    }
+
+    #[test]
+    fn tear_free_frames_start_on_a_rising_te_edge() {
+        let mut display = initialized();
+        let frame = std::vec![0x12; 240 * 135 * 2];
+        // the panel is already in a blanking period, which must be skipped
+        let levels = [true, true, false, false, true];
+        let mut polls = 0;
+        display
+            .present_tear_free(&frame, || {
+                polls += 1;
+                levels[polls - 1]
+            })
+            .unwrap();
+        assert_eq!(polls, levels.len());
+        assert_eq!(display.di.commands(), [TEON, CASET, RASET, RAMWR].map(|c| c as u8));
+        assert_eq!(display.di.params(TEON), [0x00]);
+        assert_eq!(display.di.window(), (40, 53, 279, 187));
+        assert_eq!(display.di.pixel_data().len(), 240 * 135);
+    }
+
+    #[test]
+    fn release_in_reset_leaves_the_controller_in_reset() {
+        let mut display = initialized();
+        display.rst.levels.clear();
+        let (di, rst) = display.release_in_reset().unwrap();
+        assert_eq!(rst.levels, [false]);
+        assert!(di.log.is_empty());
+
+        let (_, rst) = crate::mock::display().with_reset_active_high(true).release_in_reset().unwrap();
+        assert_eq!(rst.levels, [true]);
+
+        let mut failing = initialized();
+        failing.rst.fail = true;
+        assert!(matches!(failing.release_in_reset(), Err(Error::Pin(()))));
+    }
+
+    #[test]
+    fn prepared_blits_return_the_window_and_send_nothing() {
+        let display = initialized();
+        let data = [0u8; 2 * 10 * 4];
+        assert_eq!(display.prepare_blit(5, 6, 10, 4, &data).unwrap(), Some((5, 6, 14, 9)));
+        assert_eq!(display.prepare_blit(5, 6, 0, 4, &[]).unwrap(), None);
+        assert!(display.di.log.is_empty());
+    }
+
+    #[test]
+    fn prepared_blits_reject_a_wrong_length() {
+        let mut display = initialized();
+        let data = [0u8; 2 * 10 * 4 - 1];
+        assert_rejected(&mut display, Error::DisplayError, |d| d.prepare_blit(5, 6, 10, 4, &data).map(drop));
+    }
+
+    #[test]
+    fn prepared_blits_reject_windows_past_the_coordinate_range() {
+        let mut display = initialized();
+        let data = [0u8; 2 * 2];
+        assert_rejected(&mut display, Error::OutOfBounds, |d| d.prepare_blit(u16::MAX, 0, 2, 1, &data).map(drop));
+    }
+
+    #[test]
+    fn rgb444_blits_pack_two_pixels_in_three_bytes() {
+        use crate::colors::pack_rgb444;
+        use crate::PixelFormat;
+
+        let config = DisplayConfig { pixel_format: PixelFormat::Rgb444, ..DisplayConfig::new(240, 135, 40, 53) };
+        let mut display = ST7789::new_with_config(MockInterface::default(), MockPin::default(), config);
+        display.init_commands().unwrap();
+        assert_eq!(display.di.params(COLMOD), [0x53]);
+
+        let mut packed = [0u8; 6];
+        assert_eq!(pack_rgb444(&[0xf800, 0x07e0, 0x001f, 0xffff], &mut packed), Some(6));
+        assert_eq!(packed, [0xf0, 0x00, 0xf0, 0x00, 0xff, 0xff]);
+        display.di.clear();
+        display.blit_pixels(0, 0, 4, 1, &packed).unwrap();
+        assert_eq!(display.di.params(RAMWR), packed);
+
+        // an odd count ends with a half used byte, and chunks stay on pixel pairs
+        assert_eq!(pack_rgb444(&[0xf800, 0x07e0, 0x001f], &mut packed), Some(5));
+        assert_eq!(packed[..5], [0xf0, 0x00, 0xf0, 0x00, 0xf0]);
+        display.di.clear();
+        display.blit_pixels_chunked(0, 0, 3, 1, &packed[..5], 4).unwrap();
+        assert_eq!(display.di.data_sizes()[4..], [3, 2]);
+        assert_eq!(display.di.commands()[2..], [RAMWR as u8, WRMEMC as u8]);
+    }
+
+    #[test]
+    fn fills_need_the_rgb565_format() {
+        use crate::PixelFormat;
+
+        for format in [PixelFormat::Rgb565, PixelFormat::Rgb666, PixelFormat::Rgb444] {
+            let config = DisplayConfig { pixel_format: format, ..DisplayConfig::new(240, 135, 40, 53) };
+            let mut display = ST7789::new_with_config(MockInterface::default(), MockPin::default(), config);
+            display.init_commands().unwrap();
+            display.di.clear();
+            if format == PixelFormat::Rgb565 {
+                display.fill_rect(1, 2, 3, 4, 0xf800).unwrap();
+                assert_eq!(display.di.pixel_data(), [0xf800; 12]);
+            } else {
+                assert_misuse(&mut display, Error::DisplayError, |d| d.fill_rect(1, 2, 3, 4, 0xf800));
+                assert!(display.di.pixel_data().is_empty(), "{:?}", format);
+            }
+        }
+    }
+
+    #[test]
+    fn rgb444_blits_round_the_length_up_to_whole_bytes() {
+        let config = DisplayConfig { pixel_format: crate::PixelFormat::Rgb444, ..DisplayConfig::new(240, 135, 40, 53) };
+        let mut display = ST7789::new_with_config(MockInterface::default(), MockPin::default(), config);
+        assert_eq!(display.data_len(3), 5);
+        assert_rejected(&mut display, Error::DisplayError, |d| d.blit_pixels(0, 0, 3, 1, &[0; 6]));
+    }
+
+    #[test]
+    fn reinit_policy_repeats_a_failed_fill_once() {
+        use crate::{ErrorPolicy, SWRESET_DELAY_US};
+        use std::sync::Mutex;
+
+        static WAITS: Mutex<std::vec::Vec<u32>> = Mutex::new(std::vec::Vec::new());
+        let mut display = initialized();
+        display.set_error_policy(ErrorPolicy::Reinit(|us| WAITS.lock().unwrap().push(us)));
+        display.di.fail_next = 1;
+        display.fill_rect(0, 0, 2, 2, 0x1234).unwrap();
+
+        let commands = display.di.commands();
+        assert_eq!(commands[0], SWRESET as u8);
+        assert_eq!(commands[commands.len() - 4..], [DISPON, CASET, RASET, RAMWR].map(|c| c as u8));
+        assert_eq!(*WAITS.lock().unwrap(), [SWRESET_DELAY_US, DISPON_DELAY_US]);
+        assert_eq!((display.di.at(0, 0), display.di.at(1, 1)), (0x1234, 0x1234));
+    }
+
+    #[test]
+    fn reinit_policy_forgets_the_reset_settings() {
+        use crate::ErrorPolicy;
+
+        let mut display = initialized();
+        let frame_period = display.frame_period_us();
+        display.set_scroll_area(10, 20).unwrap();
+        display.set_scroll_offset(42).unwrap();
+        display.set_frame_rate(0x1f).unwrap();
+        display.set_little_endian_pixels(true);
+        display.set_error_policy(ErrorPolicy::Reinit(|_| {}));
+        display.di.fail_next = 1;
+        display.fill_rect(0, 0, 1, 1, 0x1234).unwrap();
+
+        // the repeated fill already uses the controller's reset byte order
+        assert_eq!(display.di.params(RAMWR), [0x12, 0x34]);
+        assert_eq!((display.scroll_offset(), display.frame_period_us()), (0, frame_period));
+        display.set_scroll_offset(5).unwrap();
+        assert_eq!(display.scroll_offset(), 5);
+    }
+
+    #[test]
+    fn errors_are_propagated_by_default() {
+        let mut display = initialized();
+        display.di.fail_next = 1;
+        assert!(matches!(display.fill_rect(0, 0, 2, 2, 0x1234), Err(Error::DisplayError)));
+        assert_eq!(display.di.count(SWRESET), 0);
+        assert_eq!(display.di.at(0, 0), 0);
+    }
+
+    #[test]
+    fn known_resolutions_get_their_offsets() {
+        use crate::known_offsets;
+
+        const PICO: Option<(u16, u16)> = known_offsets(240, 135);
+        assert_eq!(PICO, Some((40, 53)));
+        let expected = [((240, 135), (40, 53)), ((240, 240), (0, 0)), ((280, 240), (20, 0)), ((320, 170), (0, 35))];
+        for ((w, h), offset) in expected.iter().copied().chain([((320, 240), (0, 0)), ((100, 100), (0, 0))]) {
+            let mut display = ST7789::new_for_resolution(MockInterface::default(), MockPin::default(), w, h);
+            display.init_commands().unwrap();
+            display.set_pixel(0, 0, 1).unwrap();
+            assert_eq!(display.di.window(), (offset.0, offset.1, offset.0, offset.1), "{}x{}", w, h);
+        }
+        assert_eq!(known_offsets(100, 100), None);
+    }
+
+    #[test]
+    fn wrapped_pixels_take_the_height_from_the_length() {
+        let mut display = initialized();
+        display.set_pixels_wrapped(10, 20, 4, 0..12).unwrap();
+        assert_eq!(display.di.window(), (50, 73, 53, 75));
+        assert!(display.di.pixel_data().into_iter().eq(0..12));
+        assert_eq!((display.di.at(13, 20), display.di.at(10, 21)), (3, 4));
+
+        display.di.clear();
+        display.set_pixels_wrapped(10, 20, 4, 0..0).unwrap();
+        assert!(display.di.log.is_empty());
+    }
+
+    #[test]
+    fn wrapped_pixels_must_fill_whole_rows() {
+        let mut display = initialized();
+        assert_rejected(&mut display, Error::DisplayError, |d| d.set_pixels_wrapped(10, 20, 4, 0..10));
+    }
+
+    #[test]
+    fn minimal_init_skips_the_panel_tuning() {
+        let mut delay = MockDelay::default();
+        let mut display = display();
+        display.init_minimal(&mut delay).unwrap();
+        assert_eq!(display.di.commands(), [MADCTL, COLMOD, INVON, SLPOUT, DISPON].map(|c| c as u8));
+        assert_eq!(display.di.params(MADCTL), [0x60]);
+        assert_eq!(display.di.params(COLMOD), [0x55]);
+        assert_eq!(display.rst.levels, [true, false, true]);
+        assert_eq!(delay.delays, [RESET_PULSE_US, RESET_PULSE_US, RESET_SETTLE_US, DISPON_DELAY_US]);
+
+        let mut full = crate::mock::display();
+        full.init(&mut MockDelay::default()).unwrap();
+        assert!(full.di.commands().len() > display.di.commands().len());
+    }
+
+    #[test]
+    fn scan_direction_sets_only_the_ml_bit() {
+        let mut display = initialized();
+        display.set_scan_direction(true).unwrap();
+        assert_eq!(display.di.params(MADCTL), [0x70]);
+        // kept when the orientation changes, and addressing stays the same
+        display.set_orientation(Orientation::PortraitFlipped).unwrap();
+        assert_eq!(display.di.params(MADCTL), [0xd0]);
+        display.set_color_order(ColorOrder::Bgr).unwrap();
+        assert_eq!(display.di.params(MADCTL), [0xd8]);
+        display.set_pixel(0, 0, 1).unwrap();
+        assert_eq!(display.di.window(), (53, 40, 53, 40));
+
+        display.set_scan_direction(false).unwrap();
+        assert_eq!(display.di.params(MADCTL), [0xc8]);
+    }
+
+    #[test]
+    #[cfg_attr(debug_assertions, should_panic(expected = "drawing before init"))]
+    fn drawing_before_init_panics_in_debug_builds() {
+        display().set_pixel(0, 0, 1).unwrap();
+    }
+
+    #[test]
+    fn restored_drivers_draw_without_another_init() {
+        let (mut di, rst, state) = initialized().release_full();
+        assert!(state.initialized);
+
+        di.clear();
+        let config = DisplayConfig::new(240, 135, 40, 53);
+        let mut display = ST7789::new_with_config(di, rst, config);
+        display.restore_state(state).unwrap();
+        display.fill_rect(0, 0, 2, 2, 0x1234).unwrap();
+        assert_eq!(display.di.at(1, 1), 0x1234);
+        assert_eq!(display.di.count(SLPOUT), 0);
+    }
+
+    #[test]
+    fn fills_are_sent_in_chunks_of_the_selected_size() {
+        use crate::FILL_CHUNK;
+
+        let expected = if cfg!(feature = "fill-128") {
+            128
+        } else if cfg!(feature = "fill-32") {
+            32
+        } else {
+            64
+        };
+        assert_eq!(FILL_CHUNK, expected);
+
+        let mut display = initialized();
+        display.fill_rect(0, 0, FILL_CHUNK as u16 + 1, 3, 0x1234).unwrap();
+        let pixels = (FILL_CHUNK + 1) * 3;
+        let mut sizes = std::vec![FILL_CHUNK * 2; pixels / FILL_CHUNK];
+        sizes.push(pixels % FILL_CHUNK * 2);
+        assert_eq!(display.di.data_sizes()[4..], sizes);
+    }
+
+    #[test]
+    fn upside_down_mounting_flips_every_orientation() {
+        let mut display = display().with_upside_down(true);
+        display.init_commands().unwrap();
+        assert_eq!(display.di.params(MADCTL), [0xa0]);
+        assert_eq!(display.get_orientation(), Orientation::Landscape as u8);
+        display.set_pixel(0, 0, 1).unwrap();
+        assert_eq!(display.di.window(), (40, 52, 40, 52));
+
+        display.set_orientation(Orientation::Portrait).unwrap();
+        assert_eq!(display.di.params(MADCTL), [0xc0]);
+        display.set_pixel(0, 0, 1).unwrap();
+        assert_eq!(display.di.window(), (53, 40, 53, 40));
+
+        let config = DisplayConfig { upside_down: true, ..DisplayConfig::new(240, 135, 40, 53) };
+        let mut configured = ST7789::new_with_config(MockInterface::default(), MockPin::default(), config);
+        configured.init_commands().unwrap();
+        assert_eq!(configured.di.params(MADCTL), [0xa0]);
+    }
+
+    #[test]
+    fn frame_diffs_send_only_the_changed_tiles() {
+        let mut display = initialized();
+        let old = std::vec![0u16; 240 * 135];
+        display.present_diff(&old, &old, 16).unwrap();
+        assert!(display.di.log.is_empty());
+
+        let mut new = old.clone();
+        new[20 * 240 + 37] = 0x1234;
+        display.present_diff(&new, &old, 16).unwrap();
+        assert_eq!(display.di.count(RAMWR), 1);
+        assert_eq!(display.di.window(), (40 + 32, 53 + 16, 40 + 47, 53 + 31));
+        assert_eq!(display.di.pixel_data().len(), 16 * 16);
+        assert_eq!(display.di.at(37, 20), 0x1234);
+
+        // tiles at the right and bottom edges are cut to the visible area
+        display.di.clear();
+        let mut corner = old.clone();
+        corner[240 * 135 - 1] = 0x4321;
+        display.present_diff(&corner, &old, 16).unwrap();
+        assert_eq!(display.di.window(), (40 + 224, 53 + 128, 40 + 239, 53 + 134));
+        assert_eq!(display.di.pixel_data().len(), 16 * 7);
+    }
+
+    #[test]
+    fn frame_diffs_need_two_full_frames() {
+        let mut display = initialized();
+        let frame = std::vec![0u16; 240 * 135];
+        assert_rejected(&mut display, Error::DisplayError, |d| d.present_diff(&frame, &frame[1..], 16));
     }
 }
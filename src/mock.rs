@@ -0,0 +1,347 @@
+//! Recording doubles of the display interface, the reset pin and the delay
+//! source for the unit tests.
+//!
+//! `MockInterface` logs every command and data transfer and emulates the
+//! controller's pixel RAM in address coordinates: CASET / RASET set the
+//! window, RAMWR and WRMEMC write 16bpp pixels into it and RAMRD reads them
+//! back in the 18 bit read format. MADCTL isn't emulated, so the RAM is
+//! indexed as (column, row) exactly as sent in CASET / RASET.
+// not every helper is used with every feature set
+#![allow(dead_code)]
+use crate::instruction::Instruction::{self, *};
+use crate::{Error, ST7789};
+use display_interface::{DataFormat, DisplayError, WriteOnlyDataCommand};
+use embedded_hal::blocking::delay::DelayUs;
+use embedded_hal::digital::v2::OutputPin;
+use std::collections::VecDeque;
+use std::vec::Vec;
+
+// Both address ranges are emulated up to the long side of the RAM, so
+// windows with row/column exchange fit as well
+const SIDE: usize = 320;
+
+/// A transfer seen by `MockInterface`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Transfer {
+    Command(u8),
+    Data(Vec<u8>),
+}
+
+pub struct MockInterface {
+    /// Every transfer, in order
+    pub log: Vec<Transfer>,
+    /// Number of transfers to fail before the next one succeeds
+    pub fail_next: usize,
+    /// Transfers larger than this fail
+    pub max_transfer: Option<usize>,
+    /// Bytes returned by reads other than RAMRD
+    pub reads: VecDeque<u8>,
+    ram: Vec<u16>,
+    window: (usize, usize, usize, usize),
+    pointer: (usize, usize),
+    command: u8,
+    params: Vec<u8>,
+    pending: Option<u8>,
+}
+
+impl Default for MockInterface {
+    fn default() -> Self {
+        Self {
+            log: Vec::new(),
+            fail_next: 0,
+            max_transfer: None,
+            reads: VecDeque::new(),
+            ram: std::vec![0; SIDE * SIDE],
+            window: (0, 0, SIDE - 1, SIDE - 1),
+            pointer: (0, 0),
+            command: NOP as u8,
+            params: Vec::new(),
+            pending: None,
+        }
+    }
+}
+
+impl MockInterface {
+    /// All command bytes sent, in order
+    pub fn commands(&self) -> Vec<u8> {
+        self.log
+            .iter()
+            .filter_map(|transfer| match transfer {
+                Transfer::Command(command) => Some(*command),
+                Transfer::Data(_) => None,
+            })
+            .collect()
+    }
+
+    /// Number of times `command` was sent
+    pub fn count(&self, command: Instruction) -> usize {
+        let command = command as u8;
+        self.commands().iter().filter(|&&c| c == command).count()
+    }
+
+    /// Data bytes sent after the last `command`, up to the next command
+    pub fn params(&self, command: Instruction) -> Vec<u8> {
+        let command = Transfer::Command(command as u8);
+        let start = self
+            .log
+            .iter()
+            .rposition(|transfer| *transfer == command)
+            .expect("command not sent");
+        self.log[start + 1..]
+            .iter()
+            .map_while(|transfer| match transfer {
+                Transfer::Data(data) => Some(data.clone()),
+                Transfer::Command(_) => None,
+            })
+            .flatten()
+            .collect()
+    }
+
+    /// Sizes of all data transfers, in order
+    pub fn data_sizes(&self) -> Vec<usize> {
+        self.log
+            .iter()
+            .filter_map(|transfer| match transfer {
+                Transfer::Data(data) => Some(data.len()),
+                Transfer::Command(_) => None,
+            })
+            .collect()
+    }
+
+    /// The last window sent as (sx, sy, ex, ey)
+    pub fn window(&self) -> (u16, u16, u16, u16) {
+        let pair = |command| {
+            let p = self.params(command);
+            (u16::from_be_bytes([p[0], p[1]]), u16::from_be_bytes([p[2], p[3]]))
+        };
+        let ((sx, ex), (sy, ey)) = (pair(CASET), pair(RASET));
+        (sx, sy, ex, ey)
+    }
+
+    /// Pixel data sent after RAMWR or WRMEMC, as big endian 16bpp colors
+    pub fn pixel_data(&self) -> Vec<u16> {
+        let mut pixels = false;
+        let mut bytes = Vec::new();
+        for transfer in &self.log {
+            match transfer {
+                Transfer::Command(command) => pixels = *command == RAMWR as u8 || *command == WRMEMC as u8,
+                Transfer::Data(data) if pixels => bytes.extend_from_slice(data),
+                Transfer::Data(_) => {}
+            }
+        }
+        bytes.chunks_exact(2).map(|p| u16::from_be_bytes([p[0], p[1]])).collect()
+    }
+
+    /// The pixel at the given RAM address
+    pub fn pixel(&self, col: u16, row: u16) -> u16 {
+        self.ram[usize::from(row) * SIDE + usize::from(col)]
+    }
+
+    /// The pixel shown at visible position (x, y) of `display()` in its
+    /// default landscape orientation
+    pub fn at(&self, x: u16, y: u16) -> u16 {
+        self.pixel(x + 40, y + 53)
+    }
+
+    /// Sets the pixel at the given RAM address without logging anything
+    pub fn set_pixel(&mut self, col: u16, row: u16, color: u16) {
+        self.ram[usize::from(row) * SIDE + usize::from(col)] = color;
+    }
+
+    /// Forgets the logged transfers
+    pub fn clear(&mut self) {
+        self.log.clear();
+    }
+
+    fn fail(&mut self, len: usize) -> Result<(), DisplayError> {
+        if self.fail_next > 0 {
+            self.fail_next -= 1;
+            return Err(DisplayError::BusWriteError);
+        }
+        match self.max_transfer {
+            Some(limit) if len > limit => Err(DisplayError::BusWriteError),
+            _ => Ok(()),
+        }
+    }
+
+    fn write_pixel(&mut self, color: u16) {
+        let (sx, sy, ex, ey) = self.window;
+        let (col, row) = self.pointer;
+        if col < SIDE && row < SIDE {
+            self.ram[row * SIDE + col] = color;
+        }
+        self.pointer = if col < ex {
+            (col + 1, row)
+        } else if row < ey {
+            (sx, row + 1)
+        } else {
+            (sx, sy)
+        };
+    }
+}
+
+/// The bytes of a transfer in the order they go over the bus
+pub fn bytes(data: DataFormat<'_>) -> Result<Vec<u8>, DisplayError> {
+    Ok(match data {
+        DataFormat::U8(data) => data.to_vec(),
+        DataFormat::U8Iter(iter) => iter.collect(),
+        DataFormat::U16BEIter(iter) => iter.flat_map(u16::to_be_bytes).collect(),
+        DataFormat::U16LEIter(iter) => iter.flat_map(u16::to_le_bytes).collect(),
+        _ => return Err(DisplayError::DataFormatNotImplemented),
+    })
+}
+
+impl WriteOnlyDataCommand for MockInterface {
+    fn send_commands(&mut self, cmd: DataFormat<'_>) -> Result<(), DisplayError> {
+        let cmd = bytes(cmd)?;
+        self.fail(cmd.len())?;
+        for &command in &cmd {
+            self.log.push(Transfer::Command(command));
+            self.command = command;
+            self.params.clear();
+            self.pending = None;
+            if command == RAMWR as u8 || command == RAMRD as u8 {
+                self.pointer = (self.window.0, self.window.1);
+            }
+        }
+        Ok(())
+    }
+
+    fn send_data(&mut self, buf: DataFormat<'_>) -> Result<(), DisplayError> {
+        let data = bytes(buf)?;
+        self.fail(data.len())?;
+        if self.command == RAMWR as u8 || self.command == WRMEMC as u8 {
+            for &byte in &data {
+                match self.pending.take() {
+                    Some(high) => self.write_pixel(u16::from_be_bytes([high, byte])),
+                    None => self.pending = Some(byte),
+                }
+            }
+        } else {
+            self.params.extend_from_slice(&data);
+            if self.params.len() == 4 && (self.command == CASET as u8 || self.command == RASET as u8) {
+                let start = usize::from(u16::from_be_bytes([self.params[0], self.params[1]]));
+                let end = usize::from(u16::from_be_bytes([self.params[2], self.params[3]]));
+                if self.command == CASET as u8 {
+                    self.window.0 = start;
+                    self.window.2 = end;
+                } else {
+                    self.window.1 = start;
+                    self.window.3 = end;
+                }
+            }
+        }
+        self.log.push(Transfer::Data(data));
+        Ok(())
+    }
+}
+
+#[cfg(feature = "read")]
+impl crate::ReadableDataCommand for MockInterface {
+    fn read_data(&mut self, buf: &mut [u8]) -> Result<(), DisplayError> {
+        self.fail(buf.len())?;
+        if self.command != RAMRD as u8 {
+            for byte in buf.iter_mut() {
+                *byte = self.reads.pop_front().unwrap_or(0);
+            }
+            return Ok(());
+        }
+        // a dummy byte, then R, G and B left aligned in one byte each
+        buf[0] = 0;
+        for rgb in buf[1..].chunks_exact_mut(3) {
+            let (col, row) = self.pointer;
+            let color = self.ram[row * SIDE + col];
+            rgb.copy_from_slice(&[(color >> 11 << 3) as u8, (color >> 5 << 2) as u8, (color << 3) as u8]);
+            let (sx, _, ex, ey) = self.window;
+            self.pointer = if col < ex { (col + 1, row) } else if row < ey { (sx, row + 1) } else { (col, row) };
+        }
+        Ok(())
+    }
+}
+
+/// A reset pin recording every level it is driven to
+#[derive(Default)]
+pub struct MockPin {
+    /// Levels driven, true for high
+    pub levels: Vec<bool>,
+    /// Whether setting the pin fails
+    pub fail: bool,
+}
+
+impl OutputPin for MockPin {
+    type Error = ();
+
+    fn set_low(&mut self) -> Result<(), ()> {
+        if self.fail {
+            return Err(());
+        }
+        self.levels.push(false);
+        Ok(())
+    }
+
+    fn set_high(&mut self) -> Result<(), ()> {
+        if self.fail {
+            return Err(());
+        }
+        self.levels.push(true);
+        Ok(())
+    }
+}
+
+/// A delay source recording every wait instead of waiting
+#[derive(Default)]
+pub struct MockDelay {
+    /// Requested delays in microseconds, in order
+    pub delays: Vec<u32>,
+}
+
+impl DelayUs<u32> for MockDelay {
+    fn delay_us(&mut self, us: u32) {
+        self.delays.push(us);
+    }
+}
+
+pub type Display = ST7789<MockInterface, MockPin>;
+
+/// The Pico-LCD 1.14" (240x135 at 40, 53), not initialized
+pub fn display() -> Display {
+    ST7789::new(MockInterface::default(), MockPin::default(), 240, 135, 40, 53)
+}
+
+/// The Pico-LCD 1.14" after `init_commands`, with an empty log
+pub fn initialized() -> Display {
+    let mut display = display();
+    display.init_commands().unwrap();
+    display.di.clear();
+    display
+}
+
+/// Checks that `op` is rejected as a misuse of the API before anything is
+/// sent, see `assert_misuse`.
+pub fn assert_rejected<T, F>(display: &mut Display, expected: Error<()>, op: F)
+where
+    F: FnOnce(&mut Display) -> Result<T, Error<()>>,
+{
+    let sent = display.di.log.len();
+    assert_misuse(display, expected, op);
+    assert_eq!(display.di.log[sent..], [], "rejected call sent transfers");
+}
+
+/// Checks that `op` is reported as a misuse of the API: debug builds with
+/// the `strict` feature have to panic, all others have to fail with the
+/// error variant of `expected`.
+pub fn assert_misuse<T, F>(display: &mut Display, expected: Error<()>, op: F)
+where
+    F: FnOnce(&mut Display) -> Result<T, Error<()>>,
+{
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| op(display).map(drop)));
+    match result {
+        Ok(_) if cfg!(all(feature = "strict", debug_assertions)) => panic!("misuse didn't panic"),
+        Ok(Err(error)) => assert_eq!(core::mem::discriminant(&error), core::mem::discriminant(&expected)),
+        Ok(Ok(())) => panic!("expected {:?}, the call succeeded", expected),
+        Err(panic) => {
+            let message = panic.downcast_ref::<std::string::String>().map_or("", |m| m.as_str());
+            assert!(message.starts_with("invalid arguments"), "unexpected panic: {}", message);
+        }
+    }
+}
@@ -0,0 +1,113 @@
+//! Programming the controller's NVM, for factory configuration tools.
+//!
+//! NVM cells can only be programmed a limited number of times, on some
+//! controller revisions only once, and a programmed value is loaded on every
+//! reset from then on. A wrong value can leave a panel permanently unusable
+//! with the generic init, so this is behind its own feature and is not meant
+//! for firmware running in the field.
+use crate::instruction::Instruction::{NVMSET, PROMACT, PROMEN};
+use crate::{misuse, Error, ST7789};
+use display_interface::WriteOnlyDataCommand;
+use embedded_hal::blocking::delay::DelayUs;
+use embedded_hal::digital::v2::OutputPin;
+
+/// Time `write_nvm` waits for the program action to finish, in microseconds
+pub const NVM_PROGRAM_US: u32 = 100_000;
+
+// PROMEN key, followed by the enable flag (bit 2)
+const PROMEN_KEY: [u8; 3] = [0x5a, 0x69, 0xee];
+// PROMACT parameters starting the program action
+const PROMACT_START: [u8; 2] = [0x29, 0xa5];
+
+impl<DI, RST, PinE> ST7789<DI, RST>
+where
+    DI: WriteOnlyDataCommand,
+    RST: OutputPin<Error = PinE>,
+{
+    ///
+    /// Programs one byte of the controller's NVM. **This can't be undone**:
+    /// NVM cells may only be programmed once, and the value is loaded into
+    /// the register it belongs to on every reset from then on.
+    ///
+    /// The sequence sent is:
+    ///
+    /// * PROMEN (FAh) `5A 69 EE 04` - enter program mode
+    /// * NVMSET (FCh) `address data` - select the NVM address and the value
+    /// * PROMACT (FEh) `29 A5` - start programming, then wait `NVM_PROGRAM_US`
+    /// * PROMEN (FAh) `5A 69 EE 00` - leave program mode
+    ///
+    /// The controller must be out of sleep mode (after `init`), otherwise
+    /// this fails with `DisplayError` without sending anything. Programming
+    /// also needs the supply voltage the datasheet specifies for it; check
+    /// the sequence and the address map against the datasheet of the exact
+    /// controller revision before programming a batch.
+    ///
+    /// # Arguments
+    ///
+    /// * `address` - NVM address to program
+    /// * `data` - the value to program
+    /// * `delay_source` - mutable reference to a delay provider
+    ///
+    pub fn write_nvm(
+        &mut self,
+        address: u8,
+        data: u8,
+        delay_source: &mut impl DelayUs<u32>,
+    ) -> Result<(), Error<PinE>> {
+        if !self.initialized {
+            return Err(misuse(Error::DisplayError));
+        }
+        self.set_program_mode(true)?;
+        self.write_command(NVMSET)?;
+        self.write_data(&[address, data])?;
+        self.write_command(PROMACT)?;
+        self.write_data(&PROMACT_START)?;
+        delay_source.delay_us(NVM_PROGRAM_US);
+        self.set_program_mode(false)
+    }
+
+    // Enters or leaves NVM program mode (PROMEN).
+    fn set_program_mode(&mut self, enable: bool) -> Result<(), Error<PinE>> {
+        let mut params = [0u8; 4];
+        params[..3].copy_from_slice(&PROMEN_KEY);
+        params[3] = if enable { 0x04 } else { 0x00 };
+        self.write_command(PROMEN)?;
+        self.write_data(&params)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::NVM_PROGRAM_US;
+    use crate::instruction::Instruction::*;
+    use crate::mock::{assert_rejected, display, initialized, MockDelay, Transfer};
+    use crate::Error;
+
+    #[test]
+    fn nvm_writes_unlock_program_and_lock_again() {
+        let mut display = initialized();
+        let mut delay = MockDelay::default();
+        display.write_nvm(0x10, 0x2a, &mut delay).unwrap();
+        let data = |bytes: &[u8]| Transfer::Data(bytes.to_vec());
+        assert_eq!(
+            display.di.log,
+            [
+                Transfer::Command(PROMEN as u8),
+                data(&[0x5a, 0x69, 0xee, 0x04]),
+                Transfer::Command(NVMSET as u8),
+                data(&[0x10, 0x2a]),
+                Transfer::Command(PROMACT as u8),
+                data(&[0x29, 0xa5]),
+                Transfer::Command(PROMEN as u8),
+                data(&[0x5a, 0x69, 0xee, 0x00]),
+            ]
+        );
+        assert_eq!(delay.delays, [NVM_PROGRAM_US]);
+    }
+
+    #[test]
+    fn nvm_writes_need_an_initialized_controller() {
+        let mut display = display();
+        assert_rejected(&mut display, Error::DisplayError, |d| d.write_nvm(0x10, 0x2a, &mut MockDelay::default()));
+    }
+}
@@ -0,0 +1,290 @@
+//! Runtime access to the panel tuning registers otherwise only written by `init`.
+use crate::instruction::Instruction::*;
+use crate::{Error, ST7789};
+use display_interface::WriteOnlyDataCommand;
+use embedded_hal::digital::v2::OutputPin;
+
+impl<DI, RST, PinE> ST7789<DI, RST>
+where
+    DI: WriteOnlyDataCommand,
+    RST: OutputPin<Error = PinE>,
+{
+    ///
+    /// Overrides the positive (PVGAMCTRL) and negative (NVGAMCTRL) voltage gamma tables.
+    ///
+    /// # Arguments
+    ///
+    /// * `positive` - PVGAMCTRL parameters
+    /// * `negative` - NVGAMCTRL parameters
+    ///
+    pub fn set_gamma_tables(&mut self, positive: &[u8; 14], negative: &[u8; 14]) -> Result<(), Error<PinE>> {
+        self.write_command(PVGAMCTRL)?;
+        self.write_data(positive)?;
+        self.write_command(NVGAMCTRL)?;
+        self.write_data(negative)
+    }
+
+    ///
+    /// Enables or disables the digital gamma tables (DGMEN bit 2), which are
+    /// applied on top of the analog gamma set with `set_gamma_tables`. They
+    /// are off after reset; upload the tables with `set_digital_gamma_luts`
+    /// before enabling them.
+    ///
+    /// # Arguments
+    ///
+    /// * `enable` - whether the digital gamma tables are used
+    ///
+    pub fn set_digital_gamma(&mut self, enable: bool) -> Result<(), Error<PinE>> {
+        self.write_command(DGMEN)?;
+        self.write_data(&[if enable { 0x04 } else { 0x00 }])
+    }
+
+    ///
+    /// Uploads the digital gamma look-up tables for red (DGMLUTR) and blue
+    /// (DGMLUTB). Each table has one entry per 6 bit channel level, 0 to 63:
+    /// entry n holds the level shown for input level n, so `[0, 1, .., 63]`
+    /// leaves the colors unchanged. The controller has no table for green.
+    ///
+    /// # Arguments
+    ///
+    /// * `red` - DGMLUTR parameters
+    /// * `blue` - DGMLUTB parameters
+    ///
+    pub fn set_digital_gamma_luts(&mut self, red: &[u8; 64], blue: &[u8; 64]) -> Result<(), Error<PinE>> {
+        self.write_command(DGMLUTR)?;
+        self.write_data(red)?;
+        self.write_command(DGMLUTB)?;
+        self.write_data(blue)
+    }
+
+    ///
+    /// Selects whether the VDV and VRH voltages come from the VDVS and VRHS
+    /// commands (VDVVRHEN CMDEN = 1, as set by `init`) or from NVM (0). The
+    /// second parameter byte is fixed at 0xff as required by the datasheet.
+    ///
+    /// # Arguments
+    ///
+    /// * `enable` - whether the command values are used
+    ///
+    pub fn set_vdv_vrh_enable(&mut self, enable: bool) -> Result<(), Error<PinE>> {
+        self.write_command(VDVVRHEN)?;
+        self.write_data(&[enable as u8, 0xff])
+    }
+
+    ///
+    /// Writes the VRH setting (VRHS), overriding the value 0x12 sent by `init`.
+    ///
+    /// Bits 5-0 set the gamma reference voltage GVDD in steps of 0.05V from
+    /// 3.55V (0x00) up to 5.5V (0x27 and above), on top of VCOM, the VCOM
+    /// offset and VDV. 0x12 gives 4.45V. Only takes effect while the VDV/VRH
+    /// values are taken from the commands, see `set_vdv_vrh_enable`.
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - the VRHS parameter
+    ///
+    pub fn set_vrhs(&mut self, value: u8) -> Result<(), Error<PinE>> {
+        self.write_command(VRHS)?;
+        self.write_data(&[value])
+    }
+
+    ///
+    /// Writes the VDV setting (VDVS), overriding the value 0x20 sent by `init`.
+    ///
+    /// Bits 5-0 set VDV in steps of 0.025V from -0.8V (0x00) through 0V (0x20,
+    /// the reset default) to 0.775V (0x3f). Only takes effect while the
+    /// VDV/VRH values are taken from the commands, see `set_vdv_vrh_enable`.
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - the VDVS parameter
+    ///
+    pub fn set_vdvs(&mut self, value: u8) -> Result<(), Error<PinE>> {
+        self.write_command(VDVS)?;
+        self.write_data(&[value])
+    }
+
+    ///
+    /// Writes the gate control register (GCTRL), overriding the value 0x35
+    /// sent by `init`.
+    ///
+    /// Bits 6-4 (VGHS) select the VGH gate voltage from 12.2V (0) to 14.97V
+    /// (7) and bits 2-0 (VGLS) the VGL gate voltage from -7.16V (0) to
+    /// -12.5V (7). 0x35 gives 13.26V and -10.43V. The gate scan direction is
+    /// not part of this register, see `set_gate_scan`.
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - the GCTRL parameter
+    ///
+    pub fn set_gate_control(&mut self, value: u8) -> Result<(), Error<PinE>> {
+        self.write_command(GCTRL)?;
+        self.write_data(&[value])
+    }
+
+    ///
+    /// Writes the gate scan control register (GATECTRL).
+    ///
+    /// The first parameter (bits 5-0, NL) sets the number of gate lines as
+    /// (NL + 1) * 8, the second (bits 5-0, SCN) the first gate line to scan.
+    /// In the third, bit 0 (GS) reverses the gate scan direction, bit 2 (SM)
+    /// selects interlaced scanning and bit 4 (TMG) mirrors the gate outputs.
+    /// The reset defaults are 0x27, 0x00 and 0x10 for 320 lines.
+    ///
+    /// # Arguments
+    ///
+    /// * `params` - the three GATECTRL parameters
+    ///
+    pub fn set_gate_scan(&mut self, params: [u8; 3]) -> Result<(), Error<PinE>> {
+        self.write_command(GATECTRL)?;
+        self.write_data(&params)
+    }
+
+    ///
+    /// Writes the power control 2 register (PWCTRL2, 0xE8), which sets the
+    /// clocks of the booster circuits: bits 5-4 (SBCLK) select the clock of
+    /// the AVDD/AVCL/VGH/VGL boosters and bits 1-0 (STP14CK) the clock of the
+    /// step up circuits 1 and 4.
+    ///
+    /// The controller has no separate booster enable: the boosters are
+    /// switched on by sleep out (SLPOUT) and off by sleep in (SLPIN), see the
+    /// power on/off sequences in the ST7789 datasheet. Their state can be
+    /// checked with bit 7 of `read_power_mode` when the interface can read.
+    ///
+    /// # Arguments
+    ///
+    /// * `params` - the PWCTRL2 parameter
+    ///
+    pub fn set_booster_clocks(&mut self, params: u8) -> Result<(), Error<PinE>> {
+        self.write_command(PWCTRL2)?;
+        self.write_data(&[params])
+    }
+
+    ///
+    /// Writes the RAM control register (RAMCTRL).
+    ///
+    /// `ctrl1` bit 4 (RM) selects RAM access from the MCU (0) or RGB interface
+    /// (1), bits 1-0 (DM) the display mode (0 = MCU). `ctrl2` bit 3 (ENDIAN)
+    /// selects the byte order of 16bpp pixel data: 0 sends the high byte
+    /// first, which is what the driver produces by default; 1 expects the low
+    /// byte first and needs `set_little_endian_pixels(true)`. Bits 1-0 (MDT)
+    /// select the pixel data transfer method and bits 5-4 (EPF) the expansion
+    /// of 65K/4K color data to 18 bits.
+    /// The reset defaults are 0x00 and 0xf0.
+    ///
+    /// # Arguments
+    ///
+    /// * `ctrl1` - first RAMCTRL parameter
+    /// * `ctrl2` - second RAMCTRL parameter
+    ///
+    pub fn set_ram_control(&mut self, ctrl1: u8, ctrl2: u8) -> Result<(), Error<PinE>> {
+        self.write_command(RAMCTRL)?;
+        self.write_data(&[ctrl1, ctrl2])
+    }
+
+    ///
+    /// Writes the RGB interface control register (RGBCTRL).
+    ///
+    /// The first parameter holds WO (bit 7, direct RGB mode or via RAM),
+    /// RCM (bits 6-5, DE or HV mode) and the VSYNC, HSYNC, DOTCLK and ENABLE
+    /// polarities (bits 3-0). The second and third parameters set the vertical
+    /// (bits 6-0) and horizontal (bits 4-0) back porch. The reset defaults are
+    /// 0x40, 0x02 and 0x14. Only relevant when the RGB interface is selected
+    /// via RAMCTRL.
+    ///
+    /// # Arguments
+    ///
+    /// * `params` - the three RGBCTRL parameters
+    ///
+    pub fn set_rgb_control(&mut self, params: [u8; 3]) -> Result<(), Error<PinE>> {
+        self.write_command(RGBCTRL)?;
+        self.write_data(&params)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::instruction::Instruction::*;
+    use crate::mock::initialized;
+
+    #[test]
+    fn gamma_tables_are_sent_unchanged() {
+        let mut display = initialized();
+        let positive = [1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14];
+        let negative = [14, 13, 12, 11, 10, 9, 8, 7, 6, 5, 4, 3, 2, 1];
+        display.set_gamma_tables(&positive, &negative).unwrap();
+        assert_eq!(display.di.commands(), [PVGAMCTRL as u8, NVGAMCTRL as u8]);
+        assert_eq!(display.di.params(PVGAMCTRL), positive);
+        assert_eq!(display.di.params(NVGAMCTRL), negative);
+    }
+
+    #[test]
+    fn ram_control_sends_both_parameters() {
+        let mut display = initialized();
+        display.set_ram_control(0x00, 0xf8).unwrap();
+        assert_eq!(display.di.commands(), [RAMCTRL as u8]);
+        assert_eq!(display.di.params(RAMCTRL), [0x00, 0xf8]);
+    }
+
+    #[test]
+    fn rgb_control_sends_all_three_parameters() {
+        let mut display = initialized();
+        display.set_rgb_control([0x40, 0x02, 0x14]).unwrap();
+        assert_eq!(display.di.commands(), [RGBCTRL as u8]);
+        assert_eq!(display.di.params(RGBCTRL), [0x40, 0x02, 0x14]);
+    }
+
+    #[test]
+    fn booster_clocks_write_pwctrl2() {
+        let mut display = initialized();
+        display.set_booster_clocks(0x13).unwrap();
+        assert_eq!(display.di.commands(), [0xe8]);
+        assert_eq!(display.di.params(PWCTRL2), [0x13]);
+    }
+
+    #[test]
+    fn gate_control_sends_the_value() {
+        let mut display = initialized();
+        display.set_gate_control(0x35).unwrap();
+        assert_eq!(display.di.commands(), [GCTRL as u8]);
+        assert_eq!(display.di.params(GCTRL), [0x35]);
+    }
+
+    #[test]
+    fn voltage_settings_write_one_byte() {
+        let mut display = initialized();
+        display.set_vrhs(0x27).unwrap();
+        display.set_vdvs(0x00).unwrap();
+        assert_eq!(display.di.commands(), [VRHS as u8, VDVS as u8]);
+        assert_eq!(display.di.params(VRHS), [0x27]);
+        assert_eq!(display.di.params(VDVS), [0x00]);
+    }
+
+    #[test]
+    fn vdv_vrh_enable_sends_two_bytes() {
+        let mut display = crate::mock::display();
+        display.init_commands().unwrap();
+        assert_eq!(display.di.params(VDVVRHEN), [0x01, 0xff]);
+        display.set_vdv_vrh_enable(false).unwrap();
+        assert_eq!(display.di.params(VDVVRHEN), [0x00, 0xff]);
+    }
+
+    #[test]
+    fn digital_gamma_luts_are_uploaded_then_enabled() {
+        let mut display = initialized();
+        let mut red = [0; 64];
+        let mut blue = [0; 64];
+        for level in 0..64 {
+            red[level] = level as u8;
+            blue[level] = 63 - level as u8;
+        }
+        display.set_digital_gamma_luts(&red, &blue).unwrap();
+        display.set_digital_gamma(true).unwrap();
+        assert_eq!(display.di.commands(), [DGMLUTR as u8, DGMLUTB as u8, DGMEN as u8]);
+        assert_eq!(display.di.params(DGMLUTR), red);
+        assert_eq!(display.di.params(DGMLUTB), blue);
+        assert_eq!(display.di.params(DGMEN), [0x04]);
+        display.set_digital_gamma(false).unwrap();
+        assert_eq!(display.di.params(DGMEN), [0x00]);
+    }
+}
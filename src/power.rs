@@ -0,0 +1,450 @@
+//! Partial display, idle mode, frame rate and brightness control for reducing power.
+use crate::instruction::Instruction::*;
+use crate::{misuse, Error, ST7789};
+use core::ops::Range;
+use display_interface::WriteOnlyDataCommand;
+use embedded_hal::blocking::delay::DelayUs;
+use embedded_hal::digital::v2::OutputPin;
+
+#[cfg(feature = "graphics")]
+use embedded_graphics::primitives::Rectangle;
+
+// FRCTRL2 value for the lowest frame rate (39Hz in normal mode)
+#[cfg(feature = "graphics")]
+const FRAME_RATE_LOWEST: u8 = 0x1f;
+// FRCTRL2 reset default (60Hz)
+const FRAME_RATE_DEFAULT: u8 = 0x0f;
+// PORCTRL back and front porch reset default
+const PORCH_DEFAULT: u8 = 0x0c;
+
+impl<DI, RST, PinE> ST7789<DI, RST>
+where
+    DI: WriteOnlyDataCommand,
+    RST: OutputPin<Error = PinE>,
+{
+    ///
+    /// Sets the gate lines shown in partial mode (PTLAR). The lines are
+    /// counted in controller RAM rows, regardless of orientation and offsets;
+    /// if the start is past the end the area wraps around.
+    ///
+    /// # Arguments
+    ///
+    /// * `start` - first gate line of the partial area
+    /// * `end` - last gate line of the partial area, inclusive
+    ///
+    pub fn set_partial_area(&mut self, start: u16, end: u16) -> Result<(), Error<PinE>> {
+        self.write_command(PTLAR)?;
+        self.write_data(&start.to_be_bytes())?;
+        self.write_data(&end.to_be_bytes())
+    }
+
+    ///
+    /// Switches between partial mode (PTLON), which only drives the area set
+    /// via `set_partial_area`, and normal mode (NORON).
+    ///
+    /// # Arguments
+    ///
+    /// * `partial` - whether to enter partial mode
+    ///
+    pub fn set_partial_mode(&mut self, partial: bool) -> Result<(), Error<PinE>> {
+        self.write_command(if partial { PTLON } else { NORON })
+    }
+
+    ///
+    /// Enables or disables idle mode (IDMON / IDMOFF), which reduces the
+    /// display to 8 colors by only using the MSB of every channel. The RAM
+    /// content is kept, so the full colors return with idle mode off;
+    /// `colors::idle_color` gives the color a pixel shows while idle.
+    ///
+    /// # Arguments
+    ///
+    /// * `idle` - whether to enter idle mode
+    ///
+    pub fn set_idle_mode(&mut self, idle: bool) -> Result<(), Error<PinE>> {
+        self.write_command(if idle { IDMON } else { IDMOFF })
+    }
+
+    ///
+    /// Writes the normal mode frame rate control register (FRCTRL2).
+    ///
+    /// Bits 4-0 (RTNA) select the frame rate from 119Hz (0x00) down to 39Hz
+    /// (0x1f), 0x0f is the 60Hz default. Bits 7-5 (NLA) set the inversion
+    /// mode, 0 for dot inversion.
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - the FRCTRL2 parameter
+    ///
+    pub fn set_frame_rate(&mut self, value: u8) -> Result<(), Error<PinE>> {
+        self.write_command(FRCTRL2)?;
+        self.write_data(&[value])?;
+        self.frame_rate = Some(value);
+        Ok(())
+    }
+
+    ///
+    /// Estimated duration of one frame in normal mode in microseconds,
+    /// following the datasheet formula 10MHz / ((320 + FPA + BPA) * (250 +
+    /// RTNA * 16)) with the frame rate set by `set_frame_rate` or `init` and
+    /// the porches of the init sequence. The internal oscillator isn't
+    /// trimmed, so the real period may differ by a few percent.
+    ///
+    pub fn frame_period_us(&self) -> u32 {
+        let rtna = u32::from(self.current_frame_rate() & 0x1f);
+        let (back, front) = self
+            .init_sequence
+            .iter()
+            .find(|(command, data)| *command == PORCTRL && data.len() >= 2)
+            .map_or((PORCH_DEFAULT, PORCH_DEFAULT), |(_, data)| (data[0], data[1]));
+        let lines = 320 + u32::from(back & 0x7f) + u32::from(front & 0x7f);
+        // 10MHz clock, i.e. 10 cycles per microsecond
+        lines * (250 + rtna * 16) / 10
+    }
+
+    ///
+    /// Best effort vsync without a TE line: waits for the rest of the frame
+    /// period (see `frame_period_us`) after the last present, so frames are
+    /// sent at about the panel's refresh rate. The driver has no clock, so
+    /// the caller measures the time since the last present; if a whole
+    /// period has already passed this returns right away.
+    ///
+    /// Pacing alone doesn't lock onto the panel's refresh, so tearing is only
+    /// reduced, not avoided; use `set_tearing_effect` and `present_synced`
+    /// where the TE pin is connected.
+    ///
+    /// # Arguments
+    ///
+    /// * `delay_source` - mutable reference to a delay provider
+    /// * `elapsed_us` - microseconds since the start of the last present
+    ///
+    pub fn wait_next_frame(&self, delay_source: &mut impl DelayUs<u32>, elapsed_us: u32) {
+        let remaining = self.frame_period_us().saturating_sub(elapsed_us);
+        if remaining > 0 {
+            delay_source.delay_us(remaining);
+        }
+    }
+
+    // FRCTRL2 value in effect: the last one set, else the init sequence's.
+    fn current_frame_rate(&self) -> u8 {
+        self.frame_rate.unwrap_or_else(|| self.init_frame_rate())
+    }
+
+    // FRCTRL2 value sent by the init sequence, or the reset default.
+    fn init_frame_rate(&self) -> u8 {
+        self.init_sequence
+            .iter()
+            .find(|(command, data)| *command == FRCTRL2 && data.len() == 1)
+            .map_or(FRAME_RATE_DEFAULT, |(_, data)| data[0])
+    }
+
+    ///
+    /// Restricts the display to a single active area, e.g. one widget: the
+    /// gate lines covering `rect` are selected as partial area and partial
+    /// mode is entered, and the address window is set to `rect`, so pixel
+    /// data sent with RAMWR (e.g. via `send_raw_command` / `send_raw_data`)
+    /// lands there. `set_partial_mode(false)` returns to normal mode.
+    ///
+    /// As with `enter_low_power_clock_mode` only the gate lines are limited:
+    /// the area spans the full width of the other axis, and the lines outside
+    /// it show the non-display area setting (PARCTRL), usually black.
+    ///
+    /// # Arguments
+    ///
+    /// * `rect` - the part of the visible area to keep driving
+    ///
+    #[cfg(feature = "graphics")]
+    pub fn refresh_only(&mut self, rect: Rectangle) -> Result<(), Error<PinE>> {
+        let (sx, sy, ex, ey) = self
+            .clip_to_display(&rect)
+            .ok_or_else(|| misuse(Error::OutOfBounds))?;
+        let (start, end) = self.gate_lines(sx, sy, ex, ey)?;
+        self.set_partial_area(start, end)?;
+        self.set_partial_mode(true)?;
+        self.set_address_window(sx, sy, ex, ey)
+    }
+
+    ///
+    /// Enters a low power mode for mostly static content such as a clock
+    /// face: only the gate lines covering `region` are driven (partial mode),
+    /// the frame rate is dropped to the lowest setting and idle mode reduces
+    /// the colors to 8. Everything outside the partial area is shown as
+    /// set by the non-display area scan settings (PARCTRL), usually black.
+    ///
+    /// The partial area can only limit the gate lines, so in landscape
+    /// orientations it covers the x range of `region`, in portrait the y range,
+    /// each across the full width of the other axis.
+    ///
+    /// # Arguments
+    ///
+    /// * `region` - the part of the visible area to keep driving
+    ///
+    #[cfg(feature = "graphics")]
+    pub fn enter_low_power_clock_mode(&mut self, region: Rectangle) -> Result<(), Error<PinE>> {
+        self.refresh_only(region)?;
+        self.set_frame_rate(FRAME_RATE_LOWEST)?;
+        self.set_idle_mode(true)
+    }
+
+    ///
+    /// Leaves the mode entered by `enter_low_power_clock_mode`, restoring
+    /// full colors, the frame rate of the init sequence and normal mode.
+    ///
+    #[cfg(feature = "graphics")]
+    pub fn exit_low_power_clock_mode(&mut self) -> Result<(), Error<PinE>> {
+        let frame_rate = self.init_frame_rate();
+        self.set_idle_mode(false)?;
+        self.set_frame_rate(frame_rate)?;
+        self.set_partial_mode(false)
+    }
+
+    ///
+    /// Sets the display brightness value (WRDISBV), from 0 (darkest) to 255.
+    /// The value controls the controller's brightness output, which only
+    /// dims the backlight on modules wiring the backlight driver to it, and
+    /// only while brightness control is enabled with WRCTRLD (BCTRL bit).
+    /// On the Pico-LCD 1.14" the backlight is driven by a GPIO instead, see
+    /// `Backlight`. The reset default is 0.
+    ///
+    /// # Arguments
+    ///
+    /// * `level` - the brightness value
+    ///
+    pub fn set_brightness(&mut self, level: u8) -> Result<(), Error<PinE>> {
+        self.write_command(WRDISBV)?;
+        self.write_data(&[level])?;
+        self.brightness = level;
+        Ok(())
+    }
+
+    ///
+    /// Ramps the brightness from the value last set with `set_brightness`
+    /// to `target`, up or down, changing it by `step` per frame. A step of 0
+    /// is treated as 1. The steps are timed with `frame_period_us`, so every
+    /// level is shown for about one frame.
+    ///
+    /// # Arguments
+    ///
+    /// * `target` - the final brightness value
+    /// * `step` - change of the brightness value per frame
+    /// * `delay_source` - mutable reference to a delay provider
+    ///
+    pub fn fade_to(&mut self, target: u8, step: u8, delay_source: &mut impl DelayUs<u32>) -> Result<(), Error<PinE>> {
+        let step = step.max(1);
+        let frame = self.frame_period_us();
+        while self.brightness != target {
+            let level = if target > self.brightness {
+                self.brightness.saturating_add(step).min(target)
+            } else {
+                self.brightness.saturating_sub(step).max(target)
+            };
+            self.set_brightness(level)?;
+            if level != target {
+                delay_source.delay_us(frame);
+            }
+        }
+        Ok(())
+    }
+
+    ///
+    /// Powers down all rows but `active` for fixed layouts that only use
+    /// part of the screen: the rows are cleared to black, selected as partial
+    /// area and partial mode is entered, so the panel stops driving the
+    /// other rows instead of just showing them black. Waits one frame for
+    /// the switch to take effect. Draw the content after this;
+    /// `set_partial_mode(false)` returns to normal mode.
+    ///
+    /// The rows are y coordinates of the visible area, translated to gate
+    /// lines through the y offset. As the gate lines run along the rows only
+    /// in portrait orientations, this fails with `OutOfBounds` in landscape.
+    ///
+    /// # Arguments
+    ///
+    /// * `active` - the rows to keep driving, end exclusive
+    /// * `delay_source` - mutable reference to a delay provider
+    ///
+    pub fn power_save_rows(
+        &mut self,
+        active: Range<u16>,
+        delay_source: &mut impl DelayUs<u32>,
+    ) -> Result<(), Error<PinE>> {
+        if active.start >= active.end || active.end > self.size_y || self.size_x == 0 || self.madctl() & 0x20 != 0 {
+            return Err(misuse(Error::OutOfBounds));
+        }
+        let (sx, ex) = (0, self.size_x - 1);
+        let (sy, ey) = (active.start, active.end - 1);
+        self.fill_rect(sx, sy, self.size_x, ey - sy + 1, 0)?;
+        let (start, end) = self.gate_lines(sx, sy, ex, ey)?;
+        self.set_partial_area(start, end)?;
+        self.set_partial_mode(true)?;
+        delay_source.delay_us(self.frame_period_us());
+        Ok(())
+    }
+
+    // Gate lines covered by a window in visible coordinates. The gates scan
+    // the controller rows, which carry x with row/column exchange (landscape)
+    // and y otherwise; row address order (MY) reverses them.
+    fn gate_lines(&self, sx: u16, sy: u16, ex: u16, ey: u16) -> Result<(u16, u16), Error<PinE>> {
+        let (off_x, off_y) = self.window_offsets();
+        let (start, end) = if self.madctl() & 0x20 != 0 {
+            (off_x + sx, off_x + ex)
+        } else {
+            (off_y + sy, off_y + ey)
+        };
+        if end >= crate::RAM_HEIGHT {
+            return Err(misuse(Error::OutOfBounds));
+        }
+        if self.madctl() & 0x80 != 0 {
+            Ok((crate::RAM_HEIGHT - 1 - end, crate::RAM_HEIGHT - 1 - start))
+        } else {
+            Ok((start, end))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "graphics")]
+    #[test]
+    fn low_power_clock_mode_sequence() {
+        use crate::instruction::Instruction::*;
+        use crate::mock::initialized;
+        use embedded_graphics::prelude::Point;
+        use embedded_graphics::primitives::Rectangle;
+
+        let mut display = initialized();
+        display
+            .enter_low_power_clock_mode(Rectangle::new(Point::new(10, 2), Point::new(20, 5)))
+            .unwrap();
+        let expected = [PTLAR, PTLON, CASET, RASET, FRCTRL2, IDMON].map(|c| c as u8);
+        assert_eq!(display.di.commands(), expected);
+        // landscape gate lines run along x
+        assert_eq!(display.di.params(PTLAR), [0, 50, 0, 60]);
+        assert_eq!(display.di.params(FRCTRL2), [0x1f]);
+
+        display.di.clear();
+        display.exit_low_power_clock_mode().unwrap();
+        assert_eq!(display.di.commands(), [IDMOFF, FRCTRL2, NORON].map(|c| c as u8));
+        assert_eq!(display.di.params(FRCTRL2), [0x0f]);
+    }
+
+    #[cfg(feature = "graphics")]
+    #[test]
+    fn refresh_only_selects_the_gate_lines_of_the_rect() {
+        use crate::instruction::Instruction::*;
+        use crate::mock::initialized;
+        use crate::Orientation::*;
+        use embedded_graphics::prelude::Point;
+        use embedded_graphics::primitives::Rectangle;
+
+        let mut display = initialized();
+        let rect = Rectangle::new(Point::new(10, 5), Point::new(20, 9));
+        for (orientation, lines) in [
+            (Landscape, [0, 50, 0, 60]),
+            (Portrait, [0, 45, 0, 49]),
+            (LandscapeFlipped, [1, 3, 1, 13]),
+            (PortraitFlipped, [1, 14, 1, 18]),
+        ] {
+            display.set_orientation(orientation).unwrap();
+            display.refresh_only(rect).unwrap();
+            assert_eq!(display.di.params(PTLAR), lines, "{:?}", orientation);
+        }
+        let commands = display.di.commands();
+        assert_eq!(commands[commands.len() - 4..], [PTLAR, PTLON, CASET, RASET].map(|c| c as u8));
+        assert_eq!(display.di.window(), (63, 45, 73, 49));
+
+        display.set_orientation(Landscape).unwrap();
+        display.refresh_only(rect).unwrap();
+        assert_eq!(display.di.window(), (50, 58, 60, 62));
+    }
+
+    #[test]
+    fn frame_period_matches_the_frame_rate() {
+        use crate::mock::{initialized, MockDelay};
+
+        // the datasheet lists 60Hz for 0x0f and 39Hz for 0x1f
+        let mut display = initialized();
+        assert_eq!(display.frame_period_us(), 344 * 490 / 10);
+        assert_eq!(1_000_000 / display.frame_period_us(), 59);
+        display.set_frame_rate(0x1f).unwrap();
+        assert_eq!(1_000_000 / display.frame_period_us(), 38);
+
+        let mut delay = MockDelay::default();
+        display.wait_next_frame(&mut delay, 20_000);
+        display.wait_next_frame(&mut delay, 30_000);
+        assert_eq!(delay.delays, [display.frame_period_us() - 20_000]);
+    }
+
+    #[test]
+    fn power_save_rows_drives_only_the_active_gate_lines() {
+        use crate::instruction::Instruction::*;
+        use crate::mock::{initialized, MockDelay};
+        use crate::Orientation;
+
+        // portrait: y runs along the gate lines, starting at 40
+        let mut display = initialized();
+        display.set_orientation(Orientation::Portrait).unwrap();
+        display.di.set_pixel(52, 50, 0xffff);
+        display.di.set_pixel(52, 89, 0xffff);
+        display.di.set_pixel(52, 90, 0xffff);
+        display.di.clear();
+        let mut delay = MockDelay::default();
+        display.power_save_rows(10..50, &mut delay).unwrap();
+        assert_eq!(display.di.commands()[3..], [PTLAR as u8, PTLON as u8]);
+        assert_eq!(display.di.params(PTLAR), [0, 50, 0, 89]);
+        // the active rows are cleared, the others left alone
+        assert_eq!((display.di.pixel(52, 50), display.di.pixel(52, 89), display.di.pixel(52, 90)), (0, 0, 0xffff));
+        assert_eq!(delay.delays, [display.frame_period_us()]);
+    }
+
+    #[test]
+    fn power_save_rows_rejects_empty_and_oversized_ranges() {
+        use crate::mock::{assert_rejected, initialized, MockDelay};
+        use crate::{Error, Orientation};
+
+        let mut display = initialized();
+        display.set_orientation(Orientation::Portrait).unwrap();
+        assert_rejected(&mut display, Error::OutOfBounds, |d| d.power_save_rows(20..20, &mut MockDelay::default()));
+        assert_rejected(&mut display, Error::OutOfBounds, |d| d.power_save_rows(0..241, &mut MockDelay::default()));
+    }
+
+    #[test]
+    fn power_save_rows_fails_in_landscape() {
+        use crate::mock::{assert_rejected, initialized, MockDelay};
+        use crate::{Error, Orientation};
+
+        let mut display = initialized();
+        for orientation in [Orientation::Landscape, Orientation::LandscapeFlipped] {
+            display.set_orientation(orientation).unwrap();
+            assert_rejected(&mut display, Error::OutOfBounds, |d| d.power_save_rows(10..50, &mut MockDelay::default()));
+        }
+    }
+
+    #[test]
+    fn fades_step_the_brightness_once_per_frame() {
+        use crate::instruction::Instruction::*;
+        use crate::mock::{initialized, MockDelay, Transfer};
+
+        let mut display = initialized();
+        let written = |display: &crate::mock::Display| -> std::vec::Vec<u8> {
+            let log = &display.di.log;
+            (1..log.len())
+                .filter(|&i| log[i - 1] == Transfer::Command(WRDISBV as u8))
+                .map(|i| match &log[i] {
+                    Transfer::Data(data) => data[0],
+                    Transfer::Command(_) => panic!("WRDISBV without a value"),
+                })
+                .collect()
+        };
+        let mut delay = MockDelay::default();
+        display.fade_to(100, 40, &mut delay).unwrap();
+        assert_eq!(written(&display), [40, 80, 100]);
+        assert_eq!(delay.delays, [display.frame_period_us(); 2]);
+
+        display.di.clear();
+        display.fade_to(10, 50, &mut MockDelay::default()).unwrap();
+        assert_eq!(written(&display), [50, 10]);
+        display.di.clear();
+        display.fade_to(10, 50, &mut MockDelay::default()).unwrap();
+        assert!(display.di.log.is_empty());
+    }
+}
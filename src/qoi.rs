@@ -0,0 +1,163 @@
+//! Streaming decoder for images in the QOI format (https://qoiformat.org).
+use crate::{end_point, misuse, Error, ST7789};
+use core::convert::TryFrom;
+use display_interface::WriteOnlyDataCommand;
+use embedded_hal::digital::v2::OutputPin;
+
+// Size of the QOI header: magic, width, height, channels, colorspace
+const HEADER_LEN: usize = 14;
+
+// Decodes QOI data chunk by chunk, yielding Rgb565 colors. Stops early on
+// truncated or malformed data.
+struct QoiPixels<'a> {
+    data: &'a [u8],
+    pos: usize,
+    remaining: usize,
+    run: u8,
+    px: [u8; 4],
+    index: [[u8; 4]; 64],
+}
+
+impl<'a> QoiPixels<'a> {
+    fn new(data: &'a [u8], pixels: usize) -> Self {
+        Self {
+            data,
+            pos: HEADER_LEN,
+            remaining: pixels,
+            run: 0,
+            px: [0, 0, 0, 255],
+            index: [[0; 4]; 64],
+        }
+    }
+
+    fn byte(&mut self) -> Option<u8> {
+        let byte = *self.data.get(self.pos)?;
+        self.pos += 1;
+        Some(byte)
+    }
+
+    // Decodes the next pixel into `self.px`.
+    fn decode(&mut self) -> Option<()> {
+        if self.run > 0 {
+            self.run -= 1;
+            return Some(());
+        }
+        let op = self.byte()?;
+        match op {
+            0xfe => {
+                self.px[0] = self.byte()?;
+                self.px[1] = self.byte()?;
+                self.px[2] = self.byte()?;
+            }
+            0xff => {
+                for channel in 0..4 {
+                    self.px[channel] = self.byte()?;
+                }
+            }
+            _ => match op >> 6 {
+                0b00 => self.px = self.index[op as usize],
+                0b01 => {
+                    self.px[0] = self.px[0].wrapping_add((op >> 4) & 0x03).wrapping_sub(2);
+                    self.px[1] = self.px[1].wrapping_add((op >> 2) & 0x03).wrapping_sub(2);
+                    self.px[2] = self.px[2].wrapping_add(op & 0x03).wrapping_sub(2);
+                }
+                0b10 => {
+                    let dg = (op & 0x3f).wrapping_sub(32);
+                    let rb = self.byte()?;
+                    self.px[0] = self.px[0].wrapping_add(dg.wrapping_add(rb >> 4).wrapping_sub(8));
+                    self.px[1] = self.px[1].wrapping_add(dg);
+                    self.px[2] = self.px[2].wrapping_add(dg.wrapping_add(rb & 0x0f).wrapping_sub(8));
+                }
+                _ => self.run = op & 0x3f,
+            },
+        }
+        let [r, g, b, a] = self.px;
+        let hash = (r as usize * 3 + g as usize * 5 + b as usize * 7 + a as usize * 11) % 64;
+        self.index[hash] = self.px;
+        Some(())
+    }
+}
+
+impl Iterator for QoiPixels<'_> {
+    type Item = u16;
+
+    fn next(&mut self) -> Option<u16> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.decode()?;
+        self.remaining -= 1;
+        Some(crate::colors::rgb565(self.px[0], self.px[1], self.px[2]))
+    }
+}
+
+impl<DI, RST, PinE> ST7789<DI, RST>
+where
+    DI: WriteOnlyDataCommand,
+    RST: OutputPin<Error = PinE>,
+{
+    ///
+    /// Draws a QOI image with its top left corner at the given position,
+    /// decoding it while streaming so it never has to be held in RAM
+    /// uncompressed. The size is taken from the image header. The alpha
+    /// channel is ignored, all pixels are drawn opaque.
+    ///
+    /// The data is decoded twice: once to check it is complete, as a
+    /// malformed image can't be stopped halfway through the pixel stream,
+    /// and once to send it. Fails with `DisplayError` for malformed data and
+    /// `OutOfBounds` for images that don't fit u16 coordinates.
+    ///
+    /// # Arguments
+    ///
+    /// * `sx` - x coordinate of the top left corner
+    /// * `sy` - y coordinate of the top left corner
+    /// * `qoi` - the encoded image, including header
+    ///
+    pub fn blit_qoi(&mut self, sx: u16, sy: u16, qoi: &[u8]) -> Result<(), Error<PinE>> {
+        if qoi.len() < HEADER_LEN || &qoi[..4] != b"qoif" {
+            return Err(misuse(Error::DisplayError));
+        }
+        let dimension = |bytes: &[u8]| {
+            let value = u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+            u16::try_from(value).map_err(|_| misuse(Error::OutOfBounds))
+        };
+        let (dx, dy) = (dimension(&qoi[4..8])?, dimension(&qoi[8..12])?);
+        let count = dx as usize * dy as usize;
+        if QoiPixels::new(qoi, count).count() != count {
+            return Err(misuse(Error::DisplayError));
+        }
+        if count == 0 {
+            return Ok(());
+        }
+        let (ex, ey) = end_point(sx, sy, dx, dy)?;
+        self.set_pixels(sx, sy, ex, ey, QoiPixels::new(qoi, count))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::mock::{assert_rejected, initialized};
+    use crate::Error;
+
+    // 3x2: RGB red, a run of one, RGB green, index of red, luma diff from
+    // red, index of green
+    const IMAGE: [u8; 34] = [
+        b'q', b'o', b'i', b'f', 0, 0, 0, 3, 0, 0, 0, 2, 3, 0, //
+        0xfe, 0xff, 0x00, 0x00, 0xc0, 0xfe, 0x00, 0xff, 0x00, 0x32, 0xa8, 0x00, 0x30, //
+        0, 0, 0, 0, 0, 0, 1,
+    ];
+
+    #[test]
+    fn qoi_image_is_decoded_into_its_window() {
+        let mut display = initialized();
+        display.blit_qoi(2, 1, &IMAGE).unwrap();
+        assert_eq!(display.di.window(), (42, 54, 44, 55));
+        assert_eq!(display.di.pixel_data(), [0xf800, 0xf800, 0x07e0, 0xf800, 0xf840, 0x07e0]);
+    }
+
+    #[test]
+    fn truncated_qoi_image_sends_nothing() {
+        let mut display = initialized();
+        assert_rejected(&mut display, Error::DisplayError, |d| d.blit_qoi(0, 0, &IMAGE[..26]));
+    }
+}
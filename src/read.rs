@@ -0,0 +1,563 @@
+//! Register reads for display interfaces that can receive data from the controller.
+use crate::instruction::Instruction::{self, *};
+use crate::{end_point, misuse, Error, PixelFormat, ST7789};
+use display_interface::{DisplayError, WriteOnlyDataCommand};
+use embedded_hal::digital::v2::OutputPin;
+
+///
+/// A display interface that can also read data back after a command.
+///
+pub trait ReadableDataCommand: WriteOnlyDataCommand {
+    ///
+    /// Reads `buf.len()` bytes of data following the last command. The bytes
+    /// are returned as they appear on the bus, including any dummy byte the
+    /// controller sends first.
+    ///
+    fn read_data(&mut self, buf: &mut [u8]) -> Result<(), DisplayError>;
+}
+
+// Maximum number of pixels read back in one go
+const READ_CHUNK: usize = 32;
+// Pixel values written by `self_test`, one of them differs from any original
+const TEST_PATTERNS: [u16; 2] = [0xa5a5, 0x5a5a];
+
+impl<DI, RST, PinE> ST7789<DI, RST>
+where
+    DI: ReadableDataCommand,
+    RST: OutputPin<Error = PinE>,
+{
+    ///
+    /// Reads the display power mode (RDDPM).
+    ///
+    /// Bit 7 is set when the booster is on, bit 4 when sleep mode is off and
+    /// bit 2 when the display is on.
+    ///
+    pub fn read_power_mode(&mut self) -> Result<u8, Error<PinE>> {
+        let mut buf = [0u8; 1];
+        self.read_register(RDDPM, &mut buf)?;
+        Ok(buf[0])
+    }
+
+    ///
+    /// Reads the MADCTL value held by the controller (RDDMADCTL), for
+    /// comparison with the orientation, mirroring and color order set through
+    /// the driver.
+    ///
+    pub fn read_madctl(&mut self) -> Result<u8, Error<PinE>> {
+        let mut buf = [0u8; 1];
+        self.read_register(RDDMADCTL, &mut buf)?;
+        Ok(buf[0])
+    }
+
+    ///
+    /// Reads the display image mode (RDDIM).
+    ///
+    /// Bit 7 reports whether vertical scrolling is on, bit 5 whether display
+    /// inversion is on and bits 2-0 the selected gamma curve. The controller
+    /// has no command to read back the scroll start address (VSCSAD) or the
+    /// scroll area (VSCRDEF), so this bit is all that can be checked; the
+    /// offset last sent is available from `scroll_offset`.
+    ///
+    pub fn read_image_mode(&mut self) -> Result<u8, Error<PinE>> {
+        let mut buf = [0u8; 1];
+        self.read_register(RDDIM, &mut buf)?;
+        Ok(buf[0])
+    }
+
+    ///
+    /// Reads the display brightness value (RDDISBV), 0 to 255, as last
+    /// written with `set_brightness`. Like all register reads the controller
+    /// sends a dummy byte before the value, which is skipped.
+    ///
+    /// The value only controls the brightness output of the controller; on
+    /// boards that dim the backlight with an MCU pin, like the Pico-LCD 1.14",
+    /// it has no visible effect.
+    ///
+    pub fn read_brightness(&mut self) -> Result<u8, Error<PinE>> {
+        let mut buf = [0u8; 1];
+        self.read_register(RDDISBV, &mut buf)?;
+        Ok(buf[0])
+    }
+
+    ///
+    /// Reads the manufacturer ID (RDID1).
+    ///
+    pub fn read_id1(&mut self) -> Result<u8, Error<PinE>> {
+        let mut buf = [0u8; 1];
+        self.read_register(RDID1, &mut buf)?;
+        Ok(buf[0])
+    }
+
+    ///
+    /// Reads the module / driver version ID (RDID2).
+    ///
+    pub fn read_id2(&mut self) -> Result<u8, Error<PinE>> {
+        let mut buf = [0u8; 1];
+        self.read_register(RDID2, &mut buf)?;
+        Ok(buf[0])
+    }
+
+    ///
+    /// Reads the module / driver ID (RDID3).
+    ///
+    pub fn read_id3(&mut self) -> Result<u8, Error<PinE>> {
+        let mut buf = [0u8; 1];
+        self.read_register(RDID3, &mut buf)?;
+        Ok(buf[0])
+    }
+
+    ///
+    /// Reads the result of the controller's self-diagnostic (RDDSDR).
+    ///
+    /// Bit 7 (register loading) and bit 6 (functionality) are flipped by the
+    /// controller each time the corresponding check passes after sleep out,
+    /// so an unchanged value across a sleep out means the check failed and
+    /// the controller should be re-initialized. The other bits are unused.
+    ///
+    pub fn read_self_diagnostic(&mut self) -> Result<u8, Error<PinE>> {
+        let mut buf = [0u8; 1];
+        self.read_register(RDDSDR, &mut buf)?;
+        Ok(buf[0])
+    }
+
+    ///
+    /// Reads the gate line the controller is currently scanning (GSCAN).
+    ///
+    /// This is the closest the ST7789 gets to reporting its addressing state:
+    /// it has no commands to read back the column and row address window
+    /// (CASET / RASET) or the RAM write pointer. To check where a windowed
+    /// write went, read the pixels back or trace the commands sent instead.
+    ///
+    pub fn read_scanline(&mut self) -> Result<u16, Error<PinE>> {
+        let mut buf = [0u8; 2];
+        self.read_register(GSCAN, &mut buf)?;
+        Ok(u16::from_be_bytes(buf) & 0x03ff)
+    }
+
+    ///
+    /// Inverts the colors of a rectangle by reading it back, inverting every
+    /// pixel and writing it again. This is a read-modify-write over the
+    /// interface in chunks of up to 32 pixels, so it is much slower than
+    /// just drawing the region. The colors read back already went through
+    /// the white balance, so it isn't applied again.
+    ///
+    /// # Arguments
+    ///
+    /// * `x` - x coordinate start
+    /// * `y` - y coordinate start
+    /// * `w` - width
+    /// * `h` - height
+    ///
+    pub fn invert_region(&mut self, x: u16, y: u16, w: u16, h: u16) -> Result<(), Error<PinE>> {
+        if w == 0 || h == 0 {
+            return Ok(());
+        }
+        let (ex, ey) = end_point(x, y, w, h)?;
+        let mut pixels = [0u16; READ_CHUNK];
+        for row in y..=ey {
+            let mut cx = x;
+            loop {
+                let len = (ex - cx + 1).min(READ_CHUNK as u16);
+                let cex = cx + len - 1;
+                let chunk = &mut pixels[..len as usize];
+                self.read_pixels(cx, row, cex, row, chunk)?;
+                self.write_readback(cx, row, cex, row, chunk.iter().map(|c| !c))?;
+                if cex == ex {
+                    break;
+                }
+                cx = cex + 1;
+            }
+        }
+        Ok(())
+    }
+
+    ///
+    /// Reads the color of a single pixel back from display RAM as Rgb565.
+    ///
+    /// # Arguments
+    ///
+    /// * `x` - x coordinate
+    /// * `y` - y coordinate
+    ///
+    pub fn read_pixel(&mut self, x: u16, y: u16) -> Result<u16, Error<PinE>> {
+        let mut pixel = [0u16; 1];
+        self.read_pixels(x, y, x, y, &mut pixel)?;
+        Ok(pixel[0])
+    }
+
+    ///
+    /// Checks that the panel still responds, e.g. as a periodic health
+    /// check: a test value is written to the top left pixel and read back,
+    /// then the pixel's original color is restored. Returns whether the read
+    /// value matched, `false` means the controller is wedged or not
+    /// connected, while errors of the interface itself are returned as such.
+    ///
+    /// The pixel is written as raw data, bypassing the white balance, and
+    /// compared after the 18 bit readback is converted to Rgb565, which only
+    /// round-trips exactly in the Rgb565 pixel format; in other formats this
+    /// fails with `DisplayError`.
+    ///
+    pub fn self_test(&mut self) -> Result<bool, Error<PinE>> {
+        if self.pixel_format != PixelFormat::Rgb565 || self.size_x == 0 || self.size_y == 0 {
+            return Err(misuse(Error::DisplayError));
+        }
+        let original = self.read_pixel(0, 0)?;
+        let pattern = if original == TEST_PATTERNS[0] { TEST_PATTERNS[1] } else { TEST_PATTERNS[0] };
+        self.blit_pixels(0, 0, 1, 1, &self.color_bytes(pattern))?;
+        let matched = self.read_pixel(0, 0)? == pattern;
+        self.blit_pixels(0, 0, 1, 1, &self.color_bytes(original))?;
+        Ok(matched)
+    }
+
+    ///
+    /// Reads a rectangle of display RAM into `buf`, e.g. for screenshots or
+    /// self tests. The controller returns 18 bits (3 bytes) per pixel
+    /// whatever COLMOD is set to; this is converted back to Rgb565, so `buf`
+    /// receives 2 bytes per pixel, row by row, high byte first, and can be
+    /// passed to `blit_pixels` as it is (in the default Rgb565 format). The
+    /// region is read in chunks of up to 32 pixels.
+    ///
+    /// `buf` must hold exactly `w * h * 2` bytes.
+    ///
+    /// # Arguments
+    ///
+    /// * `x` - x coordinate start
+    /// * `y` - y coordinate start
+    /// * `w` - width
+    /// * `h` - height
+    /// * `buf` - receives the pixels as big endian Rgb565
+    ///
+    pub fn read_region(&mut self, x: u16, y: u16, w: u16, h: u16, buf: &mut [u8]) -> Result<(), Error<PinE>> {
+        if buf.len() != usize::from(w) * usize::from(h) * 2 {
+            return Err(misuse(Error::DisplayError));
+        }
+        let mut out = buf.chunks_exact_mut(2);
+        self.read_region_raw(x, y, w, h, |raw| {
+            // the raw data goes first, so zip doesn't take an extra output slot
+            for (rgb, bytes) in raw.chunks_exact(3).zip(out.by_ref()) {
+                bytes.copy_from_slice(&read_convert(rgb).to_be_bytes());
+            }
+        })
+    }
+
+    ///
+    /// Same as `read_region`, but returns the pixels in the controller's 18
+    /// bit read format without converting them: 3 bytes per pixel in R, G, B
+    /// order, each channel's 6 bits left aligned. `buf` must hold exactly
+    /// `w * h * 3` bytes.
+    ///
+    /// # Arguments
+    ///
+    /// * `x` - x coordinate start
+    /// * `y` - y coordinate start
+    /// * `w` - width
+    /// * `h` - height
+    /// * `buf` - receives the pixels as Rgb666
+    ///
+    pub fn read_region_rgb666(&mut self, x: u16, y: u16, w: u16, h: u16, buf: &mut [u8]) -> Result<(), Error<PinE>> {
+        if buf.len() != usize::from(w) * usize::from(h) * 3 {
+            return Err(misuse(Error::DisplayError));
+        }
+        let mut filled = 0;
+        self.read_region_raw(x, y, w, h, |raw| {
+            buf[filled..][..raw.len()].copy_from_slice(raw);
+            filled += raw.len();
+        })
+    }
+
+    ///
+    /// Copies a rectangle of the visible area to another position by reading
+    /// it back and writing it again, e.g. to move a sprite without redrawing
+    /// it. Source and destination may overlap: rows and chunks are copied in
+    /// the order that reads every pixel before it is overwritten. Like
+    /// `invert_region` this goes over the interface in chunks of up to 32
+    /// pixels, colors pass through the 18 bit readback format and are
+    /// written without applying the white balance again.
+    ///
+    /// # Arguments
+    ///
+    /// * `sx` - x coordinate start of the source
+    /// * `sy` - y coordinate start of the source
+    /// * `w` - width
+    /// * `h` - height
+    /// * `dx` - x coordinate start of the destination
+    /// * `dy` - y coordinate start of the destination
+    ///
+    pub fn copy_region(&mut self, sx: u16, sy: u16, w: u16, h: u16, dx: u16, dy: u16) -> Result<(), Error<PinE>> {
+        if w == 0 || h == 0 {
+            return Ok(());
+        }
+        let (sex, sey) = end_point(sx, sy, w, h)?;
+        let (dex, dey) = end_point(dx, dy, w, h)?;
+        if sex.max(dex) >= self.size_x || sey.max(dey) >= self.size_y {
+            return Err(misuse(Error::OutOfBounds));
+        }
+        let mut pixels = [0u16; READ_CHUNK];
+        let chunks = usize::from(w).div_ceil(READ_CHUNK);
+        for row in 0..h {
+            // when moving down, start at the bottom (and when moving right
+            // within the same rows, at the right) so no source pixel is
+            // overwritten before it has been read
+            let row = if dy > sy { h - 1 - row } else { row };
+            for chunk in 0..chunks {
+                let chunk = if dy == sy && dx > sx { chunks - 1 - chunk } else { chunk };
+                let off = (chunk * READ_CHUNK) as u16;
+                let len = (w - off).min(READ_CHUNK as u16);
+                let colors = &mut pixels[..usize::from(len)];
+                self.read_pixels(sx + off, sy + row, sx + off + len - 1, sy + row, colors)?;
+                self.write_readback(dx + off, dy + row, dx + off + len - 1, dy + row, colors.iter().copied())?;
+            }
+        }
+        Ok(())
+    }
+
+    // Writes pixels read back from RAM like `set_pixels`, but without the
+    // white balance, which they went through when they were first drawn.
+    fn write_readback(
+        &mut self,
+        sx: u16,
+        sy: u16,
+        ex: u16,
+        ey: u16,
+        colors: impl Iterator<Item = u16>,
+    ) -> Result<(), Error<PinE>> {
+        let balance = self.white_balance.take();
+        let result = self.set_pixels(sx, sy, ex, ey, colors);
+        self.white_balance = balance;
+        result
+    }
+
+    // Reads a rectangle row by row in chunks of up to READ_CHUNK pixels,
+    // passing the raw Rgb666 data of every chunk to `sink` in order.
+    fn read_region_raw(
+        &mut self,
+        x: u16,
+        y: u16,
+        w: u16,
+        h: u16,
+        mut sink: impl FnMut(&[u8]),
+    ) -> Result<(), Error<PinE>> {
+        if w == 0 || h == 0 {
+            return Ok(());
+        }
+        let (ex, ey) = end_point(x, y, w, h)?;
+        let mut raw = [0u8; 1 + READ_CHUNK * 3];
+        for row in y..=ey {
+            let mut cx = x;
+            loop {
+                let len = (ex - cx + 1).min(READ_CHUNK as u16);
+                let cex = cx + len - 1;
+                sink(self.read_raw(cx, row, cex, row, &mut raw, len.into())?);
+                if cex == ex {
+                    break;
+                }
+                cx = cex + 1;
+            }
+        }
+        Ok(())
+    }
+
+    // Reads up to READ_CHUNK pixels of the given window as Rgb565.
+    fn read_pixels(
+        &mut self,
+        sx: u16,
+        sy: u16,
+        ex: u16,
+        ey: u16,
+        pixels: &mut [u16],
+    ) -> Result<(), Error<PinE>> {
+        let mut raw = [0u8; 1 + READ_CHUNK * 3];
+        let raw = self.read_raw(sx, sy, ex, ey, &mut raw, pixels.len())?;
+        for (pixel, rgb) in pixels.iter_mut().zip(raw.chunks_exact(3)) {
+            *pixel = read_convert(rgb);
+        }
+        Ok(())
+    }
+
+    // Reads `count` pixels of the given window in the controller's read
+    // format and returns them without the leading dummy byte.
+    fn read_raw<'b>(
+        &mut self,
+        sx: u16,
+        sy: u16,
+        ex: u16,
+        ey: u16,
+        raw: &'b mut [u8; 1 + READ_CHUNK * 3],
+        count: usize,
+    ) -> Result<&'b [u8], Error<PinE>> {
+        // RAMRD sends a dummy byte, then 3 bytes (18bpp) per pixel regardless of COLMOD
+        let raw = &mut raw[..1 + count * 3];
+        self.set_address_window(sx, sy, ex, ey)?;
+        self.write_command(RAMRD)?;
+        self.di.read_data(raw).map_err(|_| Error::DisplayError)?;
+        Ok(&raw[1..])
+    }
+
+    // Issues a read command and fills `buf` with the returned parameters,
+    // skipping the leading dummy byte.
+    fn read_register(&mut self, command: Instruction, buf: &mut [u8]) -> Result<(), Error<PinE>> {
+        let mut raw = [0u8; 5];
+        let raw = &mut raw[..buf.len() + 1];
+        self.write_command(command)?;
+        self.di.read_data(raw).map_err(|_| Error::DisplayError)?;
+        buf.copy_from_slice(&raw[1..]);
+        Ok(())
+    }
+}
+
+// Converts a pixel read back from RAM to Rgb565. RAMRD always returns 18
+// bits per pixel, R, G and B in one byte each with the 6 bits left aligned,
+// also when writing in 16bpp, so this keeps the top 5, 6 and 5 bits.
+fn read_convert(rgb: &[u8]) -> u16 {
+    (u16::from(rgb[0] >> 3) << 11) | (u16::from(rgb[1] >> 2) << 5) | u16::from(rgb[2] >> 3)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::read_convert;
+    use crate::instruction::Instruction::*;
+    use crate::mock::initialized;
+
+    #[test]
+    fn power_mode_skips_the_dummy_byte() {
+        let mut display = initialized();
+        display.di.reads.extend([0xff, 0x9c]);
+        assert_eq!(display.read_power_mode().unwrap(), 0x9c);
+        assert_eq!(display.di.commands(), [RDDPM as u8]);
+    }
+
+    #[test]
+    fn disjoint_copies_move_the_pixels() {
+        let mut display = initialized();
+        for (i, (x, y)) in [(0u16, 0u16), (1, 0), (2, 0), (0, 1), (1, 1), (2, 1)].iter().enumerate() {
+            display.di.set_pixel(40 + *x, 53 + *y, 0x1000 * (i as u16 + 1) + 0x21);
+        }
+        display.copy_region(0, 0, 3, 2, 10, 20).unwrap();
+        for y in 0..2 {
+            for x in 0..3 {
+                assert_eq!(display.di.at(10 + x, 20 + y), display.di.at(x, y));
+            }
+        }
+        assert_eq!(display.di.at(13, 20), 0);
+    }
+
+    #[test]
+    fn overlapping_copies_read_before_they_write() {
+        let mut display = initialized();
+        // 40 pixels take two chunks, so moving right must write the second first
+        for x in 0..40 {
+            display.di.set_pixel(40 + x, 53, x + 1);
+        }
+        display.copy_region(0, 0, 40, 1, 1, 0).unwrap();
+        let row: std::vec::Vec<u16> = (0..41).map(|x| display.di.at(x, 0)).collect();
+        assert_eq!(row[0], 1);
+        assert!(row[1..].iter().copied().eq(1..=40));
+
+        for y in 0..3 {
+            display.di.set_pixel(40 + 100, 53 + 10 + y, 0x0800 * (y + 1));
+        }
+        display.copy_region(100, 10, 1, 3, 100, 11).unwrap();
+        let column: std::vec::Vec<u16> = (10..14).map(|y| display.di.at(100, y)).collect();
+        assert_eq!(column, [0x0800, 0x0800, 0x1000, 0x1800]);
+    }
+
+    #[test]
+    fn regions_are_read_back_as_rgb565() {
+        let mut display = initialized();
+        let colors = [0xf800, 0x07e0, 0x001f, 0x8410, 0xffff];
+        for (x, &color) in colors.iter().enumerate() {
+            display.di.set_pixel(40 + 30 + x as u16, 53 + 7, color);
+        }
+        // 5 pixels starting at x 30 of a 34 pixel row: in its second chunk
+        let mut buf = [0u8; 34 * 2 * 2];
+        display.read_region(0, 6, 34, 2, &mut buf).unwrap();
+        assert_eq!(display.di.count(RAMRD), 4);
+        assert_eq!(display.di.window(), (72, 60, 73, 60));
+        let row: std::vec::Vec<u16> = buf[34 * 2..].chunks(2).map(|p| u16::from_be_bytes([p[0], p[1]])).collect();
+        assert!(row[..30].iter().all(|&c| c == 0));
+        assert_eq!(row[30..], colors[..4]);
+        assert!(buf[..34 * 2].iter().all(|&b| b == 0));
+    }
+
+    #[test]
+    fn rgb666_reads_keep_the_top_bits_of_every_channel() {
+        assert_eq!(read_convert(&[0xfc, 0x00, 0x00]), 0xf800);
+        assert_eq!(read_convert(&[0x00, 0xfc, 0x00]), 0x07e0);
+        assert_eq!(read_convert(&[0x00, 0x00, 0xfc]), 0x001f);
+        assert_eq!(read_convert(&[0x84, 0x82, 0x84]), 0x8410);
+        // the 6th bit of red and blue and the unused low bits are dropped
+        assert_eq!(read_convert(&[0x07, 0x03, 0x07]), 0x0000);
+    }
+
+    #[test]
+    fn pixels_and_raw_regions_are_read_back() {
+        let mut display = initialized();
+        display.di.set_pixel(40 + 3, 53 + 4, 0x8410);
+        assert_eq!(display.read_pixel(3, 4).unwrap(), 0x8410);
+        assert_eq!(display.di.window(), (43, 57, 43, 57));
+
+        let mut raw = [0u8; 6];
+        display.read_region_rgb666(3, 4, 2, 1, &mut raw).unwrap();
+        assert_eq!(raw, [0x80, 0x80, 0x80, 0, 0, 0]);
+    }
+
+    #[test]
+    fn readback_writes_skip_the_white_balance() {
+        let mut display = initialized();
+        display.set_white_balance(128, 255, 255);
+        display.fill_rect(0, 0, 1, 1, 0xf800).unwrap();
+        let balanced = display.di.at(0, 0);
+        assert_ne!(balanced, 0xf800);
+
+        display.copy_region(0, 0, 1, 1, 5, 0).unwrap();
+        assert_eq!(display.di.at(5, 0), balanced);
+        display.invert_region(5, 0, 1, 1).unwrap();
+        assert_eq!(display.di.at(5, 0), !balanced);
+    }
+
+    #[test]
+    fn self_test_restores_the_pixel_of_a_responding_panel() {
+        let mut display = initialized();
+        display.di.set_pixel(40, 53, 0x1234);
+        assert!(display.self_test().unwrap());
+        assert_eq!(display.di.at(0, 0), 0x1234);
+        assert_eq!(display.di.count(RAMRD), 2);
+        assert_eq!(display.di.count(RAMWR), 2);
+
+        // a pixel already holding the first pattern is tested with the second
+        display.di.set_pixel(40, 53, 0xa5a5);
+        assert!(display.self_test().unwrap());
+        assert_eq!(display.di.at(0, 0), 0xa5a5);
+    }
+
+    #[test]
+    fn self_test_fails_on_a_panel_that_doesnt_respond() {
+        use crate::mock::MockPin;
+        use crate::{ReadableDataCommand, ST7789};
+        use display_interface::{DataFormat, DisplayError, WriteOnlyDataCommand};
+
+        // accepts every write and reads back nothing but zeros
+        struct Wedged;
+
+        impl WriteOnlyDataCommand for Wedged {
+            fn send_commands(&mut self, _: DataFormat<'_>) -> Result<(), DisplayError> {
+                Ok(())
+            }
+
+            fn send_data(&mut self, _: DataFormat<'_>) -> Result<(), DisplayError> {
+                Ok(())
+            }
+        }
+
+        impl ReadableDataCommand for Wedged {
+            fn read_data(&mut self, buf: &mut [u8]) -> Result<(), DisplayError> {
+                buf.fill(0);
+                Ok(())
+            }
+        }
+
+        let mut display = ST7789::new(Wedged, MockPin::default(), 240, 135, 40, 53);
+        display.init_commands().unwrap();
+        assert!(!display.self_test().unwrap());
+    }
+}
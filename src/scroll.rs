@@ -0,0 +1,354 @@
+//! Vertical scroll area and wrapped addressing for scrolling consoles.
+use crate::instruction::Instruction::{RAMWR, VSCRDEF};
+use crate::{end_point, misuse, Error, ST7789, RAM_HEIGHT};
+use display_interface::WriteOnlyDataCommand;
+use embedded_hal::digital::v2::OutputPin;
+
+impl<DI, RST, PinE> ST7789<DI, RST>
+where
+    DI: WriteOnlyDataCommand,
+    RST: OutputPin<Error = PinE>,
+{
+    ///
+    /// Defines the vertical scroll area (VSCRDEF): `top_fixed` controller RAM
+    /// rows at the top and `bottom_fixed` at the bottom stay in place, the 320
+    /// rows of RAM in between scroll with `set_scroll_offset`. The reset
+    /// default scrolls all rows.
+    ///
+    /// # Arguments
+    ///
+    /// * `top_fixed` - number of fixed rows at the top
+    /// * `bottom_fixed` - number of fixed rows at the bottom
+    ///
+    pub fn set_scroll_area(&mut self, top_fixed: u16, bottom_fixed: u16) -> Result<(), Error<PinE>> {
+        let scrolling = RAM_HEIGHT
+            .checked_sub(top_fixed)
+            .and_then(|rows| rows.checked_sub(bottom_fixed))
+            .filter(|&rows| rows > 0)
+            .ok_or_else(|| misuse(Error::OutOfBounds))?;
+        self.write_command(VSCRDEF)?;
+        self.write_data(&top_fixed.to_be_bytes())?;
+        self.write_data(&scrolling.to_be_bytes())?;
+        self.write_data(&bottom_fixed.to_be_bytes())?;
+        self.scroll_area = (top_fixed, scrolling);
+        Ok(())
+    }
+
+    ///
+    /// Enables wrapped y coordinates for `set_pixel` and `fill_rect` (and the
+    /// primitives built on it): y coordinates from the start of the scroll
+    /// area on wrap around modulo its height, reaching the rows of RAM that
+    /// are not visible right now. A scrolling console can so keep writing
+    /// its next line below the last one and scroll it into view.
+    ///
+    /// The scroll area is given in controller rows, so y coordinates are
+    /// wrapped after adding the y offset of the visible area; this only works
+    /// in portrait orientations, where y runs along the rows. In landscape
+    /// drawing fails with `OutOfBounds` while wrapping is enabled.
+    /// The bottom fixed area can't be drawn to while wrapping is enabled.
+    ///
+    /// # Arguments
+    ///
+    /// * `wrap` - whether y coordinates wrap around in the scroll area
+    ///
+    pub fn set_scroll_wrap(&mut self, wrap: bool) {
+        self.scroll_wrap = wrap;
+    }
+
+    ///
+    /// Scrolls the picture by `dy` rows within the scroll area, wrapping
+    /// around its end, and fills the rows scrolled into view with
+    /// `fill_color`: at the bottom for positive `dy` (content moves up, as
+    /// for a new console line), at the top for negative `dy`. The rows are
+    /// filled before the new offset is sent, so their old content never
+    /// shows when the scroll area is taller than the visible area.
+    ///
+    /// Like `set_scroll_wrap` this only works in portrait orientations, where
+    /// y runs along the controller rows; in landscape it fails with
+    /// `OutOfBounds`. Visible rows in the fixed areas don't move and are not
+    /// filled.
+    ///
+    /// # Arguments
+    ///
+    /// * `dy` - number of rows to scroll by
+    /// * `fill_color` - the Rgb565 color for the exposed rows
+    ///
+    pub fn scroll_by(&mut self, dy: i16, fill_color: u16) -> Result<(), Error<PinE>> {
+        if self.madctl() & 0x20 != 0 {
+            return Err(misuse(Error::OutOfBounds));
+        }
+        let (top, height) = (i32::from(self.scroll_area.0), i32::from(self.scroll_area.1));
+        let start = (i32::from(self.scroll_offset) - top).rem_euclid(height);
+        let scrolled = (start + i32::from(dy)).rem_euclid(height);
+        let (off_x, off_y) = self.window_offsets();
+        let (size_x, size_y) = (self.size_x, i32::from(self.size_y));
+        let exposed = if size_x > 0 { i32::from(dy).abs().min(size_y) } else { 0 };
+        let (mut y, end) = if dy > 0 { (size_y - exposed, size_y) } else { (0, exposed) };
+        while y < end {
+            let line = i32::from(off_y) + y;
+            if line < top || line >= top + height {
+                y += 1; // fixed area
+                continue;
+            }
+            // RAM row shown on this line once scrolled, and how many of the
+            // following rows are contiguous in RAM
+            let row = top + (line - top + scrolled).rem_euclid(height);
+            let rows = (end - y).min(top + height - row).min(top + height - line);
+            self.write_ram_window(off_x, row as u16, off_x + size_x - 1, (row + rows - 1) as u16)?;
+            self.write_command(RAMWR)?;
+            self.write_solid(fill_color, usize::from(size_x) * rows as usize)?;
+            y += rows;
+        }
+        self.set_scroll_offset((top + scrolled) as u16)
+    }
+
+    ///
+    /// Draws a pane scrolled in software, for when more than the one hardware
+    /// scroll area is needed. The pane's content is a ring buffer of
+    /// `buffer.len() / (dx * bytes_per_pixel)` rows of raw pixel data, each
+    /// `dx` pixels wide; `dy` rows of it are shown starting at row `offset`,
+    /// wrapping around to the first row. Independent panes are drawn with one
+    /// call each, so each can scroll by its own offset.
+    ///
+    /// At most two windowed writes are sent, one before and one after the
+    /// wrap. Fails with `DisplayError` if the buffer holds fewer than `dy`
+    /// rows or isn't made of whole rows.
+    ///
+    /// # Arguments
+    ///
+    /// * `sx` - x coordinate start of the pane
+    /// * `sy` - y coordinate start of the pane
+    /// * `dx` - width of the pane and of the buffer rows
+    /// * `dy` - height of the pane
+    /// * `buffer` - the pane's rows, raw pixel data
+    /// * `offset` - buffer row shown at the top of the pane
+    ///
+    pub fn blit_scrolled(
+        &mut self,
+        sx: u16,
+        sy: u16,
+        dx: u16,
+        dy: u16,
+        buffer: &[u8],
+        offset: u16,
+    ) -> Result<(), Error<PinE>> {
+        if dx == 0 || dy == 0 {
+            return Ok(());
+        }
+        let row_len = self
+            .pixel_format
+            .byte_offset(dx.into())
+            .ok_or_else(|| misuse(Error::DisplayError))?;
+        let rows = buffer.len() / row_len;
+        if !buffer.len().is_multiple_of(row_len) || rows < dy as usize {
+            return Err(misuse(Error::DisplayError));
+        }
+        end_point(sx, sy, dx, dy)?;
+        let first = offset as usize % rows;
+        let upper = (rows - first).min(dy as usize);
+        let lower = dy as usize - upper;
+        self.blit_pixels(sx, sy, dx, upper as u16, &buffer[first * row_len..][..upper * row_len])?;
+        if lower > 0 {
+            self.blit_pixels(sx, sy + upper as u16, dx, lower as u16, &buffer[..lower * row_len])?;
+        }
+        Ok(())
+    }
+
+    // Scroll area as (first RAM row, height) if wrapping is enabled. Fails in
+    // landscape, where the RAM rows don't run along y.
+    pub(crate) fn wrap_region(&self) -> Result<Option<(u16, u16)>, Error<PinE>> {
+        if !self.scroll_wrap {
+            return Ok(None);
+        }
+        if self.madctl() & 0x20 != 0 {
+            return Err(misuse(Error::OutOfBounds));
+        }
+        Ok(Some(self.scroll_area))
+    }
+
+    // Fills a rectangle with wrapped y coordinates, splitting it where it
+    // crosses the end of the scroll area. Wrapping is done on RAM rows, i.e.
+    // after adding the y offset, like `scroll_by` does. `x` and `w` are
+    // already clipped.
+    pub(crate) fn fill_rect_wrapped(
+        &mut self,
+        x: u16,
+        y: u16,
+        w: u16,
+        h: u16,
+        color: u16,
+        (top, height): (u16, u16),
+    ) -> Result<(), Error<PinE>> {
+        let (off_x, off_y) = self.window_offsets();
+        let (top, height) = (u32::from(top), u32::from(height));
+        let mut line = u32::from(off_y) + u32::from(y);
+        let mut left = u32::from(h);
+        if line < top {
+            // rows above the scroll area are drawn as they are
+            let above = left.min(top - line);
+            self.fill_ram_rows(off_x + x, w, line, above, color)?;
+            left -= above;
+            line = top;
+        }
+        let mut row = top + (line - top) % height;
+        // rows beyond one full round would only overwrite themselves
+        left = left.min(height);
+        while left > 0 {
+            let rows = left.min(top + height - row);
+            self.fill_ram_rows(off_x + x, w, row, rows, color)?;
+            left -= rows;
+            row = top;
+        }
+        Ok(())
+    }
+
+    // Fills `rows` RAM rows from `row` on, `w` columns wide from column `col`.
+    fn fill_ram_rows(&mut self, col: u16, w: u16, row: u32, rows: u32, color: u16) -> Result<(), Error<PinE>> {
+        if rows == 0 {
+            return Ok(());
+        }
+        self.write_ram_window(col, row as u16, col + w - 1, (row + rows - 1) as u16)?;
+        self.write_command(RAMWR)?;
+        self.write_solid(color, w as usize * rows as usize)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::instruction::Instruction::*;
+    use crate::mock::{assert_rejected, initialized};
+    use crate::{Error, Orientation};
+
+    #[test]
+    fn wrapped_pixels_wrap_around_the_ram_rows() {
+        // portrait: visible rows 40 - 279 of the 320 scrolling RAM rows
+        let mut display = initialized();
+        display.set_orientation(Orientation::Portrait).unwrap();
+        display.set_scroll_wrap(true);
+        display.set_pixel(1, 279, 1).unwrap();
+        display.set_pixel(1, 280, 2).unwrap();
+        display.set_pixel(1, 601, 3).unwrap();
+        assert_eq!(display.di.pixel(53, 319), 1);
+        assert_eq!(display.di.pixel(53, 0), 2);
+        assert_eq!(display.di.pixel(53, 1), 3);
+        assert_eq!(display.di.window(), (53, 1, 53, 1));
+        // x is still clipped
+        display.set_pixel(135, 0, 4).unwrap();
+        assert_eq!(display.di.pixel_data(), [1, 2, 3]);
+    }
+
+    #[test]
+    fn wrapped_fills_split_at_the_end_of_the_scroll_area() {
+        let mut display = initialized();
+        display.set_orientation(Orientation::Portrait).unwrap();
+        display.set_scroll_area(50, 20).unwrap();
+        display.set_scroll_wrap(true);
+        display.di.clear();
+        // rows 48 and 49 are fixed, 50 - 299 scroll
+        display.fill_rect(0, 8, 2, 4, 5).unwrap();
+        assert_eq!(display.di.count(RAMWR), 2);
+        assert_eq!([47, 48, 49, 50, 51, 52].map(|row| display.di.pixel(52, row)), [0, 5, 5, 5, 5, 0]);
+        display.di.clear();
+        display.fill_rect(0, 258, 1, 4, 6).unwrap();
+        assert_eq!(display.di.count(RAMWR), 2);
+        assert_eq!([297, 298, 299, 50, 51, 52].map(|row| display.di.pixel(52, row)), [0, 6, 6, 6, 6, 0]);
+    }
+
+    #[test]
+    fn scrolled_panes_show_the_rows_from_their_offset() {
+        let mut display = initialized();
+        // 2 pixels wide, 4 rows of 0x0r0c
+        let buffer: std::vec::Vec<u8> = (0..4).flat_map(|row| [row, 0, row, 1]).collect();
+        display.blit_scrolled(0, 0, 2, 3, &buffer, 2).unwrap();
+        display.blit_scrolled(4, 0, 2, 3, &buffer, 5).unwrap();
+        assert_eq!(display.di.count(RAMWR), 3);
+        let column = |display: &crate::mock::Display, x| [0, 1, 2].map(|y| display.di.at(x, y) >> 8);
+        assert_eq!(column(&display, 0), [2, 3, 0]);
+        assert_eq!(column(&display, 4), [1, 2, 3]);
+        assert_eq!(display.di.at(5, 0), 0x0101);
+    }
+
+    #[test]
+    fn scrolling_fills_the_exposed_rows() {
+        // portrait: visible rows 40 - 279, columns 52 - 186
+        let mut display = initialized();
+        display.set_orientation(Orientation::Portrait).unwrap();
+        display.scroll_by(10, 0x1234).unwrap();
+        assert_eq!(display.scroll_offset(), 10);
+        assert_eq!(display.di.params(VSCSAD), [0, 10]);
+        // the bottom 10 lines show RAM rows 280 - 289 once scrolled
+        assert_eq!(display.di.window(), (52, 280, 186, 289));
+        assert_eq!((display.di.pixel(52, 279), display.di.pixel(186, 289), display.di.pixel(52, 290)), (0, 0x1234, 0));
+
+        display.scroll_by(-20, 0x4321).unwrap();
+        assert_eq!(display.scroll_offset(), 310);
+        // the top 20 lines show RAM rows 30 - 49
+        assert_eq!(display.di.window(), (52, 30, 186, 49));
+        assert_eq!((display.di.pixel(52, 29), display.di.pixel(186, 49), display.di.pixel(52, 50)), (0, 0x4321, 0));
+    }
+
+    #[test]
+    fn scrolled_fills_split_at_the_end_of_the_ram() {
+        let mut display = initialized();
+        display.set_orientation(Orientation::Portrait).unwrap();
+        display.scroll_by(50, 0x1234).unwrap();
+        assert_eq!(display.scroll_offset(), 50);
+        // lines 190 - 239 show RAM rows 280 - 319 and 0 - 9
+        assert_eq!(display.di.count(RAMWR), 2);
+        assert_eq!(display.di.window(), (52, 0, 186, 9));
+        let filled = |row| display.di.pixel(100, row) == 0x1234;
+        assert!((280..320).chain(0..10).all(filled));
+        assert!(!filled(279) && !filled(10));
+    }
+
+    #[test]
+    fn scroll_offsets_within_the_scroll_area_are_sent() {
+        let mut display = initialized();
+        // without an area all 320 rows scroll
+        display.set_scroll_offset(0).unwrap();
+        display.set_scroll_offset(319).unwrap();
+        assert_eq!(display.di.params(VSCSAD), [0x01, 0x3f]);
+
+        display.set_scroll_area(10, 20).unwrap();
+        display.set_scroll_offset(10).unwrap();
+        display.set_scroll_offset(299).unwrap();
+        assert_eq!(display.scroll_offset(), 299);
+        assert_eq!(display.di.count(VSCSAD), 4);
+    }
+
+    #[test]
+    fn scroll_offsets_past_the_ram_are_rejected() {
+        let mut display = initialized();
+        assert_rejected(&mut display, Error::OutOfBounds, |d| d.set_scroll_offset(320));
+    }
+
+    #[test]
+    fn scroll_offsets_in_the_fixed_areas_are_rejected() {
+        let mut display = initialized();
+        display.set_scroll_area(10, 20).unwrap();
+        display.set_scroll_offset(42).unwrap();
+        assert_rejected(&mut display, Error::OutOfBounds, |d| d.set_scroll_offset(9));
+        assert_rejected(&mut display, Error::OutOfBounds, |d| d.set_scroll_offset(300));
+        assert_eq!(display.scroll_offset(), 42);
+    }
+
+    #[test]
+    fn scrolling_fails_in_landscape() {
+        let mut display = initialized();
+        display.set_orientation(Orientation::LandscapeFlipped).unwrap();
+        display.di.clear();
+        assert_rejected(&mut display, Error::OutOfBounds, |d| d.scroll_by(10, 0x1234));
+        assert_eq!(display.scroll_offset(), 0);
+    }
+
+    #[test]
+    fn wrapped_drawing_fails_in_landscape() {
+        let mut display = initialized();
+        display.set_scroll_wrap(true);
+        assert_rejected(&mut display, Error::OutOfBounds, |d| d.fill_rect(0, 130, 10, 10, 0x1234));
+        assert_rejected(&mut display, Error::OutOfBounds, |d| d.set_pixel(0, 0, 0x1234));
+        display.set_scroll_wrap(false);
+        display.set_pixel(0, 0, 0x1234).unwrap();
+        assert_eq!(display.di.at(0, 0), 0x1234);
+    }
+}
@@ -0,0 +1,189 @@
+//! In-RAM shadow of the pixels written to the display, for reading them back
+//! over write-only interfaces.
+use crate::colors::rgb444_to_rgb565;
+use crate::{misuse, Error, PixelFormat, ST7789};
+use display_interface::WriteOnlyDataCommand;
+use embedded_hal::digital::v2::OutputPin;
+
+// The shadow buffer and the write position within the current window
+pub(crate) struct Shadow {
+    pixels: &'static mut [u16],
+    // Row length of the buffer
+    width: u16,
+    // Current window (sx, sy, ex, ey) in visible coordinates, None if unknown
+    window: Option<(u16, u16, u16, u16)>,
+    // Whether the window is filled column by column
+    columnwise: bool,
+    // Write position within the window
+    x: u16,
+    y: u16,
+}
+
+impl Shadow {
+    // Sets the window of the following pixel writes.
+    pub(crate) fn set_window(&mut self, window: Option<(u16, u16, u16, u16)>, columnwise: bool) {
+        self.window = window;
+        self.columnwise = columnwise;
+        self.restart();
+    }
+
+    // Moves the write position back to the start of the window (RAMWR).
+    pub(crate) fn restart(&mut self) {
+        if let Some((sx, sy, _, _)) = self.window {
+            self.x = sx;
+            self.y = sy;
+        }
+    }
+
+    // Records a written pixel and advances the write position, wrapping
+    // around at the end of the window like the controller does.
+    pub(crate) fn push(&mut self, color: u16) {
+        let (sx, sy, ex, ey) = match self.window {
+            Some(window) => window,
+            None => return,
+        };
+        if self.x < self.width {
+            let index = self.y as usize * self.width as usize + self.x as usize;
+            if let Some(pixel) = self.pixels.get_mut(index) {
+                *pixel = color;
+            }
+        }
+        let (major, major_start, major_end, minor, minor_start, minor_end) = if self.columnwise {
+            (&mut self.y, sy, ey, &mut self.x, sx, ex)
+        } else {
+            (&mut self.x, sx, ex, &mut self.y, sy, ey)
+        };
+        if *major < major_end {
+            *major += 1;
+        } else {
+            *major = major_start;
+            *minor = if *minor < minor_end { *minor + 1 } else { minor_start };
+        }
+    }
+
+    // Records raw pixel data in the given format and byte order.
+    pub(crate) fn push_bytes(&mut self, data: &[u8], format: PixelFormat, little_endian: bool) {
+        if format == PixelFormat::Rgb444 {
+            for pair in data.chunks(3) {
+                self.push(rgb444_to_rgb565(u16::from(pair[0]) << 4 | u16::from(pair.get(1).map_or(0, |b| b >> 4))));
+                if let [_, mid, last] = *pair {
+                    self.push(rgb444_to_rgb565(u16::from(mid & 0x0f) << 8 | u16::from(last)));
+                }
+            }
+            return;
+        }
+        for pixel in data.chunks_exact(format.bytes_per_pixel()) {
+            let color = match *pixel {
+                [lo, hi] if little_endian => u16::from_le_bytes([lo, hi]),
+                [hi, lo] => u16::from_be_bytes([hi, lo]),
+                [r, g, b] => crate::colors::rgb565(r, g, b),
+                _ => 0,
+            };
+            self.push(color);
+        }
+    }
+}
+
+impl<DI, RST, PinE> ST7789<DI, RST>
+where
+    DI: WriteOnlyDataCommand,
+    RST: OutputPin<Error = PinE>,
+{
+    ///
+    /// Makes the driver keep a copy of every pixel it writes in `buffer`, so
+    /// they can be read back with `get_pixel` without a readable interface.
+    /// The buffer holds the visible area row by row and must have room for at
+    /// least `width() * height()` pixels; its previous content is taken as
+    /// the current display content. Colors are recorded as drawn, before the
+    /// correction of `set_white_balance`.
+    ///
+    /// The buffer is a slice rather than a const generic array sized by the
+    /// resolution, as the resolution is only passed to `new` at runtime and
+    /// would otherwise become part of the driver's type. A `static mut` array
+    /// of the right size works just as well.
+    ///
+    /// All drawing methods update the shadow. Pixel data sent with
+    /// `send_raw_data` or into windows set with `set_address_window_raw` is
+    /// not tracked, neither is scrolling. After switching between landscape
+    /// and portrait orientations the content no longer matches the display.
+    ///
+    /// # Arguments
+    ///
+    /// * `buffer` - storage for the shadow, e.g. a `static mut` array
+    ///
+    pub fn attach_shadow(&mut self, buffer: &'static mut [u16]) -> Result<(), Error<PinE>> {
+        if buffer.len() < self.size_x as usize * self.size_y as usize {
+            return Err(misuse(Error::DisplayError));
+        }
+        self.shadow = Some(Shadow {
+            pixels: buffer,
+            width: self.size_x,
+            window: None,
+            columnwise: false,
+            x: 0,
+            y: 0,
+        });
+        Ok(())
+    }
+
+    ///
+    /// Stops tracking written pixels and hands back the shadow buffer.
+    ///
+    pub fn detach_shadow(&mut self) -> Option<&'static mut [u16]> {
+        self.shadow.take().map(|shadow| shadow.pixels)
+    }
+
+    ///
+    /// Returns the Rgb565 color last drawn to a pixel according to the
+    /// shadow, before white balance, or `None` without a shadow or outside
+    /// the visible area.
+    ///
+    /// # Arguments
+    ///
+    /// * `x` - x coordinate
+    /// * `y` - y coordinate
+    ///
+    pub fn get_pixel(&self, x: u16, y: u16) -> Option<u16> {
+        let shadow = self.shadow.as_ref()?;
+        if x >= self.size_x || y >= self.size_y || x >= shadow.width {
+            return None;
+        }
+        shadow
+            .pixels
+            .get(y as usize * shadow.width as usize + x as usize)
+            .copied()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::mock::initialized;
+    use std::vec;
+
+    #[test]
+    fn shadow_follows_fills_and_pixel_writes() {
+        let mut display = initialized();
+        display.attach_shadow(vec![0; 240 * 135].leak()).unwrap();
+        display.fill_rect(10, 20, 30, 3, 0x1234).unwrap();
+        display.set_pixels(11, 21, 12, 22, [1, 2, 3, 4]).unwrap();
+        for (x, y) in [(10, 20), (39, 22), (13, 21), (11, 21), (12, 22)] {
+            assert_eq!(display.get_pixel(x, y), Some(display.di.at(x, y)));
+        }
+        assert_eq!(display.get_pixel(12, 21), Some(2));
+        assert_eq!(display.get_pixel(40, 20), Some(0));
+        assert_eq!(display.get_pixel(240, 0), None);
+    }
+
+    #[test]
+    fn shadow_keeps_colors_before_white_balance() {
+        let mut display = initialized();
+        display.attach_shadow(vec![0; 240 * 135].leak()).unwrap();
+        display.set_white_balance(128, 255, 255);
+        display.fill_rect(0, 0, 2, 1, 0xffff).unwrap();
+        display.set_pixels(0, 1, 1, 1, [0xf800, 0xf800]).unwrap();
+        assert_eq!(display.get_pixel(1, 0), Some(0xffff));
+        assert_eq!(display.get_pixel(1, 1), Some(0xf800));
+        assert_ne!(display.di.at(1, 0), 0xffff);
+        assert_ne!(display.di.at(1, 1), 0xf800);
+    }
+}
@@ -0,0 +1,334 @@
+//! Pixel sources in various formats, drawn through a single entry point.
+use crate::draw::bit_set;
+use crate::instruction::Instruction::RAMWR;
+use crate::{end_point, misuse, Error, ST7789};
+use display_interface::WriteOnlyDataCommand;
+use embedded_hal::digital::v2::OutputPin;
+
+///
+/// A rectangular block of pixels that can be converted to Rgb565 on the fly.
+///
+pub trait PixelSource {
+    /// Number of pixels the source provides
+    fn pixel_count(&self) -> usize;
+
+    /// The pixel at `index` (row by row) as big endian Rgb565 bytes
+    fn pixel_be(&self, index: usize) -> [u8; 2];
+}
+
+/// Rgb565 colors as used by the rest of the driver
+impl PixelSource for &[u16] {
+    fn pixel_count(&self) -> usize {
+        self.len()
+    }
+
+    fn pixel_be(&self, index: usize) -> [u8; 2] {
+        self[index].to_be_bytes()
+    }
+}
+
+///
+/// Raw Rgb565 pixel data, 2 bytes per pixel, high byte first
+///
+#[derive(Clone, Copy, Debug)]
+pub struct Rgb565Bytes<'a>(pub &'a [u8]);
+
+impl PixelSource for Rgb565Bytes<'_> {
+    fn pixel_count(&self) -> usize {
+        self.0.len() / 2
+    }
+
+    fn pixel_be(&self, index: usize) -> [u8; 2] {
+        [self.0[index * 2], self.0[index * 2 + 1]]
+    }
+}
+
+///
+/// Raw Rgb888 pixel data, 3 bytes per pixel in R, G, B order
+///
+#[derive(Clone, Copy, Debug)]
+pub struct Rgb888Bytes<'a>(pub &'a [u8]);
+
+impl PixelSource for Rgb888Bytes<'_> {
+    fn pixel_count(&self) -> usize {
+        self.0.len() / 3
+    }
+
+    fn pixel_be(&self, index: usize) -> [u8; 2] {
+        let rgb = &self.0[index * 3..][..3];
+        crate::colors::rgb565(rgb[0], rgb[1], rgb[2]).to_be_bytes()
+    }
+}
+
+///
+/// A 1 bit per pixel bitmap with the colors for set and cleared bits, laid
+/// out as for `ST7789::draw_bitmap_1bpp`
+///
+#[derive(Clone, Copy, Debug)]
+pub struct Bitmap1bpp<'a> {
+    /// Bitmap data, rows padded to whole bytes, MSB first
+    pub bits: &'a [u8],
+    /// Width of the bitmap in pixels
+    pub width: u16,
+    /// Rgb565 color for set bits
+    pub fg: u16,
+    /// Rgb565 color for cleared bits
+    pub bg: u16,
+}
+
+impl PixelSource for Bitmap1bpp<'_> {
+    fn pixel_count(&self) -> usize {
+        let row_bytes = (self.width as usize).div_ceil(8);
+        if row_bytes == 0 {
+            return 0;
+        }
+        self.bits.len() / row_bytes * self.width as usize
+    }
+
+    fn pixel_be(&self, index: usize) -> [u8; 2] {
+        let width = self.width as usize;
+        let row = &self.bits[(index / width) * width.div_ceil(8)..];
+        let color = if bit_set(row, index % width) { self.fg } else { self.bg };
+        color.to_be_bytes()
+    }
+}
+
+///
+/// 8 bit palette indices, one byte per pixel, with a 256 entry Rgb565 palette
+///
+#[derive(Clone, Copy, Debug)]
+pub struct Indexed8<'a> {
+    /// Palette index of every pixel, row by row
+    pub indices: &'a [u8],
+    /// Rgb565 color for every index
+    pub palette: &'a [u16; 256],
+}
+
+impl PixelSource for Indexed8<'_> {
+    fn pixel_count(&self) -> usize {
+        self.indices.len()
+    }
+
+    fn pixel_be(&self, index: usize) -> [u8; 2] {
+        self.palette[self.indices[index] as usize].to_be_bytes()
+    }
+}
+
+///
+/// 4 bit palette indices, two pixels per byte with the left one in the high
+/// nibble, with a 16 entry Rgb565 palette. Rows are padded to whole bytes.
+///
+#[derive(Clone, Copy, Debug)]
+pub struct Indexed4<'a> {
+    /// Palette indices, row by row
+    pub indices: &'a [u8],
+    /// Width of the image in pixels
+    pub width: u16,
+    /// Rgb565 color for every index
+    pub palette: &'a [u16; 16],
+}
+
+impl PixelSource for Indexed4<'_> {
+    fn pixel_count(&self) -> usize {
+        let row_bytes = (self.width as usize).div_ceil(2);
+        if row_bytes == 0 {
+            return 0;
+        }
+        self.indices.len() / row_bytes * self.width as usize
+    }
+
+    fn pixel_be(&self, index: usize) -> [u8; 2] {
+        let width = self.width as usize;
+        let col = index % width;
+        let byte = self.indices[(index / width) * width.div_ceil(2) + col / 2];
+        let nibble = if col.is_multiple_of(2) { byte >> 4 } else { byte & 0x0f };
+        self.palette[nibble as usize].to_be_bytes()
+    }
+}
+
+impl<DI, RST, PinE> ST7789<DI, RST>
+where
+    DI: WriteOnlyDataCommand,
+    RST: OutputPin<Error = PinE>,
+{
+    ///
+    /// Draws any pixel source into the given rectangle, converting its pixels
+    /// to Rgb565 while streaming. The source must provide exactly `dx * dy` pixels.
+    ///
+    /// # Arguments
+    ///
+    /// * `sx` - x coordinate start
+    /// * `sy` - y coordinate start
+    /// * `dx` - width
+    /// * `dy` - height
+    /// * `source` - the pixels to draw
+    ///
+    pub fn blit_source(
+        &mut self,
+        sx: u16,
+        sy: u16,
+        dx: u16,
+        dy: u16,
+        source: impl PixelSource,
+    ) -> Result<(), Error<PinE>> {
+        let count = dx as usize * dy as usize;
+        if source.pixel_count() != count {
+            return Err(misuse(Error::DisplayError));
+        }
+        if count == 0 {
+            return Ok(());
+        }
+        let (ex, ey) = end_point(sx, sy, dx, dy)?;
+        let colors = (0..count).map(|i| u16::from_be_bytes(source.pixel_be(i)));
+        self.set_pixels(sx, sy, ex, ey, colors)
+    }
+
+    ///
+    /// Draws an image of 8 bit palette indices, looking up every pixel's
+    /// color while streaming. `indices` must hold exactly `dx * dy` pixels.
+    ///
+    /// # Arguments
+    ///
+    /// * `sx` - x coordinate start
+    /// * `sy` - y coordinate start
+    /// * `dx` - width
+    /// * `dy` - height
+    /// * `indices` - palette index of every pixel, row by row
+    /// * `palette` - Rgb565 color for every index
+    ///
+    pub fn blit_indexed8(
+        &mut self,
+        sx: u16,
+        sy: u16,
+        dx: u16,
+        dy: u16,
+        indices: &[u8],
+        palette: &[u16; 256],
+    ) -> Result<(), Error<PinE>> {
+        self.blit_source(sx, sy, dx, dy, Indexed8 { indices, palette })
+    }
+
+    ///
+    /// Draws an image of 4 bit palette indices, see `Indexed4` for the
+    /// layout. `indices` must hold exactly `dy` rows of `dx` pixels.
+    ///
+    /// # Arguments
+    ///
+    /// * `sx` - x coordinate start
+    /// * `sy` - y coordinate start
+    /// * `dx` - width
+    /// * `dy` - height
+    /// * `indices` - palette indices, two pixels per byte, rows padded to whole bytes
+    /// * `palette` - Rgb565 color for every index
+    ///
+    pub fn blit_indexed4(
+        &mut self,
+        sx: u16,
+        sy: u16,
+        dx: u16,
+        dy: u16,
+        indices: &[u8],
+        palette: &[u16; 16],
+    ) -> Result<(), Error<PinE>> {
+        if indices.len() != (dx as usize).div_ceil(2) * dy as usize {
+            return Err(misuse(Error::DisplayError));
+        }
+        self.blit_source(sx, sy, dx, dy, Indexed4 { indices, width: dx, palette })
+    }
+
+    ///
+    /// Draws a run-length encoded Rgb565 image into the given rectangle. The
+    /// runs are expanded while streaming, so the decoded image is never held
+    /// in RAM.
+    ///
+    /// `rle` is a sequence of 3 byte runs: the number of pixels in the run
+    /// (0 - 255, empty runs are skipped) followed by their color as big
+    /// endian Rgb565. The runs must add up to exactly `dx * dy` pixels and may
+    /// cross row boundaries.
+    ///
+    /// # Arguments
+    ///
+    /// * `sx` - x coordinate start
+    /// * `sy` - y coordinate start
+    /// * `dx` - width
+    /// * `dy` - height
+    /// * `rle` - the encoded runs
+    ///
+    pub fn blit_rle(&mut self, sx: u16, sy: u16, dx: u16, dy: u16, rle: &[u8]) -> Result<(), Error<PinE>> {
+        let count = dx as usize * dy as usize;
+        let total: usize = rle.chunks(3).map(|run| run[0] as usize).sum();
+        if !rle.len().is_multiple_of(3) || total != count {
+            return Err(misuse(Error::DisplayError));
+        }
+        if count == 0 {
+            return Ok(());
+        }
+        let (ex, ey) = end_point(sx, sy, dx, dy)?;
+        self.set_address_window(sx, sy, ex, ey)?;
+        self.write_command(RAMWR)?;
+        for run in rle.chunks_exact(3) {
+            self.write_solid(u16::from_be_bytes([run[1], run[2]]), run[0].into())?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::{assert_rejected, initialized};
+
+    #[test]
+    fn sources_convert_to_rgb565() {
+        let colors: &[u16] = &[0x1234, 0xabcd];
+        assert_eq!(colors.pixel_be(1), [0xab, 0xcd]);
+        assert_eq!(Rgb565Bytes(&[0x12, 0x34, 0xab, 0xcd]).pixel_be(1), [0xab, 0xcd]);
+        let rgb = Rgb888Bytes(&[0xff, 0x00, 0x00, 0x00, 0x00, 0xff]);
+        assert_eq!((rgb.pixel_count(), rgb.pixel_be(0), rgb.pixel_be(1)), (2, [0xf8, 0x00], [0x00, 0x1f]));
+        let bitmap = Bitmap1bpp { bits: &[0b1010_0000, 0b0100_0000], width: 3, fg: 1, bg: 2 };
+        assert_eq!(bitmap.pixel_count(), 6);
+        assert_eq!((0..6).map(|i| bitmap.pixel_be(i)[1]).collect::<std::vec::Vec<_>>(), [1, 2, 1, 2, 1, 2]);
+    }
+
+    #[test]
+    fn blit_source_streams_every_format() {
+        let mut display = initialized();
+        display.blit_source(0, 0, 2, 1, &[0x1234u16, 0xabcd][..]).unwrap();
+        display.blit_source(0, 1, 2, 1, Rgb888Bytes(&[0xff, 0x00, 0x00, 0x00, 0x00, 0xff])).unwrap();
+        let bitmap = Bitmap1bpp { bits: &[0b0100_0000], width: 2, fg: 9, bg: 8 };
+        display.blit_source(0, 2, 2, 1, bitmap).unwrap();
+        let rows: std::vec::Vec<_> = (0..3).map(|y| [display.di.at(0, y), display.di.at(1, y)]).collect();
+        assert_eq!(rows, [[0x1234, 0xabcd], [0xf800, 0x001f], [8, 9]]);
+    }
+
+    #[test]
+    fn rle_runs_expand_across_rows() {
+        let mut display = initialized();
+        let rle = [2, 0x12, 0x34, 0, 0xff, 0xff, 4, 0xab, 0xcd];
+        display.blit_rle(0, 0, 3, 2, &rle).unwrap();
+        assert_eq!(display.di.pixel_data(), [0x1234, 0x1234, 0xabcd, 0xabcd, 0xabcd, 0xabcd]);
+        assert_eq!(display.di.window(), (40, 53, 42, 54));
+    }
+
+    #[test]
+    fn rle_runs_must_cover_the_rectangle() {
+        let mut display = initialized();
+        assert_rejected(&mut display, Error::DisplayError, |d| d.blit_rle(0, 0, 3, 2, &[5, 0, 0]));
+    }
+
+    #[test]
+    fn indexed_images_look_up_the_palette() {
+        let mut display = initialized();
+        let mut palette8 = [0u16; 256];
+        palette8[1] = 0x1111;
+        palette8[200] = 0xc8c8;
+        display.blit_indexed8(0, 0, 3, 1, &[200, 0, 1], &palette8).unwrap();
+        assert_eq!(display.di.pixel_data(), [0xc8c8, 0, 0x1111]);
+
+        // 3 pixels per row take 2 bytes, the last nibble is padding
+        display.di.clear();
+        let palette4: [u16; 16] = core::array::from_fn(|i| i as u16 * 0x100);
+        display.blit_indexed4(0, 1, 3, 2, &[0x12, 0x3f, 0x45, 0x6f], &palette4).unwrap();
+        assert_eq!(display.di.pixel_data(), [0x100, 0x200, 0x300, 0x400, 0x500, 0x600]);
+    }
+}
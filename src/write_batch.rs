@@ -0,0 +1,113 @@
+//! Collecting several windowed writes and sending them in one go.
+use crate::{Error, ST7789};
+use display_interface::WriteOnlyDataCommand;
+use embedded_hal::digital::v2::OutputPin;
+
+// A pending write: x, y, width, height and the raw pixel data
+type Window<'a> = (u16, u16, u16, u16, &'a [u8]);
+
+///
+/// Up to `N` raw pixel writes to separate windows, sent in order by `flush`.
+/// Every window still gets its own address window and RAMWR, but the list
+/// can be built up without holding on to the display.
+///
+pub struct WriteBatch<'a, const N: usize> {
+    windows: [Window<'a>; N],
+    len: usize,
+}
+
+impl<'a, const N: usize> WriteBatch<'a, N> {
+    ///
+    /// Creates an empty batch.
+    ///
+    pub fn new() -> Self {
+        Self {
+            windows: [(0, 0, 0, 0, &[]); N],
+            len: 0,
+        }
+    }
+
+    ///
+    /// Adds a write of raw pixel data to a window, as for `ST7789::blit_pixels`.
+    /// Hands the data back if the batch is full.
+    ///
+    /// # Arguments
+    ///
+    /// * `sx` - x coordinate start
+    /// * `sy` - y coordinate start
+    /// * `dx` - width
+    /// * `dy` - height
+    /// * `data` - u8 slice containing raw pixel data
+    ///
+    pub fn push(&mut self, sx: u16, sy: u16, dx: u16, dy: u16, data: &'a [u8]) -> Result<(), &'a [u8]> {
+        if self.len == N {
+            return Err(data);
+        }
+        self.windows[self.len] = (sx, sy, dx, dy, data);
+        self.len += 1;
+        Ok(())
+    }
+
+    /// Number of pending writes
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether there are no pending writes
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Drops all pending writes
+    pub fn clear(&mut self) {
+        self.len = 0;
+    }
+
+    ///
+    /// Sends all pending writes in the order they were added and empties the
+    /// batch. Stops at the first failing write; the batch is emptied either way.
+    ///
+    /// # Arguments
+    ///
+    /// * `display` - the display to write to
+    ///
+    pub fn flush<DI, RST, PinE>(&mut self, display: &mut ST7789<DI, RST>) -> Result<(), Error<PinE>>
+    where
+        DI: WriteOnlyDataCommand,
+        RST: OutputPin<Error = PinE>,
+    {
+        let len = self.len;
+        self.len = 0;
+        for &(sx, sy, dx, dy, data) in &self.windows[..len] {
+            display.blit_pixels(sx, sy, dx, dy, data)?;
+        }
+        Ok(())
+    }
+}
+
+impl<const N: usize> Default for WriteBatch<'_, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instruction::Instruction::*;
+    use crate::mock::initialized;
+
+    #[test]
+    fn flush_sends_every_window_in_order() {
+        let mut display = initialized();
+        let mut batch = WriteBatch::<2>::new();
+        batch.push(0, 0, 1, 1, &[0x12, 0x34]).unwrap();
+        batch.push(10, 5, 2, 1, &[0, 1, 0, 2]).unwrap();
+        assert_eq!(batch.push(0, 0, 1, 1, &[0, 0]), Err(&[0u8, 0][..]));
+        batch.flush(&mut display).unwrap();
+        assert!(batch.is_empty());
+        assert_eq!(display.di.count(RAMWR), 2);
+        assert_eq!(display.di.window(), (50, 58, 51, 58));
+        assert_eq!([display.di.at(0, 0), display.di.at(10, 5), display.di.at(11, 5)], [0x1234, 1, 2]);
+    }
+}
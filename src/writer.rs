@@ -0,0 +1,135 @@
+//! Streaming pixel data into an open window.
+use crate::instruction::Instruction::{NOP, RAMWR};
+use crate::{end_point, Error, ST7789};
+use display_interface::WriteOnlyDataCommand;
+use embedded_hal::digital::v2::OutputPin;
+
+///
+/// A window opened for writing by `ST7789::open_window`. Pixels written
+/// through it fill the window row by row, continuing where the previous
+/// write stopped, in whatever form is at hand. The memory write is ended with
+/// a NOP when the writer is dropped; errors while doing so are ignored, call
+/// `close` to see them.
+///
+pub struct RamWriter<'a, DI, RST>
+where
+    DI: WriteOnlyDataCommand,
+    RST: OutputPin,
+{
+    display: &'a mut ST7789<DI, RST>,
+}
+
+impl<DI, RST, PinE> RamWriter<'_, DI, RST>
+where
+    DI: WriteOnlyDataCommand,
+    RST: OutputPin<Error = PinE>,
+{
+    ///
+    /// Writes Rgb565 colors.
+    ///
+    /// # Arguments
+    ///
+    /// * `colors` - anything that can provide `IntoIterator<Item = u16>` to iterate over pixel data
+    ///
+    pub fn write_colors<T>(&mut self, colors: T) -> Result<(), Error<PinE>>
+    where
+        T: IntoIterator<Item = u16>,
+    {
+        self.display.write_pixels(colors)
+    }
+
+    ///
+    /// Writes raw pixel data in the display's pixel format, as a contiguous
+    /// (DMA friendly) transfer.
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - u8 slice containing raw pixel data
+    ///
+    pub fn write_bytes(&mut self, data: &[u8]) -> Result<(), Error<PinE>> {
+        self.display.write_pixel_bytes(data)
+    }
+
+    ///
+    /// Ends the memory write.
+    ///
+    pub fn close(self) -> Result<(), Error<PinE>> {
+        let mut writer = core::mem::ManuallyDrop::new(self);
+        writer.display.write_command(NOP)
+    }
+}
+
+impl<DI, RST> Drop for RamWriter<'_, DI, RST>
+where
+    DI: WriteOnlyDataCommand,
+    RST: OutputPin,
+{
+    fn drop(&mut self) {
+        let _ = self.display.write_command(NOP);
+    }
+}
+
+impl<DI, RST, PinE> ST7789<DI, RST>
+where
+    DI: WriteOnlyDataCommand,
+    RST: OutputPin<Error = PinE>,
+{
+    ///
+    /// Sets the address window and starts a memory write (RAMWR), returning
+    /// a writer to stream the window's pixels through. Writing more pixels
+    /// than the window holds wraps around to its start. An empty window fails
+    /// with `OutOfBounds`.
+    ///
+    /// # Arguments
+    ///
+    /// * `sx` - x coordinate start
+    /// * `sy` - y coordinate start
+    /// * `dx` - width
+    /// * `dy` - height
+    ///
+    pub fn open_window(
+        &mut self,
+        sx: u16,
+        sy: u16,
+        dx: u16,
+        dy: u16,
+    ) -> Result<RamWriter<'_, DI, RST>, Error<PinE>> {
+        let (ex, ey) = end_point(sx, sy, dx, dy)?;
+        self.set_address_window(sx, sy, ex, ey)?;
+        self.write_command(RAMWR)?;
+        Ok(RamWriter { display: self })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::instruction::Instruction::*;
+    use crate::mock::{assert_rejected, initialized};
+    use crate::Error;
+
+    #[test]
+    fn writer_mixes_colors_and_bytes() {
+        let mut display = initialized();
+        let mut writer = display.open_window(1, 1, 2, 2).unwrap();
+        writer.write_colors([1, 2]).unwrap();
+        writer.write_bytes(&[0, 3, 0, 4]).unwrap();
+        writer.close().unwrap();
+        assert_eq!(display.di.commands(), [CASET, RASET, RAMWR, NOP].map(|c| c as u8));
+        assert_eq!(display.di.window(), (41, 54, 42, 55));
+        assert_eq!([(1, 1), (2, 1), (1, 2), (2, 2)].map(|(x, y)| display.di.at(x, y)), [1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn dropping_the_writer_ends_the_write() {
+        let mut display = initialized();
+        display.open_window(0, 0, 1, 1).unwrap().write_colors([5]).unwrap();
+        assert_eq!(display.di.commands().last(), Some(&(NOP as u8)));
+        assert_eq!(display.di.at(0, 0), 5);
+    }
+
+    #[test]
+    fn empty_windows_dont_open() {
+        let mut display = initialized();
+        assert_rejected(&mut display, Error::OutOfBounds, |d| d.open_window(0, 0, 0, 1).map(drop));
+    }
+}